@@ -0,0 +1,22 @@
+//! Sends the built-in counter workload to a local validator and prints the compute
+//! units each transaction consumed.
+//!
+//! ```sh
+//! PROGRAM_ID=<deployed program> cargo run --example counter_spam
+//! ```
+
+use increase_cu_program_size::prelude::*;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = BenchConfig {
+        workload: "counter".to_string(),
+        iterations: Some(10),
+        ..BenchConfig::from_env()?
+    };
+    let report = BenchRunner::new(config).run()?;
+
+    for sample in report.run.samples() {
+        println!("{}: {:?} CU", sample.signature, sample.compute_units);
+    }
+    Ok(())
+}
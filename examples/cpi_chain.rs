@@ -0,0 +1,28 @@
+//! Sends CPI chains of every depth the runtime allows, each level invoking
+//! the benchmark program again, and prints the compute units each chain
+//! consumed, so the fixed cost of one more level shows as the step between
+//! depths.
+//!
+//! ```sh
+//! PROGRAM_ID=<deployed program> cargo run --example cpi_chain
+//! ```
+
+use increase_cu_program_size::{cpi::MAX_CPI_DEPTH, prelude::*, workload::CpiWorkload};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = BenchConfig {
+        workload: "cpi-chain".to_string(),
+        ..BenchConfig::from_env()?
+    };
+    let mut registry = config.workload_registry();
+    registry.register("cpi-chain", || CpiWorkload::new(None, 1));
+    let report = BenchRunner::new(config).with_registry(registry).run()?;
+
+    for sample in report.run.samples() {
+        println!(
+            "Depth {} of {}: {:?} CU",
+            sample.index, MAX_CPI_DEPTH, sample.compute_units
+        );
+    }
+    Ok(())
+}
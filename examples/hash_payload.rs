@@ -0,0 +1,31 @@
+//! Hashes payloads from empty to 1 KiB with the sha256 syscall and prints
+//! the compute units each transaction consumed by payload length.
+//!
+//! ```sh
+//! PROGRAM_ID=<deployed program> cargo run --example hash_payload
+//! ```
+
+use increase_cu_program_size::{
+    prelude::*,
+    workload::{HashSyscall, HashWorkload},
+};
+
+const LENGTHS: [usize; 5] = [0, 32, 128, 512, 1024];
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = BenchConfig {
+        workload: "sha256-payloads".to_string(),
+        ..BenchConfig::from_env()?
+    };
+    let mut registry = config.workload_registry();
+    registry.register("sha256-payloads", || {
+        HashWorkload::new(HashSyscall::Sha256, LENGTHS.to_vec())
+    });
+    let report = BenchRunner::new(config).with_registry(registry).run()?;
+
+    for sample in report.run.samples() {
+        let length = LENGTHS[sample.index as usize];
+        println!("{} bytes: {:?} CU", length, sample.compute_units);
+    }
+    Ok(())
+}
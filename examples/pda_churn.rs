@@ -0,0 +1,27 @@
+//! Derives PDAs with `find_program_address` at every point of the built-in
+//! sweep (1 seed up to the limit, each 1, 8 or 32 bytes long) and prints the
+//! compute units each derivation run consumed, next to the instruction naming
+//! its point.
+//!
+//! ```sh
+//! PROGRAM_ID=<deployed program> cargo run --example pda_churn
+//! ```
+
+use increase_cu_program_size::prelude::*;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = BenchConfig {
+        workload: "pda-find".to_string(),
+        ..BenchConfig::from_env()?
+    };
+    let report = BenchRunner::new(config).run()?;
+
+    for sample in report.run.samples() {
+        println!(
+            "{}: {:?} CU",
+            sample.instruction.as_deref().unwrap_or_default(),
+            sample.compute_units
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! Runtime CU accounting rules that changed across validator releases, so runs
+//! taken on different versions explain their differences instead of hiding
+//! them. Currently tracks SIMD-0170, which reserves a small fixed allocation
+//! for builtin instructions instead of the per-instruction default.
+
+use crate::{instruction::COMPUTE_BUDGET_PROGRAM_ID, sdk::RpcClient};
+use solana_program::{instruction::Instruction, pubkey, pubkey::Pubkey};
+use std::{error::Error, fmt};
+
+/// Feature gate of SIMD-0170, "Reserve minimal CUs for builtin instructions".
+pub const RESERVE_MINIMAL_CUS_FOR_BUILTINS: Pubkey =
+    pubkey!("C9oAhLxDBm3ssWtJx1yBGzPY55r2rArHmN1pbQn6HogH");
+
+/// Default limit per instruction when the transaction sets none.
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+/// Allocation per builtin instruction once SIMD-0170 is active.
+pub const MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT: u32 = 3_000;
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Minimum charge of a `sol_mem*` syscall.
+pub const MEM_OP_BASE_COST: u64 = 10;
+/// Bytes per CU charged for memory syscalls and CPI data.
+pub const CPI_BYTES_PER_UNIT: u64 = 250;
+
+/// Programs the runtime treats as builtins when sizing the default limit.
+/// Compute budget instructions are left out; they never count towards it.
+const BUILTINS: [Pubkey; 9] = [
+    pubkey!("11111111111111111111111111111111"),
+    pubkey!("Vote111111111111111111111111111111111111111"),
+    pubkey!("Stake11111111111111111111111111111111111111"),
+    pubkey!("Config1111111111111111111111111111111111111"),
+    pubkey!("BPFLoader1111111111111111111111111111111111"),
+    pubkey!("BPFLoader2111111111111111111111111111111111"),
+    pubkey!("BPFLoaderUpgradeab1e11111111111111111111111"),
+    pubkey!("LoaderV411111111111111111111111111111111111"),
+    pubkey!("AddressLookupTab1e1111111111111111111111111"),
+];
+
+/// CU the runtime charges for one `sol_mem*` syscall over `len` bytes.
+pub fn mem_op_compute_units(len: u64) -> u64 {
+    MEM_OP_BASE_COST.max(len / CPI_BYTES_PER_UNIT)
+}
+
+/// The accounting rules of the cluster a run was measured on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RuntimeAccounting {
+    pub solana_core: Option<String>,
+    pub feature_set: Option<u32>,
+    /// Whether SIMD-0170 was active.
+    pub builtin_reservation: bool,
+}
+
+impl RuntimeAccounting {
+    pub fn detect(rpc_client: &RpcClient) -> Result<Self, Box<dyn Error>> {
+        let version = rpc_client.get_version()?;
+        let builtin_reservation = rpc_client
+            .get_account(&RESERVE_MINIMAL_CUS_FOR_BUILTINS)
+            .map(|feature| feature_is_active(&feature.data))
+            .unwrap_or(false);
+        Ok(Self {
+            solana_core: Some(version.solana_core),
+            feature_set: version.feature_set,
+            builtin_reservation,
+        })
+    }
+
+    /// Limit the runtime applies to `instructions` when they carry no
+    /// `SetComputeUnitLimit`, under these rules.
+    pub fn default_compute_unit_limit(&self, instructions: &[Instruction]) -> u32 {
+        let limit: u32 = instructions
+            .iter()
+            .filter(|instruction| instruction.program_id != COMPUTE_BUDGET_PROGRAM_ID)
+            .map(|instruction| {
+                if self.builtin_reservation && BUILTINS.contains(&instruction.program_id) {
+                    MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT
+                } else {
+                    DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT
+                }
+            })
+            .sum();
+        limit.min(MAX_COMPUTE_UNIT_LIMIT)
+    }
+
+    /// Why CU from a run under `other` may not be comparable with one under
+    /// `self`; empty when the rules match.
+    pub fn differences(&self, other: &RuntimeAccounting) -> Vec<String> {
+        let mut differences = Vec::new();
+        if self.builtin_reservation != other.builtin_reservation {
+            differences.push(format!(
+                "SIMD-0170 builtin CU reservation {} in one run and {} in the other; \
+                 default limits and builtin costs differ",
+                active(self.builtin_reservation),
+                active(other.builtin_reservation)
+            ));
+        }
+        if self.solana_core.is_some()
+            && other.solana_core.is_some()
+            && self.solana_core != other.solana_core
+        {
+            differences.push(format!(
+                "measured on solana-core {} and {}",
+                self.solana_core.as_deref().unwrap_or_default(),
+                other.solana_core.as_deref().unwrap_or_default()
+            ));
+        }
+        differences
+    }
+}
+
+impl fmt::Display for RuntimeAccounting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.solana_core {
+            Some(version) => write!(f, "solana-core {}", version)?,
+            None => write!(f, "unknown version")?,
+        }
+        if let Some(feature_set) = self.feature_set {
+            write!(f, " (feature set {})", feature_set)?;
+        }
+        write!(
+            f,
+            ", SIMD-0170 builtin CU reservation {}",
+            active(self.builtin_reservation)
+        )
+    }
+}
+
+fn active(flag: bool) -> &'static str {
+    if flag {
+        "active"
+    } else {
+        "inactive"
+    }
+}
+
+/// Feature accounts hold a bincode `Option<u64>` activation slot; a pending
+/// feature exists with `None`.
+fn feature_is_active(data: &[u8]) -> bool {
+    data.first() == Some(&1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_default_limit_under_both_rules() {
+        let program = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        let transfer = Instruction::new_with_bytes(BUILTINS[0], &[], vec![]);
+        let budget = Instruction::new_with_bytes(COMPUTE_BUDGET_PROGRAM_ID, &[], vec![]);
+        let instructions = [budget, program, transfer];
+
+        let mut rules = RuntimeAccounting {
+            solana_core: Some("2.0.0".to_string()),
+            feature_set: None,
+            builtin_reservation: false,
+        };
+        assert_eq!(rules.default_compute_unit_limit(&instructions), 400_000);
+
+        let before = rules.clone();
+        rules.builtin_reservation = true;
+        rules.solana_core = Some("2.2.0".to_string());
+        assert_eq!(rules.default_compute_unit_limit(&instructions), 203_000);
+        assert_eq!(before.differences(&rules).len(), 2);
+        assert!(rules.differences(&rules).is_empty());
+
+        assert!(feature_is_active(&[1, 42, 0, 0, 0, 0, 0, 0, 0]));
+        assert!(!feature_is_active(&[0]));
+
+        assert_eq!(mem_op_compute_units(1_000), MEM_OP_BASE_COST);
+        assert_eq!(mem_op_compute_units(8_192), 32);
+    }
+}
@@ -0,0 +1,71 @@
+//! Bump allocator swapped in by the `bump-allocator` feature, to compare with
+//! the SDK default. The default bumps downwards through a fixed 32 KiB and never
+//! frees; this one bumps upwards, so it can use a heap enlarged with
+//! [`request_heap_frame`](crate::instruction::request_heap_frame), and it
+//! reclaims or grows the most recent allocation in place, which suits
+//! push-and-drop patterns.
+
+use solana_program::entrypoint::HEAP_START_ADDRESS;
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    mem::size_of,
+    ptr,
+};
+
+/// Largest heap frame a transaction can request. Touching memory past the
+/// frame actually requested fails the transaction with an access violation
+/// instead of a failed allocation.
+pub const MAX_HEAP_BYTES: usize = 256 * 1024;
+
+/// Keeps the next free address in the first word of the heap, which the
+/// runtime zeroes before the program starts.
+pub struct UpwardBumpAllocator;
+
+impl UpwardBumpAllocator {
+    const NEXT: *mut usize = HEAP_START_ADDRESS as *mut usize;
+    const END: usize = HEAP_START_ADDRESS as usize + MAX_HEAP_BYTES;
+
+    unsafe fn next(&self) -> usize {
+        match *Self::NEXT {
+            0 => HEAP_START_ADDRESS as usize + size_of::<usize>(),
+            next => next,
+        }
+    }
+
+    /// Whether `ptr` of `size` bytes is the latest allocation.
+    unsafe fn is_last(&self, ptr: *mut u8, size: usize) -> bool {
+        ptr as usize + size == self.next()
+    }
+}
+
+unsafe impl GlobalAlloc for UpwardBumpAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let start = self.next().next_multiple_of(layout.align());
+        match start.checked_add(layout.size()) {
+            Some(end) if end <= Self::END => {
+                *Self::NEXT = end;
+                start as *mut u8
+            }
+            _ => ptr::null_mut(),
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if self.is_last(ptr, layout.size()) {
+            *Self::NEXT = ptr as usize;
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if self.is_last(ptr, layout.size()) && ptr as usize + new_size <= Self::END {
+            *Self::NEXT = ptr as usize + new_size;
+            return ptr;
+        }
+        let new_ptr = self.alloc(Layout::from_size_align_unchecked(new_size, layout.align()));
+        if !new_ptr.is_null() {
+            ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            self.dealloc(ptr, layout);
+        }
+        new_ptr
+    }
+}
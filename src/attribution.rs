@@ -0,0 +1,405 @@
+//! Splits a transaction's compute units across the programs it invoked, using
+//! the runtime's `consumed X of Y compute units` log lines, so the per-program
+//! figures can be checked against the transaction total and CPI chains can be
+//! broken down per call. Builds with `section-markers` also log named
+//! boundaries inside each invocation, which split it into sections.
+
+use crate::{
+    metrics::{MetricExtractor, MetricSink},
+    tx_view::ConfirmedTxView,
+};
+use solana_program::pubkey::Pubkey;
+use std::fmt;
+
+/// One program invocation, with the CPIs it made in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvocationNode {
+    pub program_id: Pubkey,
+    /// Units consumed including CPIs; `None` for builtins, which do not log it.
+    pub consumed: Option<u64>,
+    /// Units available when the invocation started, the `Y` of `consumed X of Y`.
+    pub available: Option<u64>,
+    /// `(name, remaining)` for each `Section: {name}` marker, in log order.
+    pub markers: Vec<(String, u64)>,
+    pub children: Vec<InvocationNode>,
+}
+
+impl InvocationNode {
+    fn new(program_id: Pubkey) -> Self {
+        Self {
+            program_id,
+            consumed: None,
+            available: None,
+            markers: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Units spent in each marked section, CPIs included, followed by `exit`
+    /// from the last marker to the end of the invocation. Empty without
+    /// markers or a `consumed` line.
+    pub fn sections(&self) -> Vec<(String, u64)> {
+        let (Some(consumed), Some(available)) = (self.consumed, self.available) else {
+            return Vec::new();
+        };
+        if self.markers.is_empty() {
+            return Vec::new();
+        }
+        let mut sections = Vec::new();
+        let mut previous = available;
+        for (name, remaining) in &self.markers {
+            sections.push((name.clone(), previous.saturating_sub(*remaining)));
+            previous = *remaining;
+        }
+        let end = available.saturating_sub(consumed);
+        sections.push(("exit".to_string(), previous.saturating_sub(end)));
+        sections
+    }
+
+    /// Units spent in this program itself, excluding metered CPIs.
+    pub fn self_consumed(&self) -> Option<u64> {
+        let children: u64 = self
+            .children
+            .iter()
+            .filter_map(|child| child.consumed)
+            .sum();
+        Some(self.consumed?.saturating_sub(children))
+    }
+
+    /// Invocations below this one, at any depth.
+    pub fn cpi_count(&self) -> usize {
+        self.children
+            .iter()
+            .map(|child| 1 + child.cpi_count())
+            .sum()
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        write!(
+            f,
+            "\n{:width$}{}",
+            "",
+            self.program_id,
+            width = 2 + depth * 2
+        )?;
+        match (self.consumed, self.self_consumed()) {
+            (Some(consumed), Some(own)) if !self.children.is_empty() => {
+                write!(f, ": {} CU ({} in program)", consumed, own)?
+            }
+            (Some(consumed), _) => write!(f, ": {} CU", consumed)?,
+            (None, _) => write!(f, ": unmetered")?,
+        }
+        for child in &self.children {
+            child.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the runtime's `invoke [n]`, `consumed X of Y compute units`,
+/// `success` and `failed` lines into one tree per top-level instruction, with
+/// section markers attached to the invocation that logged them. Truncated logs
+/// yield the invocations seen so far.
+pub fn parse_invocations<'a>(logs: impl IntoIterator<Item = &'a str>) -> Vec<InvocationNode> {
+    let mut roots = Vec::new();
+    let mut stack: Vec<InvocationNode> = Vec::new();
+    let finish = |stack: &mut Vec<InvocationNode>, roots: &mut Vec<InvocationNode>| {
+        if let Some(node) = stack.pop() {
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(node),
+                None => roots.push(node),
+            }
+        }
+    };
+
+    let mut section = None;
+    for line in logs {
+        // A marker is a section log followed by a `sol_log_compute_units` line
+        if let Some(name) = line.strip_prefix("Program log: Section: ") {
+            section = Some(name);
+            continue;
+        }
+        if let Some(remaining) = line.strip_prefix("Program consumption: ") {
+            let remaining = remaining
+                .split_whitespace()
+                .next()
+                .and_then(|units| units.parse().ok());
+            if let (Some(name), Some(remaining), Some(top)) =
+                (section.take(), remaining, stack.last_mut())
+            {
+                top.markers.push((name.to_string(), remaining));
+            }
+            continue;
+        }
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        let Some((program, event)) = rest.split_once(' ') else {
+            continue;
+        };
+        let Ok(program) = program.parse::<Pubkey>() else {
+            continue;
+        };
+
+        if event.starts_with("invoke [") {
+            stack.push(InvocationNode::new(program));
+        } else if let Some(consumed) = event.strip_prefix("consumed ") {
+            if let Some(top) = stack.last_mut() {
+                let mut words = consumed.split_whitespace();
+                top.consumed = words.next().and_then(|units| units.parse().ok());
+                top.available = words.nth(1).and_then(|units| units.parse().ok());
+            }
+        } else if event == "success" || event.starts_with("failed") {
+            finish(&mut stack, &mut roots);
+        }
+    }
+    while !stack.is_empty() {
+        finish(&mut stack, &mut roots);
+    }
+    roots
+}
+
+/// CU attribution for one transaction, top-level instruction to CPIs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvocationTree {
+    pub sample: u64,
+    pub instructions: Vec<InvocationNode>,
+    /// Whether the CPI count under every instruction matched the transaction
+    /// meta's inner instructions. False when logs were truncated, or when the
+    /// meta was not available to check against.
+    pub matches_inner_instructions: bool,
+}
+
+impl InvocationTree {
+    /// `inner_instruction_counts[i]` is the number of inner instructions the
+    /// meta lists under top-level instruction `i`.
+    pub fn new(
+        sample: u64,
+        instructions: Vec<InvocationNode>,
+        inner_instruction_counts: Option<&[usize]>,
+    ) -> Self {
+        let matches_inner_instructions = inner_instruction_counts.is_some_and(|counts| {
+            instructions.len() == counts.len()
+                && instructions
+                    .iter()
+                    .zip(counts)
+                    .all(|(instruction, count)| instruction.cpi_count() == *count)
+        });
+        Self {
+            sample,
+            instructions,
+            matches_inner_instructions,
+        }
+    }
+
+    pub fn has_cpis(&self) -> bool {
+        self.instructions
+            .iter()
+            .any(|instruction| !instruction.children.is_empty())
+    }
+}
+
+impl fmt::Display for InvocationTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "sample {}", self.sample)?;
+        if !self.matches_inner_instructions {
+            write!(f, " (not confirmed by inner instructions)")?;
+        }
+        for (i, instruction) in self.instructions.iter().enumerate() {
+            write!(f, "\n  Instruction {}:", i)?;
+            instruction.fmt_indented(f, 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compute units per top-level instruction, read from program logs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogAttribution {
+    /// `(program, consumed)` for each top-level invocation that logged its
+    /// consumption, in instruction order. Inner invocations are included in
+    /// their caller's figure.
+    pub programs: Vec<(Pubkey, u64)>,
+    /// Top-level invocations without a `consumed` line, such as builtins,
+    /// whose cost the logs cannot account for.
+    pub unmetered: usize,
+}
+
+impl LogAttribution {
+    pub fn parse<'a>(logs: impl IntoIterator<Item = &'a str>) -> Self {
+        Self::from_invocations(&parse_invocations(logs))
+    }
+
+    pub fn from_invocations(instructions: &[InvocationNode]) -> Self {
+        let mut attribution = Self::default();
+        for instruction in instructions {
+            match instruction.consumed {
+                Some(units) => attribution.programs.push((instruction.program_id, units)),
+                None => attribution.unmetered += 1,
+            }
+        }
+        attribution
+    }
+
+    pub fn total(&self) -> u64 {
+        self.programs.iter().map(|(_, units)| units).sum()
+    }
+
+    /// The total, when every top-level invocation was metered and there was
+    /// at least one.
+    pub fn metered_total(&self) -> Option<u64> {
+        (!self.programs.is_empty() && self.unmetered == 0).then(|| self.total())
+    }
+
+    /// Transaction total minus the attributed units. Only meaningful when every
+    /// top-level invocation was metered; otherwise `None`.
+    pub fn gap(&self, transaction_total: u64) -> Option<i64> {
+        (self.unmetered == 0).then(|| transaction_total as i64 - self.total() as i64)
+    }
+}
+
+/// Emits `section_{name}` for every section of the top-level invocations,
+/// summed over invocations sharing a section name.
+pub fn emit_sections(instructions: &[InvocationNode], sink: &mut MetricSink) {
+    let mut totals: Vec<(String, u64)> = Vec::new();
+    for (name, units) in instructions.iter().flat_map(InvocationNode::sections) {
+        match totals.iter_mut().find(|(seen, _)| *seen == name) {
+            Some((_, total)) => *total += units,
+            None => totals.push((name, units)),
+        }
+    }
+    for (name, units) in totals {
+        sink.emit(format!("section_{}", name), units as f64);
+    }
+}
+
+/// Emits `attributed_compute_units` and, when the logs cover every top-level
+/// instruction, `attribution_gap` against the reported transaction total, plus
+/// the [`emit_sections`] metrics.
+pub struct Attribution;
+
+impl MetricExtractor for Attribution {
+    fn extract(&self, tx: &ConfirmedTxView, sink: &mut MetricSink) {
+        let instructions = parse_invocations(tx.logs());
+        emit_sections(&instructions, sink);
+        let attribution = LogAttribution::from_invocations(&instructions);
+        if attribution.programs.is_empty() {
+            return;
+        }
+        sink.emit("attributed_compute_units", attribution.total() as f64);
+        // Against the meta only: a total read from the logs has no gap by definition
+        if let Some(gap) = tx.meta_cu_consumed().and_then(|cu| attribution.gap(cu)) {
+            sink.emit("attribution_gap", gap as f64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_top_level_attribution() {
+        let first = Pubkey::new_unique();
+        let second = Pubkey::new_unique();
+        let inner = Pubkey::new_unique();
+        let logs = [
+            format!("Program {} invoke [1]", first),
+            "Program log: Count: 1".to_string(),
+            format!("Program {} consumed 300 of 200000 compute units", first),
+            format!("Program {} success", first),
+            format!("Program {} invoke [1]", second),
+            format!("Program {} invoke [2]", inner),
+            format!("Program {} consumed 100 of 199000 compute units", inner),
+            format!("Program {} success", inner),
+            format!("Program {} consumed 900 of 199700 compute units", second),
+            format!("Program {} success", second),
+        ];
+
+        let attribution = LogAttribution::parse(logs.iter().map(String::as_str));
+        assert_eq!(attribution.programs, [(first, 300), (second, 900)]);
+        assert_eq!(attribution.unmetered, 0);
+        assert_eq!(attribution.gap(1_200), Some(0));
+        assert_eq!(attribution.gap(1_350), Some(150));
+
+        let builtin = Pubkey::new_unique();
+        let with_builtin = [
+            format!("Program {} invoke [1]", builtin),
+            format!("Program {} success", builtin),
+        ];
+        let attribution =
+            LogAttribution::parse(logs.iter().chain(&with_builtin).map(String::as_str));
+        assert_eq!(attribution.unmetered, 1);
+        assert_eq!(attribution.gap(1_350), None);
+    }
+
+    #[test]
+    fn test_invocation_tree() {
+        let caller = Pubkey::new_unique();
+        let callee = Pubkey::new_unique();
+        let builtin = Pubkey::new_unique();
+        let logs = [
+            format!("Program {} invoke [1]", caller),
+            format!("Program {} invoke [2]", callee),
+            format!("Program {} invoke [3]", builtin),
+            format!("Program {} success", builtin),
+            format!("Program {} consumed 400 of 199000 compute units", callee),
+            format!("Program {} success", callee),
+            format!("Program {} consumed 1000 of 200000 compute units", caller),
+            format!("Program {} success", caller),
+            format!("Program {} invoke [1]", callee),
+            "Log truncated".to_string(),
+        ];
+
+        let instructions = parse_invocations(logs.iter().map(String::as_str));
+        assert_eq!(instructions.len(), 2);
+        let root = &instructions[0];
+        assert_eq!(root.cpi_count(), 2);
+        assert_eq!(root.self_consumed(), Some(600));
+        assert_eq!(root.children[0].program_id, callee);
+        assert_eq!(root.children[0].children[0].consumed, None);
+        assert_eq!(instructions[1].consumed, None);
+
+        let tree = InvocationTree::new(3, instructions.clone(), Some(&[2, 0]));
+        assert!(tree.matches_inner_instructions);
+        assert!(tree.has_cpis());
+        assert!(
+            !InvocationTree::new(3, instructions.clone(), Some(&[2, 1])).matches_inner_instructions
+        );
+        assert!(!InvocationTree::new(3, instructions, None).matches_inner_instructions);
+    }
+
+    #[test]
+    fn test_section_markers() {
+        let program = Pubkey::new_unique();
+        let logs = [
+            format!("Program {} invoke [1]", program),
+            "Program log: Section: entrypoint".to_string(),
+            "Program consumption: 199900 units remaining".to_string(),
+            "Program log: Section: execute".to_string(),
+            "Program consumption: 199000 units remaining".to_string(),
+            // An unnamed CU log is not a section boundary
+            "Program consumption: 198950 units remaining".to_string(),
+            format!("Program {} consumed 1200 of 200000 compute units", program),
+            format!("Program {} success", program),
+        ];
+
+        let instructions = parse_invocations(logs.iter().map(String::as_str));
+        assert_eq!(instructions[0].available, Some(200_000));
+        assert_eq!(
+            instructions[0].sections(),
+            vec![
+                ("entrypoint".to_string(), 100),
+                ("execute".to_string(), 900),
+                ("exit".to_string(), 200),
+            ]
+        );
+
+        let mut sink = MetricSink::default();
+        emit_sections(&instructions, &mut sink);
+        let metrics = sink.into_metrics();
+        assert_eq!(metrics.len(), 3);
+        assert_eq!(metrics[1].name, "section_execute");
+        assert_eq!(metrics[1].value, 900.0);
+    }
+}
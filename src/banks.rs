@@ -0,0 +1,246 @@
+//! In-process backend: loads the compiled program into `solana-program-test`
+//! and drives the same workloads through a `BanksClient`, so runs need no
+//! validator. Build the program with `cargo build-sbf` first and point
+//! `SBF_OUT_DIR` at the directory holding the `.so` (usually `target/deploy`).
+//! The `snapshot_accounts` of the config are fetched from `rpc_url` first and
+//! loaded next to the program.
+
+use crate::{
+    attribution::{emit_sections, parse_invocations, InvocationTree, LogAttribution},
+    calibration::Calibration,
+    client::{BenchConfig, CuReport},
+    failure::{describe_instruction, FailureClass, InstructionFailure},
+    guard::{find_guard_address, seen_count},
+    instruction::label_instructions,
+    leader::LeaderReport,
+    metrics::{parse_log_value, MetricSink},
+    predict::predict_instructions,
+    run::Run,
+    sample::{tag_outliers, tag_warmup, Sample},
+    sdk::{RpcClient, Signer, Transaction},
+    size::ProgramSizeInfo,
+    snapshot::AccountSnapshot,
+    stats::linear_fit,
+    workload::{ActiveWorkload, WorkloadContext, WorkloadRegistry},
+};
+use solana_program::clock::Clock;
+use solana_program_test::ProgramTest;
+use std::error::Error;
+use tracing::{info, info_span, warn, Instrument};
+
+/// Name of the shared object `ProgramTest` searches for.
+pub const PROGRAM_NAME: &str = "increase_cu_program_size";
+
+pub(crate) fn run_in_process(
+    config: &BenchConfig,
+    registry: &WorkloadRegistry,
+) -> Result<CuReport, Box<dyn Error>> {
+    if config.lookup_table {
+        return Err("in-process runs send legacy transactions only, not lookup_table".into());
+    }
+    // The blocking RPC client cannot run inside the runtime below
+    let snapshot = if config.snapshot_accounts.is_empty() {
+        AccountSnapshot::default()
+    } else {
+        let rpc_client = RpcClient::new_with_commitment(config.rpc_url.clone(), config.commitment);
+        let snapshot = AccountSnapshot::fetch(&rpc_client, &config.snapshot_accounts)?;
+        info!(
+            "Fetched {} snapshot accounts from {}",
+            snapshot.accounts().len(),
+            config.rpc_url
+        );
+        snapshot
+    };
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(config, registry, &snapshot).instrument(info_span!(
+            "run",
+            program_id = %config.program_id,
+            backend = "program-test"
+        )))
+}
+
+async fn run(
+    config: &BenchConfig,
+    registry: &WorkloadRegistry,
+    snapshot: &AccountSnapshot,
+) -> Result<CuReport, Box<dyn Error>> {
+    let program_id = config.program_id;
+
+    let mut program_test = ProgramTest::new(PROGRAM_NAME, program_id, None);
+    program_test.prefer_bpf(true);
+    snapshot.load_into(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    let banks_client = &mut context.banks_client;
+    let payer = &context.payer;
+
+    // Workloads get an RPC client in their context; the built-in ones never
+    // contact it, so an in-process run works without a node behind the URL
+    let rpc_client = RpcClient::new(config.rpc_url.clone());
+    let ctx = WorkloadContext {
+        rpc_client: &rpc_client,
+        payer,
+        program_id,
+    };
+    let workload = registry
+        .create(&config.workload)
+        .ok_or_else(|| format!("unknown workload {}", config.workload))?;
+    let mut workload = ActiveWorkload::setup(workload, &ctx)?;
+
+    let mut run = Run::new(program_id);
+    let mut log_mismatches = Vec::new();
+    let mut attribution_gaps = Vec::new();
+    let mut invocation_tree = None;
+    let mut burn_points = (Vec::new(), Vec::new());
+
+    let mut i: u64 = 0;
+    while let Some(next) = workload.next_transaction() {
+        if config.stop.is_requested() {
+            info!("Stop requested, no longer sending after {} transactions", i);
+            break;
+        }
+        let index = i;
+        i += 1;
+
+        let recent_blockhash = banks_client.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            &config.with_compute_budget(&next.instructions),
+            Some(&payer.pubkey()),
+            &[payer],
+            recent_blockhash,
+        );
+        let signature = transaction.signatures[0];
+        let transaction_size = bincode::serialized_size(&transaction)
+            .ok()
+            .map(|size| size as usize);
+
+        let processed = match banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+        {
+            Ok(processed) => processed,
+            Err(e) => {
+                warn!("Failed to process transaction {}: {}", index, e);
+                run.failures.record(FailureClass::Other(e.to_string()));
+                continue;
+            }
+        };
+        if let Err(err) = &processed.result {
+            run.failures
+                .record(FailureClass::from_transaction_error(err));
+            let instructions: Vec<String> = next
+                .instructions
+                .iter()
+                .map(|instruction| describe_instruction(instruction, &[program_id]))
+                .collect();
+            match InstructionFailure::locate(index, signature, err, &instructions) {
+                Some(failure) => {
+                    warn!("{}", failure);
+                    run.instruction_failures.push(failure);
+                }
+                None => warn!("Transaction {} failed: {}", index + 1, err),
+            }
+        }
+        let Some(metadata) = processed.metadata else {
+            run.failures.record(FailureClass::NotLanded);
+            continue;
+        };
+        let logs = || metadata.log_messages.iter().map(String::as_str);
+        let cu = metadata.compute_units_consumed;
+        info!("Transaction {}: Compute Units used: {}", index + 1, cu);
+
+        if let Some(expected_log) = next.expected_log {
+            if !logs().any(|line| line.contains(&expected_log)) {
+                warn!(
+                    "Transaction {} is missing log {:?}",
+                    index + 1,
+                    expected_log
+                );
+                log_mismatches.push(index);
+            }
+        }
+        let instructions = parse_invocations(logs());
+        let attribution = LogAttribution::from_invocations(&instructions);
+        if let Some(gap) = attribution.gap(cu) {
+            attribution_gaps.push((index, gap));
+        }
+        let mut sink = MetricSink::default();
+        emit_sections(&instructions, &mut sink);
+        // BanksClient metadata carries no inner instructions to check against
+        let tree = InvocationTree::new(index, instructions, None);
+        if tree.has_cpis() {
+            invocation_tree = Some(tree);
+        }
+
+        let slot = banks_client.get_root_slot().await.unwrap_or_default();
+        let mut sample = Sample::new(index, signature, slot, Some(cu));
+        sample.transaction_size = transaction_size;
+        sample.instruction = label_instructions(&next.instructions, &[program_id]);
+        sample.syscall_compute_units = predict_instructions(&next.instructions, &[program_id]);
+        sample.log_compute_units = attribution.metered_total();
+        sample.block_time = banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .ok()
+            .map(|clock| clock.unix_timestamp);
+        // The same log-derived values the RPC backend extracts; fees are not
+        // reported by BanksClient
+        for (name, marker) in [("count", "Count: "), ("iterations", "Iterations: ")] {
+            if let Some(value) = logs().find_map(|line| parse_log_value(line, marker)) {
+                sink.emit(name, value);
+            }
+        }
+        sample.metrics = sink.into_metrics();
+        if let Some(iterations) = sample.metric("iterations") {
+            burn_points.0.push(iterations);
+            burn_points.1.push(cu as f64);
+        }
+        run.push(sample);
+    }
+    run.attempted = i;
+
+    tag_warmup(run.samples_mut(), config.warmup_samples);
+    tag_outliers(run.samples_mut());
+
+    let (guard_address, _) = find_guard_address(&program_id, &payer.pubkey());
+    let guard_unique_nonces = banks_client
+        .get_account(guard_address)
+        .await?
+        .map(|guard| seen_count(&guard.data));
+
+    // ProgramTest deploys through the non-upgradeable loader, so the program
+    // account holds the whole ELF
+    let rent = banks_client.get_rent().await?;
+    let program_size = banks_client
+        .get_account(program_id)
+        .await?
+        .map(|account| ProgramSizeInfo {
+            program_data_size: account.data.len(),
+            total_size: account.data.len(),
+            lamports: account.lamports,
+            rent_exempt_minimum: rent.minimum_balance(account.data.len()),
+            ..ProgramSizeInfo::default()
+        });
+
+    workload.teardown()?;
+
+    let slos = config.slos.iter().map(|slo| slo.evaluate(&run)).collect();
+    Ok(CuReport {
+        run,
+        payer: payer.pubkey(),
+        program_size,
+        clock: None,
+        accounting: None,
+        calibration: Some(Calibration::local()),
+        leaders: LeaderReport::default(),
+        log_mismatches,
+        burn_fit: linear_fit(&burn_points.0, &burn_points.1),
+        guard_unique_nonces,
+        attribution_gaps,
+        invocation_tree,
+        deployment: None,
+        deployment_changes: Vec::new(),
+        slos,
+    })
+}
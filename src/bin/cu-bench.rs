@@ -0,0 +1,1225 @@
+//! Command-line front end to the benchmark harness, for CI and scripts.
+//!
+//! ```text
+//! cu-bench <command> [--config PATH] ...
+//! cu-bench init   [--output PATH]
+//! cu-bench doctor [--rpc-url URL] [--program-id ID] [--payer PATH]
+//! cu-bench size   [--rpc-url URL] [--program-id ID]
+//! cu-bench run    [--rpc-url URL] [--program-id ID] [--workload NAME] [--iterations N] [--output PATH] [--timeseries PATH] [--script PATH] [--publish KEYPAIR]
+//! cu-bench sweep  [--rpc-url URL] [--program-id ID] [--iterations MAX] [--output PATH] [--timeseries PATH]
+//! cu-bench compare --program-id BEFORE (--against AFTER | --lookup-table | --cu-limit N | --cu-price P) [--workload NAME] [--iterations N]
+//! cu-bench compare --input BEFORE.csv --against AFTER.csv
+//! cu-bench matrix --program-id ID [--against ID[,ID...] | --base PATH --count N] [--workload NAME] [--iterations N]
+//! cu-bench limit  --payer PATH [--program-id ID] [--against ID[,ID...]] [--workload NAME] [--iterations N]
+//! cu-bench ids    (--base PATH --count N | --input DIR) [--output DIR]
+//! cu-bench report --input PATH
+//! cu-bench repl   [--rpc-url URL] [--program-id ID] [--iterations N]
+//! cu-bench survey [--rpc-url URL] [--program-id ID]
+//! cu-bench profile [NAME] [flags of the profile's command]
+//! cu-bench keyring --payer SOURCE --output keyring://SERVICE/ACCOUNT
+//! ```
+//!
+//! `init` asks for the cluster, payer, program ID and experiment profile and
+//! writes them to a config file (`--output`, by default `cu-bench.toml`),
+//! after validating it as a run would. Without a program ID it offers to
+//! build the bundled program with `cargo build-sbf` and deploy it with
+//! `solana program deploy`, which must then be on the `PATH`.
+//! `doctor` checks the setup a run needs, in order: the RPC node answers, its
+//! validator version, that it airdrops, that `cargo build-sbf` is installed,
+//! and that one `counter` transaction lands on the program. It prints a line
+//! per check, green or red on a terminal, and fails if any check did.
+//! `profile` lists the experiment profiles shipped in the binary, or runs the
+//! named one: its command with its settings, which a config file, the
+//! environment and flags override. The size studies and the syscall census
+//! are matrices over `--program-id` and the builds given with `--against`,
+//! `--base` or `EXTRA_PROGRAM_IDS`. See the `profile` module.
+//!
+//! `compare` without `--against` runs the workloads without compute budget
+//! instructions, then with the limit and price of `--cu-limit` and
+//! `--cu-price` (or `COMPUTE_UNIT_LIMIT` and `COMPUTE_UNIT_PRICE`), to show
+//! whether requesting them changes measured CU. Every other command prepends
+//! them to each transaction when set. With `--lookup-table` (or
+//! `LOOKUP_TABLE`), `compare` without `--against` runs legacy transactions,
+//! then v0 transactions loading their accounts through a lookup table the
+//! harness creates, and reports CU and median transaction size; the other
+//! commands send v0 transactions throughout.
+//! `matrix` runs every workload (or the one named) against every program and
+//! keeps going past failed cells, which are marked in the table; it exits with
+//! failure if any cell is missing. With `--base`, the programs after
+//! `--program-id` are the first `--count` ids derived from the base keypair;
+//! without `--base` or `--against`, they are `EXTRA_PROGRAM_IDS`.
+//! `limit` (with the `unstable` feature) binary-searches, by simulation, the
+//! smallest compute unit limit each transaction of the workload succeeds
+//! under, on every program given, and prints the largest as the limit to
+//! request; see `search::minimal_compute_unit_limit`. The payer is only
+//! named as the fee payer, so it must exist but pays nothing.
+//! `ids` lists sweep program ids derived from `--base` or loaded from the
+//! keypair files in `--input`, and writes their keypairs to `--output` for
+//! `solana program deploy --program-id`; see the `program_ids` module for the
+//! derivation scheme.
+//! `--output` writes per-transaction records as CSV or JSON depending on the
+//! extension; `report` reads the CSV form, and `compare --input` compares the
+//! steady-state CU of two such files. Both stream the file, keeping only the
+//! CU of each sample, so archived runs need not fit in memory. `--timeseries` appends each landed
+//! transaction to a CSV keyed by block time, which Grafana's Infinity
+//! datasource can serve to dashboards tracking CU and program size over time.
+//! `--script` (with the `scripting` feature) hooks a Rhai script into `run`:
+//! its `payload` function becomes the `script` workload, picked unless
+//! `--workload` names another, and its `metrics` function adds metrics. See
+//! the `script` module for the functions a script can define.
+//! `--publish` records a summary of the run (run id, sample count, median CU)
+//! in the registry PDA of the given keypair, which also pays; the program must
+//! be built with the `registry` feature. See the `registry` module.
+//! `survey` prints the sizes of well-known mainnet programs, fetched from
+//! `SURVEY_RPC_URL` or mainnet-beta, and the percentile the program given
+//! with `--program-id` on `--rpc-url` falls at among them; see the `survey`
+//! module.
+//! `repl` reads commands from stdin and keeps the cluster, payer and last
+//! report between them; type `help` for the list.
+//! `--deadlines funding=60,send=600` (any of funding, deploy, send, confirm and
+//! fetch, in seconds) abandons a run whose phase overruns, after writing a
+//! diagnostics bundle to `DIAGNOSTICS_DIR` or the temp directory.
+//! `--config PATH` (or `CU_BENCH_CONFIG`) reads settings from a TOML or YAML
+//! file, see the `config_file` module; its `workloads` list is the mix
+//! `compare` and `matrix` run when `--workload` is not given, and `output` and
+//! `output_format` stand in for `--output`.
+//! `--payer PATH` (or `PAYER_KEYPAIR` / `SOLANA_PAYER`) pays from a keypair
+//! file instead of a fresh airdropped keypair, so runs work on devnet and
+//! testnet; the payer is only topped up by airdrop when it holds less than
+//! `MIN_PAYER_BALANCE` lamports. `--payer` and `--publish` also take the
+//! seed phrase sources of the Solana CLI, such as `prompt://?key=0/0` or
+//! `ASK`, and with the `keyring` feature `keyring://SERVICE/ACCOUNT` for a
+//! key in the OS credential store; see the `signer_source` module.
+//! `keyring` (with the `keyring` feature) copies the key of `--payer`, from
+//! any of those sources, into the OS credential store at the `keyring://`
+//! address given with `--output`, and reads it back to check; later runs then
+//! take `--payer keyring://SERVICE/ACCOUNT` and the keypair file can go.
+//! `--cluster localnet|devnet|testnet|custom` (or `CLUSTER`) picks the
+//! cluster's public endpoint, unless `--rpc-url` names another, and paces
+//! requests and sends to stay under its rate limits; `RPC_RATE_LIMIT` and
+//! `SEND_INTERVAL_MS` override the pacing.
+//! `--simulate-only` (or `SIMULATE_ONLY`) measures CU with
+//! `simulateTransaction` instead of landing transactions: results in
+//! seconds, no fees and no confirmations, for quick iteration and CI.
+//! Samples then have no signature or block time, so `--timeseries` has
+//! nothing to append and `--publish` is refused.
+//! Flags override the environment variables read by `BenchConfig::from_env`,
+//! which override the config file.
+//!
+//! Exit codes, for scripts wrapping cu-bench (see the `error` module):
+//! 0 success, 1 any other failure, 2 bad flags, environment or config file,
+//! 3 an RPC request failed (e.g. the cluster is unreachable), 4 `compare
+//! --against` found a workload significantly more expensive after the change,
+//! 5 a phase overran its `--deadlines`, 6 `run` or `sweep` missed one of the
+//! `slos` of the config (`SLOS`), each listed as PASS or FAIL in the summary.
+
+#[cfg(not(target_os = "solana"))]
+fn main() -> std::process::ExitCode {
+    cli::main()
+}
+
+// The program crate also builds for the on-chain target, where there is
+// nothing to run.
+#[cfg(target_os = "solana")]
+fn main() {}
+
+#[cfg(not(target_os = "solana"))]
+mod cli {
+    #[cfg(feature = "keyring")]
+    use increase_cu_program_size::payer::{load_from_keyring, store_in_keyring};
+    #[cfg(feature = "scripting")]
+    use increase_cu_program_size::script::{Script, ScriptedWorkload};
+    use increase_cu_program_size::{
+        cluster::Cluster,
+        compare::{RESAMPLES, SEED},
+        config_file::ConfigFile,
+        deadline::PhaseDeadlines,
+        deployment::ProgramFingerprint,
+        error::exit_code,
+        export::{append_timeseries, export_as, CsvSampleReader},
+        prelude::*,
+        profile::{self, Profile, PROFILES},
+        program_ids::{derive_program_keypair, load_keypair_set, write_keypair_set},
+        redact::{redact, register_secret},
+        registry::{find_registry_address, record_run, RunSummary},
+        sdk::{read_keypair, Keypair, RpcClient, Signer, Transaction},
+        signer_source::{read_signer, SignerSource},
+        stats::{bootstrap_compare, CuStats},
+        survey::{SizeSurvey, SURVEY_RPC_URL, WELL_KNOWN_PROGRAMS},
+        workload::BurnComputeWorkload,
+    };
+    #[cfg(feature = "unstable")]
+    use increase_cu_program_size::{search::minimal_compute_unit_limit, workload::ActiveWorkload};
+    use solana_program::{hash::Hash, instruction::Instruction, pubkey::Pubkey};
+    use std::{
+        error::Error,
+        fs::{self, File},
+        io::{self, BufRead, BufReader, IsTerminal, Write},
+        path::Path,
+        process::{Command, ExitCode},
+        thread::sleep,
+        time::Duration,
+    };
+
+    /// `print!`, `println!` and `eprintln!` through [`redact`]: everything
+    /// cu-bench writes to the terminal goes through these.
+    macro_rules! out {
+        ($($arg:tt)*) => {
+            print!("{}", redact(&format!($($arg)*)))
+        };
+    }
+
+    macro_rules! outln {
+        ($($arg:tt)*) => {
+            println!("{}", redact(&format!($($arg)*)))
+        };
+    }
+
+    macro_rules! errln {
+        ($($arg:tt)*) => {
+            eprintln!("{}", redact(&format!($($arg)*)))
+        };
+    }
+
+    const USAGE: &str =
+        "usage: cu-bench <init|doctor|size|run|sweep|compare|matrix|limit|report|repl|survey|ids|keyring|profile [NAME]> [--rpc-url URL] [--program-id ID] \
+    [--against ID] [--base PATH] [--count N] [--workload NAME] [--iterations N] [--output PATH] [--timeseries PATH] [--script PATH] [--publish KEYPAIR] [--input PATH] [--deadlines PHASE=SECS,...] [--config PATH] [--payer PATH] [--cluster NAME] [--simulate-only] [--lookup-table] [--cu-limit N] [--cu-price MICROLAMPORTS]";
+
+    #[derive(Debug, Clone, Default)]
+    struct Args {
+        command: String,
+        profile: Option<String>,
+        rpc_url: Option<String>,
+        program_id: Option<String>,
+        against: Option<String>,
+        base: Option<String>,
+        count: Option<u32>,
+        workload: Option<String>,
+        iterations: Option<u32>,
+        output: Option<String>,
+        timeseries: Option<String>,
+        script: Option<String>,
+        publish: Option<String>,
+        input: Option<String>,
+        deadlines: Option<String>,
+        config: Option<String>,
+        payer: Option<String>,
+        cluster: Option<Cluster>,
+        simulate_only: bool,
+        lookup_table: bool,
+        cu_limit: Option<u32>,
+        cu_price: Option<u64>,
+    }
+
+    impl Args {
+        fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, Box<dyn Error>> {
+            let mut parsed = Self {
+                command: args.next().ok_or(USAGE)?,
+                ..Self::default()
+            };
+            let mut args = args.peekable();
+            if parsed.command == "profile" {
+                parsed.profile = args.next_if(|name| !name.starts_with("--"));
+            }
+            while let Some(flag) = args.next() {
+                let mut value = || args.next().ok_or(format!("{} needs a value", flag));
+                match flag.as_str() {
+                    "--rpc-url" => parsed.rpc_url = Some(value()?),
+                    "--program-id" => parsed.program_id = Some(value()?),
+                    "--against" => parsed.against = Some(value()?),
+                    "--base" => parsed.base = Some(value()?),
+                    "--count" => parsed.count = Some(value()?.parse()?),
+                    "--workload" => parsed.workload = Some(value()?),
+                    "--iterations" => parsed.iterations = Some(value()?.parse()?),
+                    "--output" => parsed.output = Some(value()?),
+                    "--timeseries" => parsed.timeseries = Some(value()?),
+                    "--script" => parsed.script = Some(value()?),
+                    "--publish" => parsed.publish = Some(value()?),
+                    "--input" => parsed.input = Some(value()?),
+                    "--deadlines" => parsed.deadlines = Some(value()?),
+                    "--config" => parsed.config = Some(value()?),
+                    "--payer" => parsed.payer = Some(value()?),
+                    "--cluster" => parsed.cluster = Some(value()?.parse()?),
+                    "--simulate-only" => parsed.simulate_only = true,
+                    "--lookup-table" => parsed.lookup_table = true,
+                    "--cu-limit" => parsed.cu_limit = Some(value()?.parse()?),
+                    "--cu-price" => parsed.cu_price = Some(value()?.parse()?),
+                    _ => return Err(format!("unknown flag {}\n{}", flag, USAGE).into()),
+                }
+            }
+            Ok(parsed)
+        }
+
+        fn config(&self) -> Result<BenchConfig, Box<dyn Error>> {
+            self.read_config().map_err(config_error)
+        }
+
+        fn read_config(&self) -> Result<BenchConfig, Box<dyn Error>> {
+            let mut settings = match &self.profile {
+                Some(name) => find_profile(name)?.settings(),
+                None => ConfigFile::default(),
+            };
+            let config_file = self
+                .config
+                .clone()
+                .or_else(|| std::env::var("CU_BENCH_CONFIG").ok());
+            if let Some(path) = config_file {
+                settings = settings.overlay(ConfigFile::load(Path::new(&path))?);
+            }
+            let mut config = BenchConfig::from_settings(&settings)?;
+            if let Some(cluster) = self.cluster {
+                config.use_cluster(cluster);
+            }
+            if let Some(rpc_url) = &self.rpc_url {
+                config.rpc_url = rpc_url.clone();
+            }
+            if let Some(program_id) = &self.program_id {
+                config.program_id = program_id.parse()?;
+            }
+            if let Some(workload) = &self.workload {
+                config.workload = workload.clone();
+            }
+            if let Some(deadlines) = &self.deadlines {
+                config.deadlines = PhaseDeadlines::parse(deadlines)?;
+            }
+            if let Some(iterations) = self.iterations {
+                config.iterations = Some(iterations.into());
+            }
+            if let Some(payer) = &self.payer {
+                config.payer_keypair = Some(payer.clone());
+            }
+            // Parsing registers keypair paths with the redactor, before the
+            // repl or an error can print them
+            for source in config.payer_keypair.iter().chain(&self.publish) {
+                SignerSource::parse(source)?;
+            }
+            if let Some(output) = &self.output {
+                config.output = Some(output.into());
+            }
+            config.simulate_only |= self.simulate_only;
+            config.lookup_table |= self.lookup_table;
+            if let Some(limit) = self.cu_limit {
+                config.compute_unit_limit = Some(limit);
+            }
+            if let Some(price) = self.cu_price {
+                config.compute_unit_price = Some(price);
+            }
+            config.validate()?;
+            Ok(config)
+        }
+
+        /// The first `--count` program keypairs derived from `--base`.
+        fn derived_keypairs(&self) -> Result<Option<Vec<Keypair>>, Box<dyn Error>> {
+            let Some(base) = &self.base else {
+                return Ok(None);
+            };
+            let count = self.count.ok_or("--base needs --count")?;
+            register_secret(base.as_str());
+            let base = read_keypair(&mut File::open(base)?)?;
+            Ok(Some(
+                (0..count)
+                    .map(|index| derive_program_keypair(&base, index))
+                    .collect(),
+            ))
+        }
+
+        /// The workload named by `--workload`, the configured mix, or every
+        /// one in `registry`.
+        fn workloads(&self, config: &BenchConfig, registry: &WorkloadRegistry) -> Vec<String> {
+            match &self.workload {
+                Some(workload) => vec![workload.clone()],
+                None if !config.workloads.is_empty() => config.workloads.clone(),
+                None => registry.names().map(str::to_string).collect(),
+            }
+        }
+    }
+
+    pub fn main() -> ExitCode {
+        let args = Args::parse(std::env::args().skip(1)).map_err(config_error);
+        match args.and_then(|args| run(&args)) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                errln!("{}", e);
+                ExitCode::from(exit_code(e.as_ref()))
+            }
+        }
+    }
+
+    fn config_error(e: Box<dyn Error>) -> Box<dyn Error> {
+        BenchError::Config(e.to_string()).into()
+    }
+
+    fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+        match args.command.as_str() {
+            "size" => {
+                let config = args.config()?;
+                let rpc_client = RpcClient::new_with_commitment(config.rpc_url, config.commitment);
+                let program_id = config.program_id.to_string();
+                let info = get_program_size(&rpc_client, &program_id)?;
+                outln!("Program data size: {} bytes", info.program_data_size);
+                outln!("Account total size: {} bytes", info.total_size);
+                if let Some(address) = info.programdata_address {
+                    outln!("Programdata account: {}", address);
+                }
+                outln!(
+                    "Lamports: {} (rent-exempt minimum {}, excess {})",
+                    info.lamports,
+                    info.rent_exempt_minimum,
+                    info.excess_lamports()
+                );
+                let fingerprint = ProgramFingerprint::fetch(&rpc_client, &config.program_id)?;
+                outln!("Loader: {}", fingerprint.loader);
+                if let Some(slot) = info.deployment_slot {
+                    outln!("Deployed at slot: {}", slot);
+                }
+                match info.upgrade_authority {
+                    Some(authority) => outln!("Upgrade authority: {}", authority),
+                    None => outln!("Upgrade authority: none (immutable)"),
+                }
+                outln!("Code hash: {}", fingerprint.code_hash);
+            }
+            "run" => {
+                let config = args.config()?;
+                let registry = config.workload_registry();
+                bench(args, config, registry)?;
+            }
+            "sweep" => {
+                let mut config = args.config()?;
+                config.workload = "burn-compute".to_string();
+                let mut registry = config.workload_registry();
+                if let Some(max) = config.iterations {
+                    registry.register("burn-compute", move || {
+                        let steps = (0..32).map(|exponent| 1u32 << exponent);
+                        BurnComputeWorkload::new(
+                            steps.take_while(|n| u64::from(*n) <= max).collect(),
+                        )
+                    });
+                }
+                bench(args, config, registry)?;
+            }
+            "compare" if args.input.is_some() => compare_files(args)?,
+            "compare" => {
+                let config = args.config()?;
+                let registry = config.workload_registry();
+                let workloads = args.workloads(&config, &registry);
+                let runner = BenchRunner::new(config).with_registry(registry);
+                let diff = match &args.against {
+                    Some(against) => runner.compare(against.parse()?, &workloads)?,
+                    None if runner.config().lookup_table => {
+                        runner.lookup_table_effect(&workloads)?
+                    }
+                    None if runner.config().compute_unit_limit.is_some()
+                        || runner.config().compute_unit_price.is_some() =>
+                    {
+                        runner.compute_budget_effect(&workloads)?
+                    }
+                    None => {
+                        return Err(
+                            "compare needs --against, --lookup-table, --cu-limit or --cu-price"
+                                .into(),
+                        )
+                    }
+                };
+                outln!("{}", diff);
+                // A costlier variant is the effect being measured, not a
+                // regression
+                let workloads: Vec<String> = diff.regressions().map(str::to_string).collect();
+                if args.against.is_some() && !workloads.is_empty() {
+                    return Err(BenchError::Regression { workloads }.into());
+                }
+            }
+            "matrix" => {
+                let config = args.config()?;
+                let mut programs = vec![config.program_id];
+                if let Some(keypairs) = args.derived_keypairs()? {
+                    programs.extend(keypairs.iter().map(Keypair::pubkey));
+                } else if let Some(against) = &args.against {
+                    for program_id in against.split(',') {
+                        programs.push(program_id.trim().parse()?);
+                    }
+                } else {
+                    programs.extend(&config.extra_program_ids);
+                }
+                let registry = config.workload_registry();
+                let workloads = args.workloads(&config, &registry);
+                let matrix = BenchRunner::new(config)
+                    .with_registry(registry)
+                    .matrix(&programs, &workloads);
+                outln!("{}", matrix);
+                let failed = matrix.failures().count();
+                if failed > 0 {
+                    return Err(format!("{} of {} cells failed", failed, matrix.cells.len()).into());
+                }
+            }
+            "ids" => {
+                let keypairs = match (args.derived_keypairs()?, &args.input) {
+                    (Some(keypairs), _) => keypairs,
+                    (None, Some(dir)) => load_keypair_set(Path::new(dir))?,
+                    (None, None) => return Err("ids needs --base or --input".into()),
+                };
+                if let Some(dir) = &args.output {
+                    write_keypair_set(Path::new(dir), &keypairs)?;
+                }
+                for (index, keypair) in keypairs.iter().enumerate() {
+                    outln!("{} {}", index, keypair.pubkey());
+                }
+            }
+            "report" => {
+                let input = args.input.as_deref().ok_or("report needs --input")?;
+                report(BufReader::new(File::open(input)?))?;
+            }
+            "init" => init(args, io::stdin().lock())?,
+            "doctor" => doctor(args)?,
+            "limit" => limit(args)?,
+            "repl" => repl(args, io::stdin().lock())?,
+            "survey" => survey(args)?,
+            "keyring" => keyring(args)?,
+            "profile" => match &args.profile {
+                Some(name) => run(&Args {
+                    command: find_profile(name)?.command.to_string(),
+                    ..args.clone()
+                })?,
+                None => {
+                    for profile in PROFILES {
+                        outln!(
+                            "{:<18} {:<7} {}",
+                            profile.name,
+                            profile.command,
+                            profile.description
+                        );
+                    }
+                }
+            },
+            _ => return Err(BenchError::Config(USAGE.to_string()).into()),
+        }
+        Ok(())
+    }
+
+    fn find_profile(name: &str) -> Result<&'static Profile, Box<dyn Error>> {
+        profile::find(name).ok_or_else(|| {
+            format!(
+                "unknown profile {}; run cu-bench profile for the list",
+                name
+            )
+            .into()
+        })
+    }
+
+    /// Asks for the settings of a first config file and writes it to
+    /// `--output` or `cu-bench.toml`, which must not exist yet.
+    fn init(args: &Args, mut input: impl BufRead) -> Result<(), Box<dyn Error>> {
+        let path = args.output.as_deref().unwrap_or("cu-bench.toml");
+        if Path::new(path).exists() {
+            return Err(format!("{} exists; pass --output for another path", path).into());
+        }
+        outln!("Writing {}; press enter for the answer in brackets", path);
+        let mut settings = ConfigFile::default();
+
+        let cluster = ask(
+            &mut input,
+            "Cluster (localnet, devnet, testnet or custom)",
+            "devnet",
+            |answer| {
+                let cluster = answer.parse::<Cluster>()?;
+                settings.set("cluster", answer)?;
+                Ok(cluster)
+            },
+        )?;
+        match cluster.rpc_url() {
+            Some(rpc_url) => settings.set("rpc_url", rpc_url)?,
+            None => ask(&mut input, "RPC URL", "", |answer| {
+                settings.set("rpc_url", answer)
+            })?,
+        }
+
+        let default_payer = std::env::var("HOME")
+            .map(|home| format!("{}/.config/solana/id.json", home))
+            .ok()
+            .filter(|path| Path::new(path).exists())
+            .unwrap_or_default();
+        ask(
+            &mut input,
+            "Payer (keypair path, prompt:// or keyring://; empty for a fresh airdropped keypair)",
+            &default_payer,
+            |answer| {
+                if answer.is_empty() {
+                    return Ok(());
+                }
+                SignerSource::parse(answer)?;
+                settings.set("payer_keypair", answer)
+            },
+        )?;
+
+        let have_program = ask(
+            &mut input,
+            "Program ID (empty to deploy the bundled program)",
+            "",
+            |answer| match answer {
+                "" => Ok(false),
+                _ => settings.set("program_id", answer).map(|()| true),
+            },
+        )?;
+        if !have_program {
+            let deploy = ask(
+                &mut input,
+                "Build and deploy it with cargo build-sbf and solana program deploy? (y/n)",
+                "y",
+                |answer| match answer {
+                    "y" | "yes" => Ok(true),
+                    "n" | "no" => Ok(false),
+                    _ => Err("answer y or n".into()),
+                },
+            )?;
+            if !deploy {
+                return Err("init needs a program ID; deploy the program and run it again".into());
+            }
+            let rpc_url = settings.get("rpc_url").unwrap_or_default();
+            let program_id = deploy_bundled(rpc_url, settings.get("payer_keypair"))?;
+            outln!("Deployed {}", program_id);
+            settings.set("program_id", &program_id.to_string())?;
+        }
+
+        for profile in PROFILES {
+            outln!("  {:<18} {}", profile.name, profile.description);
+        }
+        let profile = ask(
+            &mut input,
+            "Experiment profile",
+            "quick-smoke",
+            find_profile,
+        )?;
+        let settings = profile.settings().overlay(settings);
+
+        settings.validate()?;
+        BenchConfig::from_settings(&settings)?.validate()?;
+        fs::write(
+            path,
+            format!(
+                "# cu-bench {} --config {}\n# Profile {}: {}\n{}",
+                profile.command,
+                path,
+                profile.name,
+                profile.description,
+                settings.to_toml()
+            ),
+        )?;
+        outln!(
+            "Wrote {}; run it with cu-bench {} --config {}",
+            path,
+            profile.command,
+            path
+        );
+        Ok(())
+    }
+
+    /// Prompts with `question` until `answer` accepts the reply, or the
+    /// default when the reply is empty.
+    fn ask<T>(
+        input: &mut impl BufRead,
+        question: &str,
+        default: &str,
+        mut answer: impl FnMut(&str) -> Result<T, Box<dyn Error>>,
+    ) -> Result<T, Box<dyn Error>> {
+        let mut line = String::new();
+        loop {
+            match default {
+                "" => out!("{}: ", question),
+                _ => out!("{} [{}]: ", question, default),
+            }
+            io::stdout().flush()?;
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                return Err("init aborted".into());
+            }
+            let reply = match line.trim() {
+                "" => default,
+                reply => reply,
+            };
+            match answer(reply) {
+                Ok(value) => return Ok(value),
+                Err(e) => errln!("{}", e),
+            }
+        }
+    }
+
+    /// Builds this crate's program with `cargo build-sbf` and deploys it to
+    /// `rpc_url` with `solana program deploy`, paid by `payer` or the Solana
+    /// CLI's default keypair. Returns the new program ID.
+    fn deploy_bundled(rpc_url: &str, payer: Option<&str>) -> Result<Pubkey, Box<dyn Error>> {
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let status = Command::new("cargo")
+            .arg("build-sbf")
+            .arg("--manifest-path")
+            .arg(manifest_dir.join("Cargo.toml"))
+            .status()
+            .map_err(|e| format!("cargo build-sbf: {}", e))?;
+        if !status.success() {
+            return Err(format!("cargo build-sbf failed: {}", status).into());
+        }
+        let mut deploy = Command::new("solana");
+        deploy.args(["program", "deploy", "--url", rpc_url]);
+        if let Some(payer) = payer {
+            deploy.args(["--keypair", payer]);
+        }
+        let output = deploy
+            .arg(manifest_dir.join("target/deploy/increase_cu_program_size.so"))
+            .output()
+            .map_err(|e| format!("solana program deploy: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "solana program deploy failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into());
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let program_id = stdout
+            .lines()
+            .find_map(|line| line.strip_prefix("Program Id: "))
+            .ok_or("solana program deploy printed no program ID")?;
+        Ok(program_id.trim().parse()?)
+    }
+
+    /// Runs the checks of `doctor`, printing each as it finishes, and fails
+    /// if any did.
+    fn doctor(args: &Args) -> Result<(), Box<dyn Error>> {
+        let config = args.config()?;
+        let rpc_client = RpcClient::new_with_commitment(config.rpc_url.clone(), config.commitment);
+        let colored = io::stdout().is_terminal();
+        let mut failed = 0;
+        let mut check = |name: &str, result: Result<String, Box<dyn Error>>| {
+            let (mark, detail) = match (&result, colored) {
+                (Ok(detail), true) => ("\x1b[32m✔\x1b[0m", detail.clone()),
+                (Ok(detail), false) => ("ok  ", detail.clone()),
+                (Err(e), true) => ("\x1b[31m✘\x1b[0m", e.to_string()),
+                (Err(e), false) => ("FAIL", e.to_string()),
+            };
+            outln!("{} {}: {}", mark, name, detail);
+            failed += usize::from(result.is_err());
+            result.is_ok()
+        };
+
+        let reachable = check(
+            "RPC reachable",
+            rpc_client
+                .get_slot()
+                .map(|slot| format!("{} at slot {}", config.rpc_url, slot))
+                .map_err(Into::into),
+        );
+        if reachable {
+            check(
+                "Validator version",
+                rpc_client
+                    .get_version()
+                    .map(|version| match version.feature_set {
+                        Some(feature_set) => {
+                            format!("{} (feature set {})", version.solana_core, feature_set)
+                        }
+                        None => version.solana_core,
+                    })
+                    .map_err(Into::into),
+            );
+            check("Airdrop", check_airdrop(&rpc_client));
+        }
+        check("cargo build-sbf", check_build_sbf());
+        if reachable {
+            let smoke = BenchConfig {
+                workload: "counter".to_string(),
+                iterations: Some(1),
+                output: None,
+                ..config
+            };
+            check(
+                "Smoke benchmark",
+                BenchRunner::new(smoke).run().map(|report| {
+                    match report.run.samples().find_map(|sample| sample.compute_units) {
+                        Some(units) => format!("one counter transaction used {} CU", units),
+                        None => "one counter transaction landed".to_string(),
+                    }
+                }),
+            );
+        }
+
+        match failed {
+            0 => Ok(()),
+            _ => Err(format!("{} doctor checks failed", failed).into()),
+        }
+    }
+
+    /// Airdrops the rent-exempt minimum to a fresh keypair and waits up to
+    /// 30 seconds for it to land.
+    fn check_airdrop(rpc_client: &RpcClient) -> Result<String, Box<dyn Error>> {
+        let recipient = Keypair::new().pubkey();
+        let lamports = rpc_client.get_minimum_balance_for_rent_exemption(0)?;
+        let signature = rpc_client.request_airdrop(&recipient, lamports)?;
+        for _ in 0..30 {
+            if rpc_client.confirm_transaction(&signature)? {
+                return Ok(format!("{} lamports landed", lamports));
+            }
+            sleep(Duration::from_secs(1));
+        }
+        Err(format!("airdrop {} did not land within 30s", signature).into())
+    }
+
+    fn check_build_sbf() -> Result<String, Box<dyn Error>> {
+        let output = Command::new("cargo")
+            .args(["build-sbf", "--version"])
+            .output()
+            .map_err(|e| format!("cargo: {}", e))?;
+        if !output.status.success() {
+            return Err("not installed; see the Solana CLI install guide".into());
+        }
+        let version = String::from_utf8_lossy(&output.stdout);
+        Ok(version
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string())
+    }
+
+    fn bench(
+        args: &Args,
+        mut config: BenchConfig,
+        mut registry: WorkloadRegistry,
+    ) -> Result<(), Box<dyn Error>> {
+        if args.publish.is_some() && config.simulate_only {
+            return Err("--publish needs landed transactions, not --simulate-only".into());
+        }
+        let mut metrics = MetricRegistry::new();
+        if let Some(path) = &args.script {
+            hook_script(args, path, &mut config, &mut registry, &mut metrics)?;
+        }
+        let rpc_client = RpcClient::new_with_commitment(config.rpc_url.clone(), config.commitment);
+        let program_id = config.program_id;
+        let (output, output_format) = (config.output.clone(), config.output_format);
+        let report = BenchRunner::new(config)
+            .with_registry(registry)
+            .with_metrics(metrics)
+            .run()?;
+        outln!("{}", report);
+        if let Some(output) = &output {
+            export_as(output, output_format, &report.run)?;
+        }
+        if let Some(timeseries) = &args.timeseries {
+            append_timeseries(timeseries, &report)?;
+        }
+        if let Some(path) = &args.publish {
+            publish(&rpc_client, &program_id, path, &report)?;
+        }
+        let slos: Vec<String> = report
+            .slos
+            .iter()
+            .filter(|outcome| !outcome.passed)
+            .map(|outcome| outcome.slo.to_string())
+            .collect();
+        if !slos.is_empty() {
+            return Err(BenchError::SloFailed { slos }.into());
+        }
+        Ok(())
+    }
+
+    fn publish(
+        rpc_client: &RpcClient,
+        program_id: &Pubkey,
+        publisher_path: &str,
+        report: &CuReport,
+    ) -> Result<(), Box<dyn Error>> {
+        let publisher = read_signer(publisher_path)?;
+        let summary =
+            RunSummary::from_report(report).ok_or("no steady-state samples to publish")?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[record_run(program_id, &publisher.pubkey(), summary)],
+            Some(&publisher.pubkey()),
+            &[&publisher],
+            rpc_client.get_latest_blockhash()?,
+        );
+        let signature = rpc_client.send_and_confirm_transaction(&transaction)?;
+        let (registry, _) = find_registry_address(program_id, &publisher.pubkey());
+        outln!(
+            "Published run {} to registry {}: {}",
+            Hash::new_from_array(summary.run_id),
+            registry,
+            signature
+        );
+        Ok(())
+    }
+
+    /// Registers the `script` workload and metrics of the script at `path`.
+    #[cfg(feature = "scripting")]
+    fn hook_script(
+        args: &Args,
+        path: &str,
+        config: &mut BenchConfig,
+        registry: &mut WorkloadRegistry,
+        metrics: &mut MetricRegistry,
+    ) -> Result<(), Box<dyn Error>> {
+        let script = Script::load(path)?;
+        if script.defines("payload") {
+            let workload_script = script.clone();
+            registry.register("script", move || {
+                ScriptedWorkload::new(workload_script.clone())
+            });
+            if args.workload.is_none() {
+                config.workload = "script".to_string();
+            }
+        }
+        if script.defines("metrics") {
+            metrics.register(script);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "scripting"))]
+    fn hook_script(
+        _args: &Args,
+        _path: &str,
+        _config: &mut BenchConfig,
+        _registry: &mut WorkloadRegistry,
+        _metrics: &mut MetricRegistry,
+    ) -> Result<(), Box<dyn Error>> {
+        Err("--script needs the scripting feature".into())
+    }
+
+    /// Copies the key of `--payer` into the credential store at `--output`.
+    #[cfg(feature = "keyring")]
+    fn keyring(args: &Args) -> Result<(), Box<dyn Error>> {
+        let source = args
+            .payer
+            .as_deref()
+            .ok_or("keyring needs --payer with the key to store")?;
+        let target = args
+            .output
+            .as_deref()
+            .ok_or("keyring needs --output keyring://SERVICE/ACCOUNT")?;
+        let SignerSource::Keyring { service, account } = SignerSource::parse(target)? else {
+            return Err(
+                format!("--output must be keyring://SERVICE/ACCOUNT, got {}", target).into(),
+            );
+        };
+        let keypair = read_signer(source)?;
+        store_in_keyring(&service, &account, &keypair)?;
+        // Some stores accept a secret they cannot hand back
+        if load_from_keyring(&service, &account)?.pubkey() != keypair.pubkey() {
+            return Err(format!("{} returned a different key than was stored", target).into());
+        }
+        outln!(
+            "Stored {} at {}; pass --payer {}",
+            keypair.pubkey(),
+            target,
+            target
+        );
+        Ok(())
+    }
+
+    #[cfg(not(feature = "keyring"))]
+    fn keyring(_args: &Args) -> Result<(), Box<dyn Error>> {
+        Err("keyring needs cu-bench built with the keyring feature".into())
+    }
+
+    /// Prints the smallest compute unit limit every transaction of the
+    /// workload needs, on `--program-id` and each program in `--against`.
+    #[cfg(feature = "unstable")]
+    fn limit(args: &Args) -> Result<(), Box<dyn Error>> {
+        let config = args.config()?;
+        let source = config
+            .payer_keypair
+            .as_deref()
+            .ok_or("limit simulates as an existing account; pass --payer")?;
+        let payer = read_signer(source)?;
+        let rpc_client = RpcClient::new_with_commitment(config.rpc_url.clone(), config.commitment);
+        let mut programs = vec![config.program_id];
+        if let Some(against) = &args.against {
+            for program_id in against.split(',') {
+                programs.push(program_id.trim().parse()?);
+            }
+        }
+        let registry = config.workload_registry();
+        for program_id in programs {
+            let ctx = WorkloadContext {
+                rpc_client: &rpc_client,
+                payer: &payer,
+                program_id,
+            };
+            let workload = registry
+                .create(&config.workload)
+                .ok_or_else(|| BenchError::UnknownWorkload(config.workload.clone()))?;
+            let mut workload = ActiveWorkload::setup(workload, &ctx)?;
+            let mut highest = 0;
+            let mut index = 0;
+            while let Some(transaction) = workload.next_transaction() {
+                let search = minimal_compute_unit_limit(
+                    &rpc_client,
+                    &payer.pubkey(),
+                    &transaction.instructions,
+                )?;
+                let consumed = search
+                    .compute_units
+                    .map_or("?".to_string(), |units| units.to_string());
+                outln!(
+                    "{} #{}: limit {} ({} CU consumed, {} simulations)",
+                    program_id,
+                    index,
+                    search.limit,
+                    consumed,
+                    search.simulations
+                );
+                highest = highest.max(search.limit);
+                index += 1;
+            }
+            workload.teardown()?;
+            outln!(
+                "{}: request at least {} CU for {}",
+                program_id,
+                highest,
+                config.workload
+            );
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "unstable"))]
+    fn limit(_args: &Args) -> Result<(), Box<dyn Error>> {
+        Err("limit needs the unstable feature".into())
+    }
+
+    /// Prints the survey of well-known programs and where the configured
+    /// program ranks in it, when it can be fetched.
+    fn survey(args: &Args) -> Result<(), Box<dyn Error>> {
+        let config = args.config()?;
+        let survey_url =
+            std::env::var("SURVEY_RPC_URL").unwrap_or_else(|_| SURVEY_RPC_URL.to_string());
+        let survey_client = RpcClient::new_with_commitment(survey_url, config.commitment);
+        let survey = SizeSurvey::fetch(&survey_client, WELL_KNOWN_PROGRAMS);
+        out!("{}", survey);
+        if survey.entries.is_empty() {
+            return Err("no program sizes could be fetched".into());
+        }
+
+        let rpc_client = RpcClient::new_with_commitment(config.rpc_url, config.commitment);
+        match get_program_size(&rpc_client, &config.program_id.to_string()) {
+            Ok(info) => {
+                if let Some(rank) = survey.percentile_rank(info.program_data_size) {
+                    outln!(
+                        "{} ({} bytes) is at the {:.0}th percentile",
+                        config.program_id,
+                        info.program_data_size,
+                        rank
+                    );
+                }
+            }
+            Err(e) => outln!("{} not ranked: {}", config.program_id, e),
+        }
+        Ok(())
+    }
+
+    /// Summarizes a CSV file written by `run --output` or `sweep --output`.
+    fn report(input: impl BufRead) -> Result<(), Box<dyn Error>> {
+        let mut total = 0;
+        let mut landed = Vec::new();
+        let mut steady_state = Vec::new();
+        for sample in CsvSampleReader::new(input)? {
+            let sample = sample?;
+            total += 1;
+            let Some(compute_units) = sample.compute_units else {
+                continue;
+            };
+            landed.push(compute_units);
+            if sample.tags.is_empty() {
+                steady_state.push(compute_units);
+            }
+        }
+
+        outln!("Steady-state samples: {} of {}", steady_state.len(), total);
+        outln!("Compute units, all landed: {}", CuStats::summarize(&landed));
+        outln!(
+            "Compute units, steady state: {}",
+            CuStats::summarize(&steady_state)
+        );
+        Ok(())
+    }
+
+    /// Compares the steady-state CU of the CSV files of `--input` and
+    /// `--against`, failing as a regression when the second is significantly
+    /// higher.
+    fn compare_files(args: &Args) -> Result<(), Box<dyn Error>> {
+        let before = args.input.as_deref().ok_or("compare needs --input")?;
+        let after = args
+            .against
+            .as_deref()
+            .ok_or("compare --input needs --against with a second CSV file")?;
+        let steady_state = |path: &str| -> Result<Vec<u64>, Box<dyn Error>> {
+            let mut compute_units = Vec::new();
+            for sample in CsvSampleReader::open(path)? {
+                let sample = sample.map_err(|e| format!("{}: {}", path, e))?;
+                if let (Some(units), true) = (sample.compute_units, sample.tags.is_empty()) {
+                    compute_units.push(units);
+                }
+            }
+            Ok(compute_units)
+        };
+        let (before_cu, after_cu) = (steady_state(before)?, steady_state(after)?);
+        outln!("Before: {}: {}", before, CuStats::summarize(&before_cu));
+        outln!("After:  {}: {}", after, CuStats::summarize(&after_cu));
+        let comparison = bootstrap_compare(&before_cu, &after_cu, RESAMPLES, SEED)
+            .ok_or("both files need steady-state samples to compare")?;
+        outln!("Change: {}", comparison);
+        if comparison.is_significant() && comparison.diff > 0.0 {
+            return Err(BenchError::Regression {
+                workloads: vec![after.to_string()],
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    const REPL_HELP: &str = "\
+cluster [URL]      show or set the RPC URL
+payer [SOURCE]     show the payer, or load it from a keypair file, prompt:// or keyring://
+size ID            program data and account size
+sim ID HEXDATA     simulate one instruction and print its CU and logs
+send WORKLOAD      run a named workload against the configured program
+stats last         CU statistics of the last send
+workloads          list workload names
+quit";
+
+    /// State kept between REPL commands.
+    struct Session {
+        config: BenchConfig,
+        /// Without one, each `send` funds a fresh keypair by airdrop.
+        payer: Option<Keypair>,
+        last: Option<CuReport>,
+    }
+
+    fn repl(args: &Args, mut input: impl BufRead) -> Result<(), Box<dyn Error>> {
+        let mut session = Session {
+            config: args.config()?,
+            payer: None,
+            last: None,
+        };
+        outln!(
+            "Connected to {}; type help for commands",
+            session.config.rpc_url
+        );
+        let mut line = String::new();
+        loop {
+            out!("cu-bench> ");
+            io::stdout().flush()?;
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[..] {
+                [] => {}
+                ["quit" | "exit"] => return Ok(()),
+                _ => {
+                    if let Err(e) = session.execute(&words, &mut input) {
+                        errln!("{}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    impl Session {
+        fn rpc_client(&self) -> RpcClient {
+            RpcClient::new_with_commitment(self.config.rpc_url.clone(), self.config.commitment)
+        }
+
+        /// Runs one command; `input` is the REPL's own, which a `payer`
+        /// source that prompts for a seed phrase reads from.
+        fn execute(&mut self, words: &[&str], input: impl BufRead) -> Result<(), Box<dyn Error>> {
+            match words {
+                ["help"] => outln!("{}", REPL_HELP),
+                ["cluster"] => outln!("{}", self.config.rpc_url),
+                ["cluster", rpc_url] => self.config.rpc_url = rpc_url.to_string(),
+                ["payer"] => match (&self.payer, &self.config.payer_keypair) {
+                    (Some(payer), _) => outln!("{}", payer.pubkey()),
+                    (None, Some(source)) => outln!("{}", source),
+                    (None, None) => outln!("fresh keypair funded by airdrop on each send"),
+                },
+                ["payer", source] => {
+                    let payer = SignerSource::parse(source)?.load(input)?;
+                    outln!("{}", payer.pubkey());
+                    self.payer = Some(payer);
+                }
+                ["size", program_id] => {
+                    outln!("{}", get_program_size(&self.rpc_client(), program_id)?);
+                }
+                ["sim", program_id, data] => {
+                    self.simulate(program_id.parse()?, &parse_hex(data)?)?
+                }
+                ["send", workload] => {
+                    let config = BenchConfig {
+                        workload: workload.to_string(),
+                        ..self.config.clone()
+                    };
+                    let mut runner = BenchRunner::new(config);
+                    if let Some(payer) = &self.payer {
+                        runner = runner.with_payer(payer.insecure_clone());
+                    }
+                    let report = runner.run()?;
+                    outln!("{}", report);
+                    self.last = Some(report);
+                }
+                ["stats", "last"] => {
+                    let report = self.last.as_ref().ok_or("nothing sent yet")?;
+                    outln!(
+                        "Compute units, all landed: {}",
+                        report.compute_unit_stats(&TagFilter::all())
+                    );
+                    outln!(
+                        "Compute units, steady state: {}",
+                        report.compute_unit_stats(&TagFilter::steady_state())
+                    );
+                }
+                ["workloads"] => {
+                    for name in self.config.workload_registry().names() {
+                        outln!("{}", name);
+                    }
+                }
+                _ => return Err(format!("unknown command {:?}; type help", words.join(" ")).into()),
+            }
+            Ok(())
+        }
+
+        fn simulate(&self, program_id: Pubkey, data: &[u8]) -> Result<(), Box<dyn Error>> {
+            // Simulation still loads the fee payer, so it must exist on the cluster
+            let payer = self
+                .payer
+                .as_ref()
+                .ok_or("sim needs a funded payer; load one with payer SOURCE")?;
+            let rpc_client = self.rpc_client();
+            let transaction = Transaction::new_signed_with_payer(
+                &[Instruction::new_with_bytes(program_id, data, vec![])],
+                Some(&payer.pubkey()),
+                &[payer],
+                rpc_client.get_latest_blockhash()?,
+            );
+            let result = rpc_client.simulate_transaction(&transaction)?.value;
+            for log in result.logs.unwrap_or_default() {
+                outln!("  {}", log);
+            }
+            if let Some(err) = result.err {
+                outln!("Failed: {}", err);
+            }
+            match result.units_consumed {
+                Some(units) => outln!("Compute units: {}", units),
+                None => outln!("Compute units: not reported"),
+            }
+            Ok(())
+        }
+    }
+
+    fn parse_hex(hex: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
+        if !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+            return Err(format!("{} is not an even number of hex digits", hex).into());
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| Ok(u8::from_str_radix(&hex[i..i + 2], 16)?))
+            .collect()
+    }
+}
@@ -0,0 +1,79 @@
+//! Blockhash cache shared by everything that signs transactions during a
+//! run, so builders do not each call `get_latest_blockhash`: one background
+//! thread keeps the freshest hash and its last valid block height, and
+//! builders read it without a round trip.
+
+use crate::sdk::RpcClient;
+use solana_program::{clock::DEFAULT_MS_PER_SLOT, hash::Hash};
+use std::{
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread::{self, sleep, JoinHandle},
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+/// Latest blockhash and its last valid block height, shared by every transaction
+/// builder and refreshed by a background thread roughly every 20 slots.
+pub struct BlockhashCache {
+    latest: Arc<RwLock<(Hash, u64)>>,
+    stop: Arc<AtomicBool>,
+    refresher: Option<JoinHandle<()>>,
+}
+
+impl BlockhashCache {
+    const REFRESH_INTERVAL: Duration = Duration::from_millis(20 * DEFAULT_MS_PER_SLOT);
+
+    pub fn start(rpc_client: &RpcClient) -> Result<Self, Box<dyn Error>> {
+        let commitment = rpc_client.commitment();
+        let latest = Arc::new(RwLock::new(
+            rpc_client.get_latest_blockhash_with_commitment(commitment)?,
+        ));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let refresher = {
+            let latest = Arc::clone(&latest);
+            let stop = Arc::clone(&stop);
+            let rpc_client = RpcClient::new_with_commitment(rpc_client.url(), commitment);
+            thread::spawn(move || {
+                let mut last_refresh = Instant::now();
+                while !stop.load(Ordering::Relaxed) {
+                    sleep(Duration::from_millis(DEFAULT_MS_PER_SLOT));
+                    if last_refresh.elapsed() < Self::REFRESH_INTERVAL {
+                        continue;
+                    }
+                    match rpc_client.get_latest_blockhash_with_commitment(commitment) {
+                        Ok(fresh) => {
+                            *latest.write().unwrap() = fresh;
+                            last_refresh = Instant::now();
+                        }
+                        Err(e) => warn!("Failed to refresh blockhash: {}", e),
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            latest,
+            stop,
+            refresher: Some(refresher),
+        })
+    }
+
+    /// Returns the freshest blockhash and its last valid block height.
+    pub fn get(&self) -> (Hash, u64) {
+        *self.latest.read().unwrap()
+    }
+}
+
+impl Drop for BlockhashCache {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(refresher) = self.refresher.take() {
+            let _ = refresher.join();
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Quick burn-in that records the environment a run was measured in, so
+//! comparisons across machines and networks show confounding differences.
+
+use crate::{burn_compute, sdk::RpcClient};
+use std::{
+    fmt,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+/// Iterations of [`burn_compute`] timed as the local CPU score.
+const CPU_ITERATIONS: u32 = 1 << 20;
+const RTT_PROBES: usize = 5;
+
+/// Ratios beyond which two environments are reported as different.
+const CPU_TOLERANCE: f64 = 1.25;
+const RTT_TOLERANCE: f64 = 2.0;
+const SLOT_TIME_TOLERANCE: f64 = 1.1;
+
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Calibration {
+    pub os: String,
+    pub arch: String,
+    pub cpus: usize,
+    /// Fastest of three local runs of the on-chain busy loop; only drives
+    /// client-side timings, never CU.
+    pub cpu_time: Duration,
+    /// Median round trip of `getSlot`.
+    pub rpc_rtt: Option<Duration>,
+    /// Mean slot time over the validator's latest performance sample.
+    pub slot_time: Option<Duration>,
+}
+
+impl Calibration {
+    pub fn measure(rpc_client: &RpcClient) -> Self {
+        let mut rtts: Vec<Duration> = (0..RTT_PROBES)
+            .filter_map(|_| {
+                let started = Instant::now();
+                rpc_client.get_slot().ok()?;
+                Some(started.elapsed())
+            })
+            .collect();
+        rtts.sort_unstable();
+
+        let slot_time = rpc_client
+            .get_recent_performance_samples(Some(1))
+            .ok()
+            .and_then(|samples| samples.into_iter().next())
+            .filter(|sample| sample.num_slots > 0)
+            .map(|sample| {
+                Duration::from_secs(u64::from(sample.sample_period_secs)) / sample.num_slots as u32
+            });
+
+        Self {
+            rpc_rtt: rtts.get(rtts.len() / 2).copied(),
+            slot_time,
+            ..Self::local()
+        }
+    }
+
+    /// Machine fingerprint and CPU score only, without a cluster.
+    pub fn local() -> Self {
+        let cpu_time = (0..3)
+            .map(|_| {
+                let started = Instant::now();
+                black_box(burn_compute(black_box(CPU_ITERATIONS)));
+                started.elapsed()
+            })
+            .min()
+            .unwrap_or_default();
+        Self {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            cpus: std::thread::available_parallelism().map_or(1, usize::from),
+            cpu_time,
+            rpc_rtt: None,
+            slot_time: None,
+        }
+    }
+
+    /// Environmental differences large enough to affect latency figures or
+    /// hint at a different cluster; empty when the environments match.
+    pub fn differences(&self, other: &Calibration) -> Vec<String> {
+        let mut differences = Vec::new();
+        if (&self.os, &self.arch) != (&other.os, &other.arch) {
+            differences.push(format!(
+                "measured on {}/{} and {}/{}",
+                self.os, self.arch, other.os, other.arch
+            ));
+        }
+        if exceeds(self.cpu_time, other.cpu_time, CPU_TOLERANCE) {
+            differences.push(format!(
+                "local CPU speed differs ({:?} vs {:?} calibration loop)",
+                self.cpu_time, other.cpu_time
+            ));
+        }
+        if let (Some(ours), Some(theirs)) = (self.rpc_rtt, other.rpc_rtt) {
+            if exceeds(ours, theirs, RTT_TOLERANCE) {
+                differences.push(format!(
+                    "RPC round trip differs ({:?} vs {:?})",
+                    ours, theirs
+                ));
+            }
+        }
+        if let (Some(ours), Some(theirs)) = (self.slot_time, other.slot_time) {
+            if exceeds(ours, theirs, SLOT_TIME_TOLERANCE) {
+                differences.push(format!("slot time differs ({:?} vs {:?})", ours, theirs));
+            }
+        }
+        differences
+    }
+}
+
+fn exceeds(a: Duration, b: Duration, tolerance: f64) -> bool {
+    let (a, b) = (a.as_secs_f64(), b.as_secs_f64());
+    a.min(b) > 0.0 && a.max(b) / a.min(b) > tolerance
+}
+
+impl fmt::Display for Calibration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} with {} CPUs, calibration loop {:?}",
+            self.os, self.arch, self.cpus, self.cpu_time
+        )?;
+        if let Some(rtt) = self.rpc_rtt {
+            write!(f, ", RPC round trip {:?}", rtt)?;
+        }
+        if let Some(slot_time) = self.slot_time {
+            write!(f, ", slot time {:?}", slot_time)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_calibration_differences() {
+        let here = Calibration {
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            cpus: 8,
+            cpu_time: Duration::from_millis(10),
+            rpc_rtt: Some(Duration::from_millis(2)),
+            slot_time: Some(Duration::from_millis(400)),
+        };
+        let similar = Calibration {
+            cpu_time: Duration::from_millis(11),
+            rpc_rtt: Some(Duration::from_millis(3)),
+            ..here.clone()
+        };
+        assert!(here.differences(&similar).is_empty());
+
+        let remote = Calibration {
+            arch: "aarch64".to_string(),
+            cpu_time: Duration::from_millis(20),
+            rpc_rtt: Some(Duration::from_millis(80)),
+            slot_time: Some(Duration::from_millis(460)),
+            ..here.clone()
+        };
+        assert_eq!(here.differences(&remote).len(), 4);
+        assert!(Calibration::local().cpu_time > Duration::ZERO);
+    }
+}
@@ -0,0 +1,204 @@
+//! Throughput estimates derived from measured CU and the runtime's cost limits.
+
+use solana_program::pubkey::Pubkey;
+use std::{collections::HashMap, fmt};
+
+/// Cost-model limits a transaction shape is packed against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockLimits {
+    pub max_transaction_units: u64,
+    pub max_block_units: u64,
+    /// Budget for all transactions write-locking the same account in one block.
+    pub max_writable_account_units: u64,
+    pub slot_duration_ms: u64,
+}
+
+impl Default for BlockLimits {
+    fn default() -> Self {
+        Self {
+            max_transaction_units: 1_400_000,
+            max_block_units: 48_000_000,
+            max_writable_account_units: 12_000_000,
+            slot_duration_ms: 400,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct HeadroomReport {
+    pub compute_units: u64,
+    /// CU left below the per-transaction limit.
+    pub transaction_headroom: u64,
+    /// Share of the per-transaction limit consumed, in percent.
+    pub transaction_utilization: f64,
+    /// Transactions that fit in one block when they touch disjoint accounts.
+    pub per_block: u64,
+    /// Transactions that fit in one block when all write-lock the same account.
+    pub per_block_contended: u64,
+    /// Upper bound on transactions per second for the uncontended case.
+    pub max_tps: f64,
+}
+
+impl HeadroomReport {
+    pub fn new(compute_units: u64, limits: &BlockLimits) -> Self {
+        let per_tx = compute_units.max(1);
+        let per_block = limits.max_block_units / per_tx;
+        Self {
+            compute_units,
+            transaction_headroom: limits.max_transaction_units.saturating_sub(compute_units),
+            transaction_utilization: compute_units as f64 * 100.0
+                / limits.max_transaction_units as f64,
+            per_block,
+            per_block_contended: (limits.max_writable_account_units / per_tx).min(per_block),
+            max_tps: per_block as f64 * 1_000.0 / limits.slot_duration_ms as f64,
+        }
+    }
+
+    pub fn fits_in_transaction(&self) -> bool {
+        self.transaction_utilization <= 100.0
+    }
+}
+
+impl fmt::Display for HeadroomReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} CU ({:.2}% of tx limit, {} CU headroom); {} per block ({} if write-contended), ~{:.0} TPS",
+            self.compute_units,
+            self.transaction_utilization,
+            self.transaction_headroom,
+            self.per_block,
+            self.per_block_contended,
+            self.max_tps
+        )
+    }
+}
+
+/// A transaction shape observed in a run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionShape {
+    pub compute_units: u64,
+    /// Serialized transaction size in bytes.
+    pub size: usize,
+    pub writable_accounts: Vec<Pubkey>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct PackingEstimate {
+    pub blocks: usize,
+    pub transactions_per_block: f64,
+    pub bytes_per_block: f64,
+    pub max_tps: f64,
+    /// Whether per-account write-lock budgets, rather than the block CU limit,
+    /// stopped blocks from filling further.
+    pub write_lock_bound: bool,
+}
+
+/// Packs `blocks` blocks by cycling through `shapes` in order, taking every
+/// transaction that still fits the block CU limit and the write-lock budget of
+/// each account it writes, and closing a block after a full pass adds nothing.
+pub fn simulate_packing(
+    shapes: &[TransactionShape],
+    limits: &BlockLimits,
+    blocks: usize,
+) -> Option<PackingEstimate> {
+    let packable: Vec<&TransactionShape> = shapes
+        .iter()
+        .filter(|shape| shape.compute_units <= limits.max_transaction_units)
+        .collect();
+    if packable.is_empty() || blocks == 0 {
+        return None;
+    }
+
+    let (mut total_transactions, mut total_bytes) = (0usize, 0usize);
+    let (mut cursor, mut write_lock_misses, mut block_cu_misses) = (0usize, 0usize, 0usize);
+    for _ in 0..blocks {
+        let mut block_units = 0u64;
+        let mut account_units: HashMap<&Pubkey, u64> = HashMap::new();
+        let mut misses = 0;
+        while misses < packable.len() {
+            let shape = packable[cursor % packable.len()];
+            cursor += 1;
+
+            if block_units + shape.compute_units > limits.max_block_units {
+                block_cu_misses += 1;
+                misses += 1;
+                continue;
+            }
+            let contended = shape.writable_accounts.iter().any(|account| {
+                account_units.get(account).copied().unwrap_or(0) + shape.compute_units
+                    > limits.max_writable_account_units
+            });
+            if contended {
+                write_lock_misses += 1;
+                misses += 1;
+                continue;
+            }
+
+            misses = 0;
+            block_units += shape.compute_units;
+            for account in &shape.writable_accounts {
+                *account_units.entry(account).or_default() += shape.compute_units;
+            }
+            total_transactions += 1;
+            total_bytes += shape.size;
+        }
+    }
+
+    let transactions_per_block = total_transactions as f64 / blocks as f64;
+    Some(PackingEstimate {
+        blocks,
+        transactions_per_block,
+        bytes_per_block: total_bytes as f64 / blocks as f64,
+        max_tps: transactions_per_block * 1_000.0 / limits.slot_duration_ms as f64,
+        write_lock_bound: write_lock_misses > block_cu_misses,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_headroom_report() {
+        let report = HeadroomReport::new(1_412, &BlockLimits::default());
+        assert_eq!(report.transaction_headroom, 1_398_588);
+        assert_eq!(report.per_block, 33_994);
+        assert_eq!(report.per_block_contended, 8_498);
+        assert_eq!(report.max_tps, 84_985.0);
+        assert!(report.fits_in_transaction());
+
+        let over = HeadroomReport::new(1_500_000, &BlockLimits::default());
+        assert_eq!(over.transaction_headroom, 0);
+        assert!(!over.fits_in_transaction());
+    }
+
+    #[test]
+    fn test_simulate_packing() {
+        let limits = BlockLimits::default();
+        let counter = Pubkey::new_unique();
+        let disjoint: Vec<TransactionShape> = (0..4)
+            .map(|_| TransactionShape {
+                compute_units: 1_000_000,
+                size: 200,
+                writable_accounts: vec![Pubkey::new_unique()],
+            })
+            .collect();
+        let estimate = simulate_packing(&disjoint, &limits, 10).unwrap();
+        assert_eq!(estimate.transactions_per_block, 48.0);
+        assert_eq!(estimate.bytes_per_block, 9_600.0);
+        assert!(!estimate.write_lock_bound);
+
+        let contended = [TransactionShape {
+            compute_units: 1_000_000,
+            size: 200,
+            writable_accounts: vec![counter],
+        }];
+        let estimate = simulate_packing(&contended, &limits, 10).unwrap();
+        assert_eq!(estimate.transactions_per_block, 12.0);
+        assert_eq!(estimate.max_tps, 30.0);
+        assert!(estimate.write_lock_bound);
+    }
+}
@@ -0,0 +1,1853 @@
+//! Programmatic benchmark driver: funds a payer, sends a workload against the
+//! program, waits for confirmations and collects everything into a
+//! [`CuReport`]. The test harness is a thin wrapper around [`BenchRunner`].
+
+use crate::{
+    accounting::{RuntimeAccounting, MAX_COMPUTE_UNIT_LIMIT},
+    attribution::{parse_invocations, Attribution, InvocationTree, LogAttribution},
+    blockhash::BlockhashCache,
+    calibration::Calibration,
+    capacity::{BlockLimits, HeadroomReport},
+    clock::{ClockCheck, DEFAULT_DRIFT_THRESHOLD},
+    cluster::{Cluster, Pacer, RateLimits},
+    compare::CuDiff,
+    config_file::ConfigFile,
+    control::StopSignal,
+    deadline::{Phase, PhaseClock, PhaseDeadlines},
+    deployment::{DeploymentChange, ProgramFingerprint},
+    error::BenchError,
+    export::ExportFormat,
+    failure::{describe_instruction, FailureClass, InstructionFailure},
+    guard::{find_guard_address, seen_count},
+    instruction::{
+        label_instructions, set_compute_unit_limit, set_compute_unit_price,
+        sets_compute_unit_limit, sets_compute_unit_price,
+    },
+    leader::{LeaderGate, LeaderReport, LeaderSchedule, LeaderTargeting},
+    lock::ExperimentLock,
+    lookup_table::LookupTable,
+    matrix::{MatrixCell, MatrixReport},
+    metrics::{Fee, LogValue, MetricRegistry},
+    pda::BumpCacheSavings,
+    predict::{predict_instructions, ModelCheck},
+    run::Run,
+    sample::{
+        tag_congestion, tag_epoch_boundaries, tag_outliers, tag_warmup, PhaseTimings, Sample,
+        TagFilter,
+    },
+    sdk::{
+        CommitmentConfig, Keypair, RpcClient, RpcSimulateTransactionConfig, RpcTransactionConfig,
+        Signature, Signer, VersionedTransaction,
+    },
+    signer_source::read_signer,
+    size::{get_program_size, ProgramSizeInfo},
+    slo::{Slo, SloOutcome},
+    stats::{linear_fit, CuStats, LinearFit},
+    tx_view::ConfirmedTxView,
+    window::SlotWindow,
+    workload::{
+        ActiveWorkload, CounterWorkload, CpiWorkload, GuardedCounterWorkload, MultiProgramWorkload,
+        WorkloadContext, WorkloadRegistry,
+    },
+};
+use solana_program::{
+    clock::DEFAULT_MS_PER_SLOT,
+    instruction::Instruction,
+    message::{v0, Message, VersionedMessage},
+    pubkey,
+    pubkey::Pubkey,
+};
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    path::{Path, PathBuf},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use tracing::{info, info_span, warn};
+
+/// Everything a run needs to know up front.
+#[derive(Debug, Clone)]
+pub struct BenchConfig {
+    pub rpc_url: String,
+    /// Pacing of the run's requests and sends, see [`BenchConfig::use_cluster`].
+    pub rate_limits: RateLimits,
+    pub commitment: CommitmentConfig,
+    pub program_id: Pubkey,
+    /// Further deployments of the program, called alongside `program_id` by
+    /// the `multi-program` workload.
+    pub extra_program_ids: Vec<Pubkey>,
+    /// Execute in-process through `solana-program-test` instead of against
+    /// `rpc_url`. Needs the `program-test` feature.
+    pub in_process: bool,
+    /// Accounts fetched from `rpc_url` and loaded into in-process runs, with
+    /// the programdata of every upgradeable program among them, so a run
+    /// sees real cluster state; see [`crate::snapshot`].
+    pub snapshot_accounts: Vec<Pubkey>,
+    /// Name of a workload in the runner's [`WorkloadRegistry`].
+    pub workload: String,
+    /// The mix of workloads `compare` and `matrix` run; empty for every
+    /// registered one.
+    pub workloads: Vec<String>,
+    /// Transactions sent by the counter workloads; `None` keeps their defaults.
+    pub iterations: Option<u64>,
+    /// Pays from this keypair file, or any other source in
+    /// [`crate::signer_source`], instead of a fresh keypair funded by airdrop.
+    pub payer_keypair: Option<String>,
+    /// A payer from a file or [`BenchRunner::with_payer`] holding less than
+    /// this is topped up with an airdrop of `airdrop_lamports` first.
+    pub min_payer_balance: u64,
+    /// Where the CLI writes per-transaction records.
+    pub output: Option<PathBuf>,
+    /// Format of `output`; `None` picks it from the extension.
+    pub output_format: Option<ExportFormat>,
+    /// Archival node used when the primary RPC has pruned a transaction.
+    pub archival_rpc_url: Option<String>,
+    /// Prepended to every transaction as `SetComputeUnitLimit`, replacing
+    /// any limit the workload sets.
+    pub compute_unit_limit: Option<u32>,
+    /// Prepended to every transaction as `SetComputeUnitPrice`, in
+    /// micro-lamports per compute unit.
+    pub compute_unit_price: Option<u64>,
+    /// Record simulated CU next to executed CU for every sample.
+    pub simulate_before_send: bool,
+    /// Simulate every transaction instead of sending it, see
+    /// [`BenchRunner::run`].
+    pub simulate_only: bool,
+    /// Send v0 messages that load accounts through a lookup table the
+    /// harness creates, see [`crate::lookup_table`].
+    pub lookup_table: bool,
+    /// Take over the experiment lock even if another live run holds it; a
+    /// lock left by a run that is no longer running is taken over regardless.
+    pub force_lock: bool,
+    /// Ends the send phase early; whatever was sent is still verified.
+    pub stop: StopSignal,
+    /// Start every N sends at a fresh slot boundary.
+    pub slot_window_batch: Option<usize>,
+    pub leader_targeting: Option<LeaderTargeting>,
+    pub airdrop_lamports: u64,
+    pub confirm_timeout: Duration,
+    /// Leading samples tagged as warmup.
+    pub warmup_samples: u64,
+    /// Longest each phase of an RPC run may take before the run is abandoned.
+    pub deadlines: PhaseDeadlines,
+    /// Where a diagnostics bundle is written when a phase overruns.
+    pub diagnostics_dir: PathBuf,
+    /// Objectives every run is checked against, see [`crate::slo`].
+    pub slos: Vec<Slo>,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self {
+            rpc_url: "http://127.0.0.1:8899".to_string(),
+            rate_limits: RateLimits::default(),
+            commitment: CommitmentConfig::confirmed(),
+            program_id: pubkey!("H2GM7Vci4vVTWUfM4CQ5gWuUXF56wgXC4CkUY2Ea7mgC"),
+            extra_program_ids: Vec::new(),
+            in_process: false,
+            snapshot_accounts: Vec::new(),
+            workload: "counter".to_string(),
+            workloads: Vec::new(),
+            iterations: None,
+            payer_keypair: None,
+            min_payer_balance: 10_000_000,
+            output: None,
+            output_format: None,
+            archival_rpc_url: None,
+            compute_unit_limit: None,
+            compute_unit_price: None,
+            simulate_before_send: false,
+            simulate_only: false,
+            lookup_table: false,
+            force_lock: false,
+            stop: StopSignal::default(),
+            slot_window_batch: None,
+            leader_targeting: None,
+            airdrop_lamports: 1_000_000_000,
+            confirm_timeout: Duration::from_secs(60),
+            warmup_samples: 5,
+            deadlines: PhaseDeadlines::default(),
+            diagnostics_dir: std::env::temp_dir(),
+            slos: Vec::new(),
+        }
+    }
+}
+
+/// The setting `name` parsed, or `None` when it is unset.
+fn parse_setting<T>(
+    var: &impl Fn(&str) -> Option<String>,
+    name: &str,
+) -> Result<Option<T>, Box<dyn Error>>
+where
+    T: std::str::FromStr,
+    T::Err: fmt::Display,
+{
+    var(name)
+        .map(|value| {
+            value
+                .trim()
+                .parse()
+                .map_err(|e| format!("invalid {} {:?}: {}", name, value, e).into())
+        })
+        .transpose()
+}
+
+impl BenchConfig {
+    /// Defaults, or the preset of `CLUSTER` (see [`BenchConfig::use_cluster`]),
+    /// overridden by `RPC_URL`, `RPC_RATE_LIMIT` (requests per second),
+    /// `SEND_INTERVAL_MS`, `COMMITMENT`, `PROGRAM_ID`,
+    /// `EXTRA_PROGRAM_IDS` (comma-separated), `IN_PROCESS`,
+    /// `SNAPSHOT_ACCOUNTS` (comma-separated), `WORKLOAD`,
+    /// `WORKLOADS` (comma-separated), `ITERATIONS`, `PAYER_KEYPAIR` (or
+    /// `SOLANA_PAYER`), `MIN_PAYER_BALANCE`, `OUTPUT`,
+    /// `OUTPUT_FORMAT` (`csv` or `json`), `ARCHIVAL_RPC_URL`,
+    /// `COMPUTE_UNIT_LIMIT`, `COMPUTE_UNIT_PRICE` (micro-lamports),
+    /// `SIMULATE_BEFORE_SEND`, `SIMULATE_ONLY`, `LOOKUP_TABLE`, `FORCE_LOCK`, `STOP_FILE`,
+    /// `SLOT_WINDOW_BATCH`,
+    /// `LEADER_TARGET` (`<identity>` or `spread:N`), `PHASE_DEADLINES`
+    /// (`phase=seconds,...`), `DIAGNOSTICS_DIR` and `SLOS` (comma-separated,
+    /// e.g. `p99_cu < 5000,landing_rate > 99%`). Switches take `true` or
+    /// `1` and `false`, `0` or empty; any other value that does not parse is
+    /// an error, rather than a silent fallback to the default.
+    pub fn from_env() -> Result<Self, Box<dyn Error>> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Defaults overridden by the TOML or YAML file at `path`, which is
+    /// overridden in turn by the environment; see [`crate::config_file`].
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Self::from_settings(&ConfigFile::load(path.as_ref())?)
+    }
+
+    /// Defaults overridden by `settings`, for example those of a
+    /// [`crate::profile::Profile`], which are overridden in turn by the
+    /// environment.
+    pub fn from_settings(settings: &ConfigFile) -> Result<Self, Box<dyn Error>> {
+        Self::from_file_and_lookup(settings, |name| std::env::var(name).ok())
+    }
+
+    /// [`BenchConfig::from_file`] of `CU_BENCH_CONFIG` when set, otherwise
+    /// [`BenchConfig::from_env`].
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        match std::env::var_os("CU_BENCH_CONFIG") {
+            Some(path) => Self::from_file(path),
+            None => Self::from_env(),
+        }
+    }
+
+    fn from_file_and_lookup(
+        file: &ConfigFile,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, Box<dyn Error>> {
+        Self::from_lookup(|name| {
+            var(name).or_else(|| file.get(&name.to_ascii_lowercase()).map(str::to_string))
+        })
+    }
+
+    fn from_lookup(var: impl Fn(&str) -> Option<String>) -> Result<Self, Box<dyn Error>> {
+        let mut defaults = Self::default();
+        if let Some(cluster) = parse_setting(&var, "CLUSTER")? {
+            defaults.use_cluster(cluster);
+        }
+        let list = |value: String| -> Vec<String> {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        let invalid = |name: &str, value: &str, e: &dyn fmt::Display| -> Box<dyn Error> {
+            format!("invalid {} {:?}: {}", name, value, e).into()
+        };
+        let enabled = |name: &str| match var(name).as_deref().map(str::trim) {
+            None | Some("" | "0" | "false") => Ok(false),
+            Some("1" | "true") => Ok(true),
+            Some(value) => Err(invalid(name, value, &"expected true, false, 1 or 0")),
+        };
+        Ok(Self {
+            rpc_url: var("RPC_URL").unwrap_or(defaults.rpc_url),
+            rate_limits: RateLimits {
+                requests_per_second: parse_setting(&var, "RPC_RATE_LIMIT")?
+                    .or(defaults.rate_limits.requests_per_second),
+                send_interval: parse_setting(&var, "SEND_INTERVAL_MS")?
+                    .map(Duration::from_millis)
+                    .or(defaults.rate_limits.send_interval),
+            },
+            commitment: parse_setting(&var, "COMMITMENT")?.unwrap_or(defaults.commitment),
+            program_id: parse_setting(&var, "PROGRAM_ID")?.unwrap_or(defaults.program_id),
+            extra_program_ids: var("EXTRA_PROGRAM_IDS")
+                .map(list)
+                .unwrap_or_default()
+                .iter()
+                .map(|id| {
+                    id.parse()
+                        .map_err(|e| invalid("EXTRA_PROGRAM_IDS entry", id, &e))
+                })
+                .collect::<Result<_, _>>()?,
+            in_process: enabled("IN_PROCESS")?,
+            snapshot_accounts: var("SNAPSHOT_ACCOUNTS")
+                .map(list)
+                .unwrap_or_default()
+                .iter()
+                .map(|address| {
+                    address
+                        .parse()
+                        .map_err(|e| invalid("SNAPSHOT_ACCOUNTS entry", address, &e))
+                })
+                .collect::<Result<_, _>>()?,
+            workload: var("WORKLOAD").unwrap_or(defaults.workload),
+            workloads: var("WORKLOADS").map(list).unwrap_or_default(),
+            iterations: parse_setting(&var, "ITERATIONS")?,
+            payer_keypair: var("PAYER_KEYPAIR").or_else(|| var("SOLANA_PAYER")),
+            min_payer_balance: parse_setting(&var, "MIN_PAYER_BALANCE")?
+                .unwrap_or(defaults.min_payer_balance),
+            output: var("OUTPUT").map(PathBuf::from),
+            output_format: parse_setting(&var, "OUTPUT_FORMAT")?,
+            archival_rpc_url: var("ARCHIVAL_RPC_URL"),
+            compute_unit_limit: parse_setting(&var, "COMPUTE_UNIT_LIMIT")?,
+            compute_unit_price: parse_setting(&var, "COMPUTE_UNIT_PRICE")?,
+            simulate_before_send: enabled("SIMULATE_BEFORE_SEND")?,
+            simulate_only: enabled("SIMULATE_ONLY")?,
+            lookup_table: enabled("LOOKUP_TABLE")?,
+            force_lock: enabled("FORCE_LOCK")?,
+            stop: var("STOP_FILE")
+                .map(StopSignal::with_sentinel)
+                .unwrap_or_default(),
+            slot_window_batch: parse_setting(&var, "SLOT_WINDOW_BATCH")?,
+            leader_targeting: parse_setting(&var, "LEADER_TARGET")?,
+            deadlines: var("PHASE_DEADLINES")
+                .map(|spec| {
+                    PhaseDeadlines::parse(&spec).map_err(|e| invalid("PHASE_DEADLINES", &spec, &e))
+                })
+                .transpose()?
+                .unwrap_or_default(),
+            diagnostics_dir: var("DIAGNOSTICS_DIR")
+                .map(PathBuf::from)
+                .unwrap_or(defaults.diagnostics_dir),
+            slos: var("SLOS")
+                .map(list)
+                .unwrap_or_default()
+                .iter()
+                .map(|slo| slo.parse().map_err(|e| invalid("SLOS entry", slo, &e)))
+                .collect::<Result<_, _>>()?,
+            ..defaults
+        })
+    }
+
+    /// Checks settings that parse but cannot make a run, such as a compute
+    /// unit limit above the runtime's maximum or an in-process run with
+    /// lookup tables, naming the setting at fault.
+    pub fn validate(&self) -> Result<(), BenchError> {
+        self.check(self.payer_keypair.is_some())
+    }
+
+    /// [`BenchConfig::validate`], where `has_payer` says whether the run
+    /// brings its own payer rather than relying on an airdrop.
+    fn check(&self, has_payer: bool) -> Result<(), BenchError> {
+        let mut problems = Vec::new();
+        if !self.in_process && !has_payer && self.rpc_url.contains("mainnet") {
+            problems.push(format!(
+                "rpc_url {:?} has no faucet; set payer_keypair",
+                self.rpc_url
+            ));
+        }
+        if let Some(limit) = self
+            .compute_unit_limit
+            .filter(|limit| *limit > MAX_COMPUTE_UNIT_LIMIT)
+        {
+            problems.push(format!(
+                "compute_unit_limit {} is above the runtime's maximum of {}",
+                limit, MAX_COMPUTE_UNIT_LIMIT
+            ));
+        }
+        if self.iterations == Some(0) {
+            problems.push("iterations must be at least 1".to_string());
+        }
+        if self.in_process && self.lookup_table {
+            problems.push(
+                "in_process runs send legacy transactions only, not lookup_table".to_string(),
+            );
+        }
+        if !self.in_process && !self.snapshot_accounts.is_empty() {
+            problems.push("snapshot_accounts are only loaded into in_process runs".to_string());
+        }
+        if self.in_process && self.simulate_only {
+            problems
+                .push("in_process runs execute every transaction, not simulate_only".to_string());
+        }
+        match problems.len() {
+            0 => Ok(()),
+            1 => Err(BenchError::Config(problems.remove(0))),
+            count => Err(BenchError::Config(format!(
+                "{} conflicting settings:\n  {}",
+                count,
+                problems.join("\n  ")
+            ))),
+        }
+    }
+
+    /// Points the run at `cluster`'s public endpoint, unless it is
+    /// [`Cluster::Custom`], and paces it to the cluster's rate limits.
+    pub fn use_cluster(&mut self, cluster: Cluster) {
+        if let Some(rpc_url) = cluster.rpc_url() {
+            self.rpc_url = rpc_url.to_string();
+        }
+        self.rate_limits = cluster.rate_limits();
+    }
+
+    /// `instructions` behind the configured `SetComputeUnitLimit` and
+    /// `SetComputeUnitPrice`, each replacing one the workload set itself.
+    pub fn with_compute_budget(&self, instructions: &[Instruction]) -> Vec<Instruction> {
+        let mut budgeted = Vec::with_capacity(instructions.len() + 2);
+        budgeted.extend(self.compute_unit_limit.map(set_compute_unit_limit));
+        budgeted.extend(self.compute_unit_price.map(set_compute_unit_price));
+        budgeted.extend(
+            instructions
+                .iter()
+                .filter(|instruction| {
+                    !(self.compute_unit_limit.is_some() && sets_compute_unit_limit(instruction)
+                        || self.compute_unit_price.is_some()
+                            && sets_compute_unit_price(instruction))
+                })
+                .cloned(),
+        );
+        budgeted
+    }
+
+    /// The configured compute budget, e.g. `limit 200000, price 1000 micro-lamports`, or
+    /// `None` when neither is set.
+    fn compute_budget(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(limit) = self.compute_unit_limit {
+            parts.push(format!("limit {}", limit));
+        }
+        if let Some(price) = self.compute_unit_price {
+            parts.push(format!("price {} micro-lamports", price));
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    /// The built-in workloads, with the counter workloads resized to
+    /// `iterations`, plus `multi-program` and `cpi-companion` (calling into
+    /// the first extra program) when `extra_program_ids` is set.
+    pub fn workload_registry(&self) -> WorkloadRegistry {
+        let mut registry = WorkloadRegistry::with_builtins();
+        if let Some(count) = self.iterations {
+            registry.register("counter", move || CounterWorkload::new(count));
+            registry.register("guarded-counter", move || {
+                GuardedCounterWorkload::new(count)
+            });
+        }
+        if !self.extra_program_ids.is_empty() {
+            let co_programs = self.extra_program_ids.clone();
+            let count = self.iterations.unwrap_or(100);
+            registry.register("multi-program", move || {
+                MultiProgramWorkload::new(co_programs.clone(), count)
+            });
+            let companion = self.extra_program_ids[0];
+            registry.register("cpi-companion", move || {
+                CpiWorkload::new(Some(companion), 10)
+            });
+        }
+        registry
+    }
+}
+
+/// Lamports an account gained or lost over a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LamportFlow {
+    /// `None` when transactions could not be decoded, in which case accounts
+    /// are told apart by `account_index` only.
+    pub account: Option<Pubkey>,
+    pub account_index: usize,
+    pub net: i128,
+    /// Samples that changed the balance.
+    pub samples: usize,
+}
+
+impl fmt::Display for LamportFlow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.account {
+            Some(account) => write!(f, "{}", account)?,
+            None => write!(f, "#{}", self.account_index)?,
+        }
+        write!(f, " {:+} in {} samples", self.net, self.samples)
+    }
+}
+
+/// Results of one benchmark run.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct CuReport {
+    pub run: Run,
+    pub payer: Pubkey,
+    pub program_size: Option<ProgramSizeInfo>,
+    pub clock: Option<ClockCheck>,
+    /// CU accounting rules of the cluster the run was measured on.
+    pub accounting: Option<RuntimeAccounting>,
+    /// Machine and network the run was measured from.
+    pub calibration: Option<Calibration>,
+    pub leaders: LeaderReport,
+    /// Indices of landed samples whose logs lacked the workload's expected line.
+    pub log_mismatches: Vec<u64>,
+    /// CU against iteration count for `BurnCompute` samples.
+    pub burn_fit: Option<LinearFit>,
+    /// Distinct nonces recorded by the replay guard, for guarded workloads.
+    pub guard_unique_nonces: Option<u32>,
+    /// The binary measured, as seen when the run started.
+    pub deployment: Option<ProgramFingerprint>,
+    /// `(sample index, transaction CU minus per-program CU from logs)` for
+    /// every sample whose top-level instructions were all metered in the logs.
+    pub attribution_gaps: Vec<(u64, i64)>,
+    /// Per-call CU breakdown of the last landed sample that made CPIs.
+    pub invocation_tree: Option<InvocationTree>,
+    /// Changes to the binary observed between the start and end of the run;
+    /// when non-empty the samples mix different code.
+    pub deployment_changes: Vec<DeploymentChange>,
+    /// The configured SLOs, checked against the run.
+    pub slos: Vec<SloOutcome>,
+}
+
+impl CuReport {
+    /// Sorted CU of samples not tagged as warmup, outlier or otherwise noisy.
+    pub fn steady_state_compute_units(&self) -> Vec<u64> {
+        let mut compute_units = TagFilter::steady_state().compute_units(&self.run);
+        compute_units.sort_unstable();
+        compute_units
+    }
+
+    /// Summary of the CU of samples passing `filter`.
+    pub fn compute_unit_stats(&self, filter: &TagFilter) -> CuStats {
+        CuStats::summarize(&filter.compute_units(&self.run))
+    }
+
+    pub fn median_compute_units(&self) -> Option<u64> {
+        let compute_units = self.steady_state_compute_units();
+        compute_units.get(compute_units.len() / 2).copied()
+    }
+
+    /// Median steady-state CU of each section logged by a `section-markers`
+    /// build, in the order the sections ran.
+    pub fn section_compute_units(&self) -> Vec<(String, f64)> {
+        let filter = TagFilter::steady_state();
+        let mut sections: Vec<(String, Vec<f64>)> = Vec::new();
+        for sample in self.run.samples().filter(|sample| filter.matches(sample)) {
+            for metric in &sample.metrics {
+                let Some(name) = metric.name.strip_prefix("section_") else {
+                    continue;
+                };
+                match sections.iter_mut().find(|(seen, _)| seen == name) {
+                    Some((_, values)) => values.push(metric.value),
+                    None => sections.push((name.to_string(), vec![metric.value])),
+                }
+            }
+        }
+        sections
+            .into_iter()
+            .map(|(name, mut values)| {
+                values.sort_by(f64::total_cmp);
+                (name, values[values.len() / 2])
+            })
+            .collect()
+    }
+
+    /// Median steady-state CU saved by verifying a PDA with a cached bump
+    /// instead of re-deriving it, per seed configuration such as `4x8`, in
+    /// the order they were first sent. See [`crate::pda::BumpCacheSavings`].
+    pub fn bump_cache_savings(&self) -> Vec<(String, f64)> {
+        let filter = TagFilter::steady_state();
+        let mut configs: Vec<(String, Vec<f64>)> = Vec::new();
+        for sample in self.run.samples().filter(|sample| filter.matches(sample)) {
+            for metric in &sample.metrics {
+                let Some(config) = metric.name.strip_prefix("bump_cache_savings_") else {
+                    continue;
+                };
+                match configs.iter_mut().find(|(seen, _)| seen == config) {
+                    Some((_, values)) => values.push(metric.value),
+                    None => configs.push((config.to_string(), vec![metric.value])),
+                }
+            }
+        }
+        configs
+            .into_iter()
+            .map(|(config, mut values)| {
+                values.sort_by(f64::total_cmp);
+                (config, values[values.len() / 2])
+            })
+            .collect()
+    }
+
+    /// Net lamport change per account over all landed samples, in order of
+    /// first appearance.
+    pub fn lamport_flows(&self) -> Vec<LamportFlow> {
+        let mut flows: Vec<LamportFlow> = Vec::new();
+        let deltas = self.run.samples().flat_map(|sample| &sample.balance_deltas);
+        for delta in deltas.filter(|delta| delta.mint.is_none()) {
+            let same_account = |flow: &&mut LamportFlow| {
+                flow.account == delta.account
+                    && (flow.account.is_some() || flow.account_index == delta.account_index)
+            };
+            match flows.iter_mut().find(same_account) {
+                Some(flow) => {
+                    flow.net += delta.change();
+                    flow.samples += 1;
+                }
+                None => flows.push(LamportFlow {
+                    account: delta.account,
+                    account_index: delta.account_index,
+                    net: delta.change(),
+                    samples: 1,
+                }),
+            }
+        }
+        flows
+    }
+
+    pub fn headroom(&self, limits: &BlockLimits) -> Option<HeadroomReport> {
+        Some(HeadroomReport::new(self.median_compute_units()?, limits))
+    }
+
+    /// Reasons this run should not be compared with `other`: the program was
+    /// changed during either run, or the two runs measured different binaries.
+    pub fn comparison_caveats(&self, other: &CuReport) -> Vec<DeploymentChange> {
+        let mut caveats = self.deployment_changes.clone();
+        caveats.extend(other.deployment_changes.iter().cloned());
+        if let (Some(ours), Some(theirs)) = (&self.deployment, &other.deployment) {
+            if ours.program_id == theirs.program_id {
+                caveats.extend(theirs.changes_since(ours));
+            }
+        }
+        caveats
+    }
+
+    /// Runtime accounting differences between the clusters the two runs were
+    /// measured on, which can move CU without any change to the program.
+    pub fn accounting_caveats(&self, other: &CuReport) -> Vec<String> {
+        match (&self.accounting, &other.accounting) {
+            (Some(ours), Some(theirs)) => ours.differences(theirs),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Machine and network differences between the two runs. They do not move
+    /// CU, but they do move latencies and landing behavior.
+    pub fn environment_caveats(&self, other: &CuReport) -> Vec<String> {
+        match (&self.calibration, &other.calibration) {
+            (Some(ours), Some(theirs)) => ours.differences(theirs),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for CuReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let run = &self.run;
+        writeln!(f, "Program {}", run.program_id)?;
+        if self
+            .deployment
+            .as_ref()
+            .is_some_and(ProgramFingerprint::is_immutable)
+        {
+            writeln!(f, "Program is immutable (no upgrade authority)")?;
+        }
+        for change in &self.deployment_changes {
+            writeln!(f, "WARNING: {} during the run", change)?;
+        }
+        if let Some(accounting) = &self.accounting {
+            writeln!(f, "Runtime: {}", accounting)?;
+        }
+        if let Some(calibration) = &self.calibration {
+            writeln!(f, "Environment: {}", calibration)?;
+        }
+        if let Some(info) = &self.program_size {
+            writeln!(f, "Program size: {}", info)?;
+        }
+        writeln!(
+            f,
+            "Steady-state samples: {} of {}",
+            self.steady_state_compute_units().len(),
+            run.len()
+        )?;
+        writeln!(
+            f,
+            "Compute units, all landed: {}",
+            self.compute_unit_stats(&TagFilter::all())
+        )?;
+        writeln!(
+            f,
+            "Compute units, steady state: {}",
+            self.compute_unit_stats(&TagFilter::steady_state())
+        )?;
+        if let Some(headroom) = self.headroom(&BlockLimits::default()) {
+            writeln!(f, "Headroom at median: {}", headroom)?;
+        }
+        let sections = self.section_compute_units();
+        if !sections.is_empty() {
+            let sections: Vec<String> = sections
+                .iter()
+                .map(|(name, cu)| format!("{} {:.0}", name, cu))
+                .collect();
+            writeln!(f, "Median CU by section: {}", sections.join(", "))?;
+        }
+        let savings = self.bump_cache_savings();
+        if !savings.is_empty() {
+            let savings: Vec<String> = savings
+                .iter()
+                .map(|(config, cu)| format!("{} bytes {:.0}", config, cu))
+                .collect();
+            writeln!(
+                f,
+                "Median CU saved by a cached PDA bump: {}",
+                savings.join(", ")
+            )?;
+        }
+        if let Some(fit) = &self.burn_fit {
+            writeln!(
+                f,
+                "Marginal CU per burn iteration: {:.3} (95% CI {:.3}..{:.3}, fixed {:.0}, r² {:.4})",
+                fit.slope, fit.slope_ci.0, fit.slope_ci.1, fit.intercept, fit.r_squared
+            )?;
+        }
+
+        let flows: Vec<String> = self
+            .lamport_flows()
+            .iter()
+            .map(LamportFlow::to_string)
+            .collect();
+        if !flows.is_empty() {
+            writeln!(f, "Net lamport change by account: {}", flows.join(", "))?;
+        }
+
+        let mut discrepancies: Vec<i64> =
+            run.samples().filter_map(Sample::cu_discrepancy).collect();
+        discrepancies.sort_unstable();
+        if let (Some(min), Some(max)) = (discrepancies.first(), discrepancies.last()) {
+            writeln!(
+                f,
+                "Executed - simulated CU over {} samples: min {}, median {}, max {} ({} differ)",
+                discrepancies.len(),
+                min,
+                discrepancies[discrepancies.len() / 2],
+                max,
+                discrepancies.iter().filter(|d| **d != 0).count()
+            )?;
+        }
+
+        if !self.attribution_gaps.is_empty() {
+            let differing: Vec<i64> = self
+                .attribution_gaps
+                .iter()
+                .map(|(_, gap)| *gap)
+                .filter(|gap| *gap != 0)
+                .collect();
+            writeln!(
+                f,
+                "Per-program CU from logs differs from the transaction total in {} of {} samples (largest gap {})",
+                differing.len(),
+                self.attribution_gaps.len(),
+                differing.iter().max_by_key(|gap| gap.unsigned_abs()).unwrap_or(&0)
+            )?;
+        }
+
+        if let Some(check) = ModelCheck::new(run) {
+            writeln!(f, "Syscall cost model: {}", check)?;
+        }
+
+        if let Some(tree) = &self.invocation_tree {
+            writeln!(f, "CU by invocation for {}", tree)?;
+        }
+
+        let mut landed_slots: Vec<u64> = run.samples().map(|sample| sample.slot).collect();
+        landed_slots.sort_unstable();
+        landed_slots.dedup();
+        writeln!(
+            f,
+            "Samples landed across {} distinct slots",
+            landed_slots.len()
+        )?;
+        writeln!(f, "Per-leader results:{}", self.leaders)?;
+        if let Some(unique) = self.guard_unique_nonces {
+            writeln!(
+                f,
+                "Replay guard recorded {} unique nonces for {} landed samples",
+                unique,
+                run.len()
+            )?;
+        }
+        if let Some(mean) = PhaseTimings::mean(run.samples().map(|sample| &sample.timings)) {
+            writeln!(
+                f,
+                "Mean phase latency: build {:?}, sign {:?}, send {:?}, land {:?}, confirm-detect {:?}",
+                mean.build, mean.sign, mean.send_rpc, mean.land, mean.confirm_detect
+            )?;
+        }
+        for outcome in &self.slos {
+            writeln!(f, "SLO {}", outcome)?;
+        }
+        write!(f, "Failures: {}", run.failures)?;
+        let mut failed_instructions = BTreeMap::new();
+        for failure in &run.instruction_failures {
+            *failed_instructions
+                .entry((
+                    failure.instruction_index,
+                    &failure.instruction,
+                    failure.error.to_string(),
+                ))
+                .or_insert(0) += 1;
+        }
+        for ((index, instruction, error), count) in failed_instructions {
+            write!(
+                f,
+                "\n  instruction {} ({}): {} x{}",
+                index, instruction, error, count
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Drives a [`BenchConfig`] against a cluster.
+pub struct BenchRunner {
+    config: BenchConfig,
+    registry: WorkloadRegistry,
+    metrics: MetricRegistry,
+    payer: Option<Keypair>,
+}
+
+impl BenchRunner {
+    /// Runner over [`BenchConfig::workload_registry`].
+    pub fn new(config: BenchConfig) -> Self {
+        let registry = config.workload_registry();
+        Self {
+            config,
+            registry,
+            metrics: MetricRegistry::new(),
+            payer: None,
+        }
+    }
+
+    /// Looks workloads up in `registry` instead of the built-in one.
+    pub fn with_registry(mut self, registry: WorkloadRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Runs the extractors of `metrics` on every landed transaction of an RPC
+    /// run, after the built-in ones.
+    pub fn with_metrics(mut self, metrics: MetricRegistry) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Pays with `payer` instead of a fresh keypair funded by airdrop.
+    pub fn with_payer(mut self, payer: Keypair) -> Self {
+        self.payer = Some(payer);
+        self
+    }
+
+    pub fn config(&self) -> &BenchConfig {
+        &self.config
+    }
+
+    /// Runs each of `workloads` against the configured program and then
+    /// against `after_program`, and diffs the results.
+    pub fn compare(
+        &self,
+        after_program: Pubkey,
+        workloads: &[String],
+    ) -> Result<CuDiff, Box<dyn Error>> {
+        let mut pairs = Vec::new();
+        for workload in workloads {
+            let before = BenchConfig {
+                workload: workload.clone(),
+                ..self.config.clone()
+            };
+            let after = BenchConfig {
+                program_id: after_program,
+                ..before.clone()
+            };
+            pairs.push((
+                workload.clone(),
+                self.run_with(&before)?,
+                self.run_with(&after)?,
+            ));
+        }
+        CuDiff::new(&pairs).ok_or_else(|| "no workloads to compare".into())
+    }
+
+    /// Runs `workloads` without any compute budget instructions, then with
+    /// the configured `compute_unit_limit` and `compute_unit_price`, to see
+    /// whether requesting them changes measured CU.
+    pub fn compute_budget_effect(&self, workloads: &[String]) -> Result<CuDiff, Box<dyn Error>> {
+        let budget = self
+            .config
+            .compute_budget()
+            .ok_or("no compute unit limit or price is configured")?;
+        let mut pairs = Vec::new();
+        for workload in workloads {
+            let with = BenchConfig {
+                workload: workload.clone(),
+                ..self.config.clone()
+            };
+            let without = BenchConfig {
+                compute_unit_limit: None,
+                compute_unit_price: None,
+                ..with.clone()
+            };
+            pairs.push((
+                workload.clone(),
+                self.run_with(&without)?,
+                self.run_with(&with)?,
+            ));
+        }
+        let mut diff = CuDiff::new(&pairs).ok_or("no workloads to compare")?;
+        diff.variant = Some(format!("compute budget ({})", budget));
+        Ok(diff)
+    }
+
+    /// Runs `workloads` with legacy transactions, then with v0 transactions
+    /// and a lookup table, to compare CU and transaction size.
+    pub fn lookup_table_effect(&self, workloads: &[String]) -> Result<CuDiff, Box<dyn Error>> {
+        let mut pairs = Vec::new();
+        for workload in workloads {
+            let legacy = BenchConfig {
+                workload: workload.clone(),
+                lookup_table: false,
+                ..self.config.clone()
+            };
+            let v0 = BenchConfig {
+                lookup_table: true,
+                ..legacy.clone()
+            };
+            pairs.push((
+                workload.clone(),
+                self.run_with(&legacy)?,
+                self.run_with(&v0)?,
+            ));
+        }
+        let mut diff = CuDiff::new(&pairs).ok_or("no workloads to compare")?;
+        diff.variant = Some("v0 messages and a lookup table".to_string());
+        Ok(diff)
+    }
+
+    /// Runs each of `workloads` against each of `programs`. Runs that fail
+    /// are recorded in their cell instead of ending the matrix.
+    pub fn matrix(&self, programs: &[Pubkey], workloads: &[String]) -> MatrixReport {
+        let mut report = MatrixReport {
+            programs: programs.to_vec(),
+            workloads: workloads.to_vec(),
+            cells: Vec::new(),
+        };
+        for program_id in programs {
+            for workload in workloads {
+                let config = BenchConfig {
+                    program_id: *program_id,
+                    workload: workload.clone(),
+                    ..self.config.clone()
+                };
+                let outcome = self.run_with(&config).map_err(|e| e.to_string());
+                if let Err(e) = &outcome {
+                    warn!(
+                        "{} on {} failed, continuing with the matrix: {}",
+                        workload, program_id, e
+                    );
+                }
+                report.cells.push(MatrixCell {
+                    program_id: *program_id,
+                    workload: workload.clone(),
+                    outcome,
+                });
+            }
+        }
+        report
+    }
+
+    #[cfg(feature = "program-test")]
+    fn run_in_process(&self, config: &BenchConfig) -> Result<CuReport, Box<dyn Error>> {
+        crate::banks::run_in_process(config, &self.registry)
+    }
+
+    #[cfg(not(feature = "program-test"))]
+    fn run_in_process(&self, _config: &BenchConfig) -> Result<CuReport, Box<dyn Error>> {
+        Err("in-process runs need the program-test feature".into())
+    }
+
+    /// Failures of the RPC helpers surface as a boxed [`BenchError`], which
+    /// callers can match on after `downcast_ref`.
+    ///
+    /// With `simulate_only`, every transaction goes through
+    /// `simulateTransaction`, without signature checks and on the node's
+    /// latest blockhash, instead of landing: CU comes back in one round trip
+    /// per sample and no fees are paid. The payer must still exist, as
+    /// simulation checks it can pay the fee, so it is funded as usual.
+    /// Samples carry no signature, block time or metrics.
+    pub fn run(&self) -> Result<CuReport, Box<dyn Error>> {
+        self.run_with(&self.config)
+    }
+
+    fn run_with(&self, config: &BenchConfig) -> Result<CuReport, Box<dyn Error>> {
+        config.check(self.payer.is_some() || config.payer_keypair.is_some())?;
+        if config.in_process {
+            return self.run_in_process(config);
+        }
+        let rpc_client = RpcClient::new_with_commitment(config.rpc_url.clone(), config.commitment);
+        let archival_client = config
+            .archival_rpc_url
+            .as_ref()
+            .map(|url| RpcClient::new_with_commitment(url.clone(), config.commitment));
+        let program_id = config.program_id;
+        let _run_span = info_span!("run", %program_id).entered();
+
+        // Latency numbers and timestamps are misleading on a skewed clock
+        let clock = match ClockCheck::measure(&rpc_client) {
+            Ok(check) => {
+                if check.exceeds(DEFAULT_DRIFT_THRESHOLD) {
+                    warn!(
+                        "Local clock differs from block time by {}s at slot {}",
+                        check.drift_secs, check.slot
+                    );
+                }
+                Some(check)
+            }
+            Err(e) => {
+                warn!("Failed to check clock drift: {}", e);
+                None
+            }
+        };
+
+        let accounting = match RuntimeAccounting::detect(&rpc_client) {
+            Ok(accounting) => Some(accounting),
+            Err(e) => {
+                warn!("Failed to detect runtime accounting: {}", e);
+                None
+            }
+        };
+
+        let calibration = info_span!("calibrate").in_scope(|| Calibration::measure(&rpc_client));
+
+        let program_size = match get_program_size(&rpc_client, &program_id.to_string()) {
+            Ok(sizes) => Some(sizes),
+            Err(e) => {
+                warn!("Failed to get program size: {}", e);
+                None
+            }
+        };
+
+        let deployment = match ProgramFingerprint::fetch(&rpc_client, &program_id) {
+            Ok(fingerprint) => Some(fingerprint),
+            Err(e) => {
+                warn!("Failed to fingerprint program: {}", e);
+                None
+            }
+        };
+
+        let _lock = ExperimentLock::acquire(&program_id, config.force_lock)?;
+        let mut phases = PhaseClock::new(
+            config.deadlines.clone(),
+            config.diagnostics_dir.clone(),
+            program_id,
+        );
+        // Polling, fetches and sends are paced; one-off queries are not
+        let mut requests = Pacer::per_second(config.rate_limits.requests_per_second);
+        let mut sends = Pacer::new(config.rate_limits.send_interval);
+        let funded;
+        phases.enter(Phase::Funding);
+        let payer = match (&self.payer, &config.payer_keypair) {
+            (Some(payer), _) => {
+                top_up(&rpc_client, payer, config, &mut phases, &mut requests)?;
+                payer
+            }
+            (None, Some(source)) => {
+                funded = read_signer(source)?;
+                top_up(&rpc_client, &funded, config, &mut phases, &mut requests)?;
+                &funded
+            }
+            (None, None) => {
+                funded = Keypair::new();
+                airdrop(
+                    &rpc_client,
+                    &funded,
+                    config.airdrop_lamports,
+                    &mut phases,
+                    &mut requests,
+                )?;
+                &funded
+            }
+        };
+
+        let ctx = WorkloadContext {
+            rpc_client: &rpc_client,
+            payer,
+            program_id,
+        };
+        let workload = self
+            .registry
+            .create(&config.workload)
+            .ok_or_else(|| BenchError::UnknownWorkload(config.workload.clone()))?;
+        // Setup blocks, so an overrun is only noticed once it returns. Any
+        // failure from here on tears the workload down as it is dropped
+        phases.enter(Phase::Deploy);
+        let mut workload = ActiveWorkload::setup(workload, &ctx)?;
+        phases.check(&rpc_client, [])?;
+
+        // Blockhash is kept fresh in the background for the whole send phase
+        let blockhash_cache = BlockhashCache::start(&rpc_client)?;
+
+        let mut lookup_table = if config.lookup_table {
+            Some(LookupTable::create(&rpc_client, &blockhash_cache, payer)?)
+        } else {
+            None
+        };
+
+        let mut slot_window = config.slot_window_batch.map(SlotWindow::new);
+
+        // Leaders for the whole run, used to report per-leader figures
+        let leader_schedule = LeaderSchedule::fetch(&rpc_client, 5000)
+            .map_err(|e| warn!("Failed to fetch leader schedule: {}", e))
+            .ok();
+        let mut leader_gate = config
+            .leader_targeting
+            .zip(leader_schedule.clone())
+            .map(|(targeting, schedule)| LeaderGate::new(schedule, targeting));
+
+        let mut run = Run::new(program_id);
+        let mut pending = Vec::new();
+        let mut log_mismatches = Vec::new();
+        let mut invocation_tree = None;
+        let bench_programs: Vec<Pubkey> = std::iter::once(program_id)
+            .chain(config.extra_program_ids.iter().copied())
+            .collect();
+
+        // Send all transactions first
+        let send_phase = info_span!("send").entered();
+        phases.enter(Phase::Send);
+        let mut i: u64 = 0;
+        while let Some(next) = workload.next_transaction() {
+            phases.check(
+                &rpc_client,
+                pending
+                    .iter()
+                    .map(|sample: &PendingSample| sample.signature),
+            )?;
+            if config.stop.is_requested() {
+                info!("Stop requested, no longer sending after {} transactions", i);
+                break;
+            }
+            let _sample_span = info_span!("sample", index = i).entered();
+
+            let mut timings = PhaseTimings::default();
+
+            let instructions = config.with_compute_budget(&next.instructions);
+            if let Some(table) = lookup_table.as_mut() {
+                let _extend_span = info_span!("extend_lookup_table").entered();
+                table.extend_for(&rpc_client, &blockhash_cache, payer, &instructions)?;
+            }
+
+            let started = Instant::now();
+            let message = info_span!("build").in_scope(|| {
+                let (recent_blockhash, _last_valid_block_height) = blockhash_cache.get();
+                Ok::<_, Box<dyn Error>>(match &lookup_table {
+                    Some(table) => VersionedMessage::V0(v0::Message::try_compile(
+                        &payer.pubkey(),
+                        &instructions,
+                        &[table.account()],
+                        recent_blockhash,
+                    )?),
+                    None => VersionedMessage::Legacy(Message::new_with_blockhash(
+                        &instructions,
+                        Some(&payer.pubkey()),
+                        &recent_blockhash,
+                    )),
+                })
+            })?;
+            timings.build = started.elapsed();
+
+            let started = Instant::now();
+            let transaction =
+                info_span!("sign").in_scope(|| VersionedTransaction::try_new(message, &[payer]))?;
+            timings.sign = started.elapsed();
+            let transaction_size = bincode::serialized_size(&transaction)
+                .ok()
+                .map(|size| size as usize);
+
+            if config.simulate_only {
+                let started = Instant::now();
+                let simulated = {
+                    let _simulate_span = info_span!("simulate").entered();
+                    requests.wait();
+                    rpc_client.simulate_transaction_with_config(
+                        &transaction,
+                        RpcSimulateTransactionConfig {
+                            sig_verify: false,
+                            replace_recent_blockhash: true,
+                            commitment: Some(config.commitment),
+                            ..RpcSimulateTransactionConfig::default()
+                        },
+                    )
+                };
+                timings.send_rpc = started.elapsed();
+
+                match simulated {
+                    Ok(response) => {
+                        let result = response.value;
+                        let logs = result.logs.unwrap_or_default();
+                        info!(
+                            "Transaction {} simulated: {:?} CU",
+                            i, result.units_consumed
+                        );
+                        if let Some(err) = result.err {
+                            run.failures
+                                .record(FailureClass::from_transaction_error(&err));
+                            let instructions: Vec<String> = next
+                                .instructions
+                                .iter()
+                                .map(|instruction| {
+                                    describe_instruction(instruction, &bench_programs)
+                                })
+                                .collect();
+                            match InstructionFailure::locate(
+                                i,
+                                Signature::default(),
+                                &err,
+                                &instructions,
+                            ) {
+                                Some(failure) => {
+                                    warn!("{}", failure);
+                                    run.instruction_failures.push(failure);
+                                }
+                                None => warn!("Transaction {} failed: {}", i + 1, err),
+                            }
+                        }
+                        if let Some(expected_log) = next.expected_log {
+                            if !logs.iter().any(|line| line.contains(&expected_log)) {
+                                warn!("Transaction {} is missing log {:?}", i + 1, expected_log);
+                                log_mismatches.push(i);
+                            }
+                        }
+                        let invocations = parse_invocations(logs.iter().map(String::as_str));
+                        let mut sample = Sample::new(
+                            i,
+                            Signature::default(),
+                            response.context.slot,
+                            result.units_consumed,
+                        );
+                        sample.log_compute_units =
+                            LogAttribution::from_invocations(&invocations).metered_total();
+                        sample.transaction_size = transaction_size;
+                        sample.instruction =
+                            label_instructions(&next.instructions, &bench_programs);
+                        sample.syscall_compute_units =
+                            predict_instructions(&next.instructions, &bench_programs);
+                        sample.timings = timings;
+                        run.push(sample);
+                        let tree = InvocationTree::new(i, invocations, None);
+                        if tree.has_cpis() {
+                            invocation_tree = Some(tree);
+                        }
+                    }
+                    Err(e) => {
+                        let message = e.to_string();
+                        warn!("Failed to simulate transaction {}: {}", i, message);
+                        phases.record_error(message);
+                        run.failures.record(FailureClass::from_client_error(&e));
+                    }
+                }
+                i += 1;
+                continue;
+            }
+
+            let simulated_cu = if config.simulate_before_send {
+                let _simulate_span = info_span!("simulate").entered();
+                requests.wait();
+                match rpc_client.simulate_transaction(&transaction) {
+                    Ok(response) => response.value.units_consumed,
+                    Err(e) => {
+                        warn!("Failed to simulate transaction {}: {}", i, e);
+                        phases.record_error(e.to_string());
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+
+            if let Some(window) = slot_window.as_mut() {
+                if let Err(e) = window.wait_turn(&rpc_client) {
+                    warn!("Failed to align with slot boundary: {}", e);
+                }
+            }
+
+            if let Some(gate) = leader_gate.as_mut() {
+                if let Err(e) = gate.wait_turn(&rpc_client, Duration::from_secs(60)) {
+                    warn!("Failed to wait for target leader: {}", e);
+                }
+            }
+
+            sends.wait();
+            let started = Instant::now();
+            let sent = {
+                let _send_span = info_span!("send_rpc").entered();
+                rpc_client.send_transaction(&transaction)
+            };
+            timings.send_rpc = started.elapsed();
+
+            match sent {
+                Ok(signature) => {
+                    info!("Transaction {} sent: {}", i, signature);
+                    pending.push(PendingSample {
+                        index: i,
+                        signature,
+                        expected_log: next.expected_log,
+                        instructions: next
+                            .instructions
+                            .iter()
+                            .map(|instruction| describe_instruction(instruction, &bench_programs))
+                            .collect(),
+                        label: label_instructions(&next.instructions, &bench_programs),
+                        predicted_cu: predict_instructions(&next.instructions, &bench_programs),
+                        simulated_cu,
+                        transaction_size,
+                        timings,
+                        sent_at: Instant::now(),
+                        slot_at_send: rpc_client
+                            .get_slot_with_commitment(CommitmentConfig::processed())
+                            .ok(),
+                        confirmed: None,
+                    });
+                }
+                Err(e) => {
+                    let message = e.to_string();
+                    warn!("Failed to send transaction {}: {}", i, message);
+                    phases.record_error(message);
+                    run.failures.record(FailureClass::from_client_error(&e));
+                }
+            }
+            i += 1;
+        }
+        run.attempted = i;
+        drop(send_phase);
+        drop(blockhash_cache);
+
+        info!("All transactions sent, waiting for confirmations...");
+        phases.enter(Phase::Confirm);
+        info_span!("confirm").in_scope(|| {
+            detect_confirmations(
+                &rpc_client,
+                &mut pending,
+                config.confirm_timeout,
+                &mut phases,
+                &mut requests,
+            )
+        })?;
+
+        info!("Now verifying...");
+        let verify_phase = info_span!("verify").entered();
+
+        let mut metrics = MetricRegistry::new();
+        metrics
+            .register(Fee)
+            .register(Attribution)
+            .register(BumpCacheSavings)
+            .register(LogValue::new("count", "Count: "))
+            .register(LogValue::new("iterations", "Iterations: "));
+        let mut burn_points = (Vec::new(), Vec::new());
+        let mut leaders = LeaderReport::default();
+        let mut attribution_gaps = Vec::new();
+
+        phases.enter(Phase::Fetch);
+        let signatures: Vec<Signature> = pending.iter().map(|sample| sample.signature).collect();
+        for (
+            position,
+            PendingSample {
+                index: i,
+                signature,
+                expected_log,
+                instructions,
+                label,
+                predicted_cu,
+                simulated_cu,
+                transaction_size,
+                mut timings,
+                sent_at,
+                slot_at_send,
+                confirmed,
+            },
+        ) in pending.into_iter().enumerate()
+        {
+            phases.check(&rpc_client, signatures[position..].iter().copied())?;
+            let _sample_span = info_span!("sample", index = i).entered();
+            requests.wait();
+            let tx_details =
+                get_transaction_with_fallback(&rpc_client, archival_client.as_ref(), &signature);
+            let Some(tx) = tx_details.map(ConfirmedTxView::from) else {
+                phases.record_error(format!("Transaction {} could not be fetched", signature));
+                run.failures.record(FailureClass::NotLanded);
+                continue;
+            };
+
+            if let Some(err) = tx.err() {
+                run.failures
+                    .record(FailureClass::from_transaction_error(&err));
+                match InstructionFailure::locate(i, signature, &err, &instructions) {
+                    Some(failure) => {
+                        warn!("{}", failure);
+                        run.instruction_failures.push(failure);
+                    }
+                    None => warn!("Transaction {} failed: {}", i + 1, err),
+                }
+            }
+
+            if let Some(expected_log) = expected_log {
+                if !tx.logs().any(|line| line.contains(&expected_log)) {
+                    warn!("Transaction {} is missing log {:?}", i + 1, expected_log);
+                    log_mismatches.push(i);
+                }
+            }
+
+            match (tx.meta_cu_consumed(), tx.log_cu_consumed()) {
+                (Some(cu), _) => info!(
+                    "Transaction {} (counter: {}): Compute Units used: {}",
+                    i + 1,
+                    i,
+                    cu
+                ),
+                (None, Some(cu)) => warn!(
+                    "Transaction {}: Compute units missing from meta, {} from logs",
+                    i + 1,
+                    cu
+                ),
+                (None, None) => warn!("Transaction {}: Compute units not available", i + 1),
+            }
+
+            let instructions = parse_invocations(tx.logs());
+            let inner_counts = tx.inner_instruction_counts(instructions.len());
+            let tree = InvocationTree::new(i, instructions, inner_counts.as_deref());
+            if tree.has_cpis() {
+                invocation_tree = Some(tree);
+            }
+
+            let mut tx_metrics = metrics.extract(&tx);
+            tx_metrics.extend(self.metrics.extract(&tx));
+            for metric in &tx_metrics {
+                info!("Transaction {}: {} = {}", i + 1, metric.name, metric.value);
+                if let ("iterations", Some(cu)) = (metric.name.as_str(), tx.cu_consumed()) {
+                    burn_points.0.push(metric.value);
+                    burn_points.1.push(cu as f64);
+                }
+                if metric.name == "attribution_gap" {
+                    attribution_gaps.push((i, metric.value as i64));
+                }
+            }
+
+            if let Some(schedule) = &leader_schedule {
+                if let Some(leader) = slot_at_send.and_then(|slot| schedule.leader_at(slot)) {
+                    leaders.record_sent(leader);
+                }
+                if let Some(leader) = schedule.leader_at(tx.slot()) {
+                    leaders.record_landed(leader, tx.cu_consumed());
+                }
+            }
+
+            let mut sample = Sample::new(i, signature, tx.slot(), tx.cu_consumed());
+            sample.block_time = tx.block_time();
+            sample.log_compute_units = tx.log_cu_consumed();
+            sample.balance_deltas = tx.balance_deltas();
+            sample.simulated_compute_units = simulated_cu;
+            sample.transaction_size = transaction_size;
+            sample.instruction = label;
+            sample.syscall_compute_units = predicted_cu;
+            sample.metrics = tx_metrics;
+            if let Some((confirmed_at, landed_slot)) = confirmed {
+                let land = slot_at_send.map(|slot| {
+                    Duration::from_millis(landed_slot.saturating_sub(slot) * DEFAULT_MS_PER_SLOT)
+                });
+                timings.land = land;
+                timings.confirm_detect = Some(
+                    confirmed_at
+                        .duration_since(sent_at)
+                        .saturating_sub(land.unwrap_or_default()),
+                );
+            }
+            sample.timings = timings;
+            run.push(sample);
+        }
+        drop(verify_phase);
+
+        tag_warmup(run.samples_mut(), config.warmup_samples);
+        tag_outliers(run.samples_mut());
+        tag_congestion(run.samples_mut());
+        match rpc_client.get_epoch_schedule() {
+            Ok(schedule) => tag_epoch_boundaries(run.samples_mut(), &schedule),
+            Err(e) => warn!("Failed to fetch the epoch schedule: {}", e),
+        }
+
+        let (guard_address, _) = find_guard_address(&program_id, &payer.pubkey());
+        let guard_unique_nonces = rpc_client
+            .get_account(&guard_address)
+            .ok()
+            .map(|guard| seen_count(&guard.data));
+
+        let deployment_changes = deployment
+            .as_ref()
+            .zip(ProgramFingerprint::fetch(&rpc_client, &program_id).ok())
+            .map(|(before, after)| after.changes_since(before))
+            .unwrap_or_default();
+        for change in &deployment_changes {
+            warn!("Program {} during the run", change);
+        }
+
+        workload.teardown()?;
+
+        let slos = config.slos.iter().map(|slo| slo.evaluate(&run)).collect();
+        Ok(CuReport {
+            run,
+            payer: payer.pubkey(),
+            program_size,
+            clock,
+            accounting,
+            calibration: Some(calibration),
+            leaders,
+            log_mismatches,
+            burn_fit: linear_fit(&burn_points.0, &burn_points.1),
+            guard_unique_nonces,
+            attribution_gaps,
+            invocation_tree,
+            deployment,
+            deployment_changes,
+            slos,
+        })
+    }
+}
+
+/// Airdrops to `payer` when it holds less than `config.min_payer_balance`.
+/// Clusters without a faucet make that fail, with a message saying how much
+/// the payer is short.
+fn top_up(
+    rpc_client: &RpcClient,
+    payer: &Keypair,
+    config: &BenchConfig,
+    phases: &mut PhaseClock,
+    requests: &mut Pacer,
+) -> Result<(), Box<dyn Error>> {
+    let balance = rpc_client.get_balance(&payer.pubkey())?;
+    if balance >= config.min_payer_balance {
+        info!("Paying from {} (balance {})", payer.pubkey(), balance);
+        return Ok(());
+    }
+    warn!(
+        "Payer {} holds {} lamports, below {}; falling back to an airdrop",
+        payer.pubkey(),
+        balance,
+        config.min_payer_balance
+    );
+    airdrop(rpc_client, payer, config.airdrop_lamports, phases, requests).map_err(|e| {
+        format!(
+            "payer {} holds {} of the {} lamports needed and the airdrop failed: {}",
+            payer.pubkey(),
+            balance,
+            config.min_payer_balance,
+            e
+        )
+        .into()
+    })
+}
+
+fn airdrop(
+    rpc_client: &RpcClient,
+    payer: &Keypair,
+    lamports: u64,
+    phases: &mut PhaseClock,
+    requests: &mut Pacer,
+) -> Result<(), BenchError> {
+    info!("Requesting airdrop for {}", payer.pubkey());
+    let before = rpc_client.get_balance(&payer.pubkey())?;
+    let airdrop_signature = rpc_client.request_airdrop(&payer.pubkey(), lamports)?;
+
+    loop {
+        requests.wait();
+        match rpc_client.confirm_transaction(&airdrop_signature) {
+            Ok(_) => {
+                requests.wait();
+                if let Ok(balance) = rpc_client.get_balance(&payer.pubkey()) {
+                    if balance > before {
+                        info!("Airdrop confirmed! Balance: {}", balance);
+                        return Ok(());
+                    }
+                }
+            }
+            Err(e) => phases.record_error(e.to_string()),
+        }
+        phases.check(rpc_client, [airdrop_signature])?;
+        sleep(Duration::from_millis(100));
+    }
+}
+
+fn get_transaction_with_retries(
+    rpc_client: &RpcClient,
+    signature: &Signature,
+    mut retries: u32,
+) -> Option<EncodedConfirmedTransactionWithStatusMeta> {
+    while retries > 0 {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(rpc_client.commitment()),
+            max_supported_transaction_version: Some(0),
+        };
+        match rpc_client.get_transaction_with_config(signature, config) {
+            Ok(details) => return Some(details),
+            Err(_) => {
+                sleep(Duration::from_millis(50));
+                retries -= 1;
+            }
+        }
+    }
+    None
+}
+
+/// Fetches a confirmed transaction, falling back to the archival endpoint when
+/// the primary node no longer has it (e.g. history pruned mid-run).
+pub fn get_transaction_with_fallback(
+    rpc_client: &RpcClient,
+    archival_client: Option<&RpcClient>,
+    signature: &Signature,
+) -> Option<EncodedConfirmedTransactionWithStatusMeta> {
+    if let Some(details) = get_transaction_with_retries(rpc_client, signature, 10) {
+        return Some(details);
+    }
+
+    let archival_client = archival_client?;
+    if let Ok(first_available) = rpc_client.minimum_ledger_slot() {
+        info!(
+            "Transaction {} not found (primary ledger starts at slot {}), trying archival RPC",
+            signature, first_available
+        );
+    }
+    get_transaction_with_retries(archival_client, signature, 10)
+}
+
+/// A sent transaction waiting to be confirmed and verified.
+struct PendingSample {
+    index: u64,
+    signature: Signature,
+    expected_log: Option<String>,
+    /// [`describe_instruction`] of each instruction sent.
+    instructions: Vec<String>,
+    /// [`label_instructions`] of the instructions sent.
+    label: Option<String>,
+    /// [`predict_instructions`] of the instructions sent.
+    predicted_cu: Option<u64>,
+    simulated_cu: Option<u64>,
+    transaction_size: Option<usize>,
+    timings: PhaseTimings,
+    sent_at: Instant,
+    slot_at_send: Option<u64>,
+    /// When the confirmation was first observed, and the slot it landed in.
+    confirmed: Option<(Instant, u64)>,
+}
+
+/// Polls signature statuses until every pending transaction is confirmed or
+/// `timeout` elapses, recording when each confirmation was first observed.
+/// Fails if the confirm phase overruns its deadline first.
+fn detect_confirmations(
+    rpc_client: &RpcClient,
+    pending: &mut [PendingSample],
+    timeout: Duration,
+    phases: &mut PhaseClock,
+    requests: &mut Pacer,
+) -> Result<(), Box<dyn Error>> {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        let mut unconfirmed: Vec<&mut PendingSample> = pending
+            .iter_mut()
+            .filter(|sample| sample.confirmed.is_none())
+            .collect();
+        if unconfirmed.is_empty() {
+            break;
+        }
+        phases.check(
+            rpc_client,
+            unconfirmed.iter().map(|sample| sample.signature),
+        )?;
+
+        for chunk in unconfirmed.chunks_mut(256) {
+            let signatures: Vec<Signature> = chunk.iter().map(|sample| sample.signature).collect();
+            requests.wait();
+            let statuses = match rpc_client.get_signature_statuses(&signatures) {
+                Ok(statuses) => statuses,
+                Err(e) => {
+                    phases.record_error(e.to_string());
+                    continue;
+                }
+            };
+            let observed_at = Instant::now();
+            for (sample, status) in chunk.iter_mut().zip(statuses.value) {
+                if let Some(status) =
+                    status.filter(|status| status.satisfies_commitment(rpc_client.commitment()))
+                {
+                    sample.confirmed = Some((observed_at, status.slot));
+                }
+            }
+        }
+        sleep(Duration::from_millis(100));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_config_from_env_overrides_defaults() {
+        let config = BenchConfig::from_lookup(|name| match name {
+            "SLOT_WINDOW_BATCH" => Some("8".to_string()),
+            "LEADER_TARGET" => Some("spread:3".to_string()),
+            "EXTRA_PROGRAM_IDS" => Some("11111111111111111111111111111111, ".to_string()),
+            "SOLANA_PAYER" => Some("~/.config/solana/id.json".to_string()),
+            "CLUSTER" => Some("devnet".to_string()),
+            "SEND_INTERVAL_MS" => Some("500".to_string()),
+            "COMPUTE_UNIT_LIMIT" => Some("50000".to_string()),
+            "COMPUTE_UNIT_PRICE" => Some("1000".to_string()),
+            "SLOS" => Some("p99_cu < 5000, landing_rate > 99%".to_string()),
+            _ => None,
+        })
+        .unwrap();
+
+        assert_eq!(config.slot_window_batch, Some(8));
+        let slos: Vec<String> = config.slos.iter().map(Slo::to_string).collect();
+        assert_eq!(slos, ["p99_cu < 5000", "landing_rate > 99%"]);
+        assert_eq!(
+            config.leader_targeting,
+            Some(LeaderTargeting::Spread { per_leader: 3 })
+        );
+        assert_eq!(config.program_id, BenchConfig::default().program_id);
+        assert_eq!(config.extra_program_ids, [Pubkey::default()]);
+        assert_eq!(config.rpc_url, Cluster::Devnet.rpc_url().unwrap());
+        assert_eq!(
+            config.rate_limits,
+            RateLimits {
+                send_interval: Some(Duration::from_millis(500)),
+                ..Cluster::Devnet.rate_limits()
+            }
+        );
+        assert_eq!(
+            config.payer_keypair.as_deref(),
+            Some("~/.config/solana/id.json")
+        );
+        assert_eq!(
+            config.min_payer_balance,
+            BenchConfig::default().min_payer_balance
+        );
+
+        // The configured limit replaces the workload's own
+        let workload_ix = crate::instruction::BenchInstruction::LogCounter { counter: 1 }
+            .to_instruction(config.program_id, vec![]);
+        let budgeted =
+            config.with_compute_budget(&[set_compute_unit_limit(400_000), workload_ix.clone()]);
+        assert_eq!(
+            budgeted,
+            [
+                set_compute_unit_limit(50_000),
+                set_compute_unit_price(1_000),
+                workload_ix
+            ]
+        );
+    }
+
+    #[test]
+    fn test_env_overrides_config_file() {
+        let file = ConfigFile::parse_yaml(
+            "commitment: finalized\n\
+             workload: sha256\n\
+             workloads: [counter, sha256]\n\
+             iterations: 7\n\
+             force_lock: false\n\
+             simulate_only: true\n\
+             output_format: json\n",
+        )
+        .unwrap();
+        let config = BenchConfig::from_file_and_lookup(&file, |name| match name {
+            "WORKLOAD" => Some("counter".to_string()),
+            _ => None,
+        })
+        .unwrap();
+
+        assert_eq!(config.commitment, CommitmentConfig::finalized());
+        assert_eq!(config.workload, "counter");
+        assert_eq!(config.workloads, ["counter", "sha256"]);
+        assert_eq!(config.iterations, Some(7));
+        assert!(!config.force_lock);
+        assert!(config.simulate_only);
+        assert_eq!(config.output_format, Some(ExportFormat::Json));
+        assert_eq!(config.rpc_url, BenchConfig::default().rpc_url);
+    }
+
+    #[test]
+    fn test_unparsable_settings_are_rejected() {
+        let file = ConfigFile::parse_yaml("program_id: H2GM7bogus\n").unwrap();
+        let e = BenchConfig::from_file_and_lookup(&file, |_| None).unwrap_err();
+        assert!(e
+            .to_string()
+            .starts_with("invalid PROGRAM_ID \"H2GM7bogus\""));
+
+        for (name, value) in [
+            ("COMMITMENT", "confirmd"),
+            (
+                "EXTRA_PROGRAM_IDS",
+                "11111111111111111111111111111111, bogus",
+            ),
+            ("PHASE_DEADLINES", "send=soon"),
+            ("LEADER_TARGET", "spread:many"),
+            ("ITERATIONS", "-1"),
+            ("SLOS", "p99 < 5000"),
+            ("FORCE_LOCK", "flase"),
+            ("SNAPSHOT_ACCOUNTS", "bogus"),
+        ] {
+            let lookup = |var: &str| (var == name).then(|| value.to_string());
+            let e = BenchConfig::from_lookup(lookup).unwrap_err();
+            assert!(e.to_string().contains(name), "{}", e);
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_conflicting_settings() {
+        assert!(BenchConfig::default().validate().is_ok());
+
+        let config = BenchConfig {
+            rpc_url: "https://api.mainnet-beta.solana.com".to_string(),
+            compute_unit_limit: Some(2_000_000),
+            ..BenchConfig::default()
+        };
+        let e = config.validate().unwrap_err();
+        assert_eq!(e.exit_code(), crate::error::EXIT_CONFIG);
+        let message = e.to_string();
+        assert!(message.contains("2 conflicting settings"), "{}", message);
+        assert!(message.contains("rpc_url \"https://api.mainnet-beta.solana.com\" has no faucet"));
+        assert!(message.contains("compute_unit_limit 2000000 is above"));
+
+        let config = BenchConfig {
+            payer_keypair: Some("payer.json".to_string()),
+            ..config
+        };
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .starts_with("compute_unit_limit"));
+
+        let config = BenchConfig {
+            in_process: true,
+            simulate_only: true,
+            ..BenchConfig::default()
+        };
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("simulate_only"));
+    }
+}
@@ -0,0 +1,78 @@
+//! Sanity check of the local wall clock against cluster block times, so skewed
+//! machines are caught before their timestamps end up in reports.
+
+use crate::sdk::{CommitmentConfig, RpcClient};
+use std::{
+    error::Error,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Drift tolerated before a run warns. Block times only have second resolution
+/// and trail the tip by a slot or two, so anything tighter is noise.
+pub const DEFAULT_DRIFT_THRESHOLD: Duration = Duration::from_secs(3);
+
+/// Local clock compared with the block time of the latest confirmed slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ClockCheck {
+    pub slot: u64,
+    /// Local time minus block time, in seconds. Positive when the local clock
+    /// runs ahead of the cluster.
+    pub drift_secs: i64,
+}
+
+impl ClockCheck {
+    pub fn measure(rpc_client: &RpcClient) -> Result<Self, Box<dyn Error>> {
+        let slot = rpc_client.get_slot_with_commitment(CommitmentConfig::confirmed())?;
+        let block_time = rpc_client.get_block_time(slot)?;
+        Ok(Self::from_observation(slot, block_time, SystemTime::now()))
+    }
+
+    pub fn from_observation(slot: u64, block_time: i64, local: SystemTime) -> Self {
+        let local_secs = match local.duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_secs() as i64,
+            Err(before) => -(before.duration().as_secs() as i64),
+        };
+        Self {
+            slot,
+            drift_secs: local_secs - block_time,
+        }
+    }
+
+    pub fn exceeds(&self, threshold: Duration) -> bool {
+        self.drift_secs.unsigned_abs() > threshold.as_secs()
+    }
+
+    /// Maps a local timestamp onto cluster time by removing the measured drift.
+    pub fn adjust(&self, local: SystemTime) -> SystemTime {
+        let drift = Duration::from_secs(self.drift_secs.unsigned_abs());
+        if self.drift_secs >= 0 {
+            local - drift
+        } else {
+            local + drift
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_drift_detection_and_adjustment() {
+        let block_time = 1_700_000_000;
+        let local = UNIX_EPOCH + Duration::from_secs(block_time as u64 + 10);
+
+        let check = ClockCheck::from_observation(42, block_time, local);
+        assert_eq!(check.drift_secs, 10);
+        assert!(check.exceeds(DEFAULT_DRIFT_THRESHOLD));
+        assert_eq!(
+            check.adjust(local),
+            UNIX_EPOCH + Duration::from_secs(block_time as u64)
+        );
+
+        let behind = ClockCheck::from_observation(42, block_time, local - Duration::from_secs(12));
+        assert_eq!(behind.drift_secs, -2);
+        assert!(!behind.exceeds(DEFAULT_DRIFT_THRESHOLD));
+    }
+}
@@ -0,0 +1,151 @@
+//! Cluster presets, and pacing of the RPC requests and sends of a run. Public
+//! endpoints rate-limit per IP (devnet and testnet allow about 100 requests
+//! per 10 seconds, 40 of them for any one method), so an unpaced run is
+//! throttled within its first batch of sends.
+
+use std::{
+    fmt,
+    str::FromStr,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cluster {
+    Localnet,
+    Devnet,
+    Testnet,
+    /// Whatever `rpc_url` points at, unthrottled unless limits are set.
+    Custom,
+}
+
+impl Cluster {
+    /// The public RPC endpoint, `None` for [`Cluster::Custom`].
+    pub fn rpc_url(&self) -> Option<&'static str> {
+        match self {
+            Self::Localnet => Some("http://127.0.0.1:8899"),
+            Self::Devnet => Some("https://api.devnet.solana.com"),
+            Self::Testnet => Some("https://api.testnet.solana.com"),
+            Self::Custom => None,
+        }
+    }
+
+    /// Limits that keep a run under the endpoint's rate limits, with some
+    /// headroom for the background blockhash refresh.
+    pub fn rate_limits(&self) -> RateLimits {
+        match self {
+            Self::Localnet | Self::Custom => RateLimits::default(),
+            Self::Devnet | Self::Testnet => RateLimits {
+                requests_per_second: Some(8.0),
+                send_interval: Some(Duration::from_millis(300)),
+            },
+        }
+    }
+}
+
+impl FromStr for Cluster {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "localnet" | "localhost" => Ok(Self::Localnet),
+            "devnet" => Ok(Self::Devnet),
+            "testnet" => Ok(Self::Testnet),
+            "custom" => Ok(Self::Custom),
+            _ => Err(format!(
+                "unknown cluster {:?}; expected localnet, devnet, testnet or custom",
+                name
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Localnet => "localnet",
+            Self::Devnet => "devnet",
+            Self::Testnet => "testnet",
+            Self::Custom => "custom",
+        })
+    }
+}
+
+/// How fast a run may talk to its RPC node; `None` is unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RateLimits {
+    /// Polling and fetch requests made by the run loops.
+    pub requests_per_second: Option<f64>,
+    /// Least time between two sent transactions.
+    pub send_interval: Option<Duration>,
+}
+
+/// Spaces calls to [`Pacer::wait`] at least an interval apart.
+#[derive(Debug, Clone)]
+pub struct Pacer {
+    interval: Option<Duration>,
+    next: Instant,
+}
+
+impl Pacer {
+    pub fn new(interval: Option<Duration>) -> Self {
+        Self {
+            interval,
+            next: Instant::now(),
+        }
+    }
+
+    /// Paces to `rate` calls per second; rates of zero or below are ignored.
+    pub fn per_second(rate: Option<f64>) -> Self {
+        Self::new(
+            rate.filter(|rate| *rate > 0.0)
+                .map(|rate| Duration::from_secs_f64(1.0 / rate)),
+        )
+    }
+
+    /// Blocks until the next call is due.
+    pub fn wait(&mut self) {
+        let Some(interval) = self.interval else {
+            return;
+        };
+        let now = Instant::now();
+        if self.next > now {
+            sleep(self.next - now);
+        }
+        self.next = self.next.max(now) + interval;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_presets_and_pacing() {
+        for cluster in [
+            Cluster::Localnet,
+            Cluster::Devnet,
+            Cluster::Testnet,
+            Cluster::Custom,
+        ] {
+            assert_eq!(cluster.to_string().parse(), Ok(cluster));
+        }
+        assert!("mainnet".parse::<Cluster>().is_err());
+        assert_eq!(Cluster::Localnet.rate_limits(), RateLimits::default());
+        assert!(Cluster::Devnet.rate_limits().send_interval.is_some());
+
+        let mut pacer = Pacer::new(Some(Duration::from_millis(5)));
+        let started = Instant::now();
+        for _ in 0..3 {
+            pacer.wait();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(10));
+
+        let mut unlimited = Pacer::per_second(Some(0.0));
+        let started = Instant::now();
+        for _ in 0..1_000 {
+            unlimited.wait();
+        }
+        assert!(started.elapsed() < Duration::from_millis(10));
+    }
+}
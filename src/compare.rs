@@ -0,0 +1,276 @@
+//! Before/after comparison of two deployments: the same workloads run against
+//! both, diffed per workload next to the change in program size. To compare
+//! two builds, deploy each under its own program id.
+
+use crate::{
+    client::CuReport,
+    sample::TagFilter,
+    stats::{bootstrap_compare, BootstrapComparison, CuStats},
+};
+use solana_program::pubkey::Pubkey;
+use std::fmt;
+
+/// Bootstrap resamples per workload; fixed seed so reruns print the same table.
+pub const RESAMPLES: usize = 1_000;
+pub const SEED: u64 = 0;
+
+/// Steady-state CU of one workload under both deployments.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct WorkloadDiff {
+    pub workload: String,
+    pub before: CuStats,
+    pub after: CuStats,
+    /// `after - before`; `None` when either side has no steady-state samples.
+    pub comparison: Option<BootstrapComparison>,
+    /// Median serialized transaction size in bytes of the steady-state
+    /// samples, when it was recorded.
+    pub before_transaction_size: Option<f64>,
+    pub after_transaction_size: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct CuDiff {
+    pub before_program: Pubkey,
+    pub after_program: Pubkey,
+    /// Program data size in bytes, when it could be read.
+    pub before_size: Option<usize>,
+    pub after_size: Option<usize>,
+    pub workloads: Vec<WorkloadDiff>,
+    /// What `after` adds when both sides ran the same program, such as a
+    /// compute budget; `None` for a comparison of two deployments.
+    pub variant: Option<String>,
+    /// Reasons the numbers may not reflect the program change alone.
+    pub caveats: Vec<String>,
+}
+
+impl CuDiff {
+    /// Diffs `(workload, before, after)` report pairs.
+    pub fn new(pairs: &[(String, CuReport, CuReport)]) -> Option<Self> {
+        let (_, first_before, first_after) = pairs.first()?;
+        let mut diff = Self {
+            before_program: first_before.run.program_id,
+            after_program: first_after.run.program_id,
+            before_size: first_before.program_size.map(|info| info.program_data_size),
+            after_size: first_after.program_size.map(|info| info.program_data_size),
+            workloads: Vec::new(),
+            variant: None,
+            caveats: Vec::new(),
+        };
+
+        let steady_state = TagFilter::steady_state();
+        for (workload, before, after) in pairs {
+            let before_cu = steady_state.compute_units(&before.run);
+            let after_cu = steady_state.compute_units(&after.run);
+            diff.workloads.push(WorkloadDiff {
+                workload: workload.clone(),
+                before: CuStats::summarize(&before_cu),
+                after: CuStats::summarize(&after_cu),
+                comparison: bootstrap_compare(&before_cu, &after_cu, RESAMPLES, SEED),
+                before_transaction_size: median_transaction_size(before, &steady_state),
+                after_transaction_size: median_transaction_size(after, &steady_state),
+            });
+
+            let caveats = before
+                .comparison_caveats(after)
+                .into_iter()
+                .map(|change| change.to_string())
+                .chain(before.accounting_caveats(after))
+                .chain(before.environment_caveats(after));
+            for caveat in caveats {
+                let caveat = format!("{}: {}", workload, caveat);
+                if !diff.caveats.contains(&caveat) {
+                    diff.caveats.push(caveat);
+                }
+            }
+        }
+        Some(diff)
+    }
+
+    /// Workloads whose steady-state median CU rose significantly.
+    pub fn regressions(&self) -> impl Iterator<Item = &str> {
+        self.workloads
+            .iter()
+            .filter(|row| {
+                row.comparison
+                    .as_ref()
+                    .is_some_and(|comparison| comparison.is_significant() && comparison.diff > 0.0)
+            })
+            .map(|row| row.workload.as_str())
+    }
+}
+
+fn median_transaction_size(report: &CuReport, filter: &TagFilter) -> Option<f64> {
+    let sizes: Vec<u64> = report
+        .run
+        .filter(filter)
+        .filter_map(|sample| sample.transaction_size)
+        .map(|size| size as u64)
+        .collect();
+    (!sizes.is_empty()).then(|| CuStats::summarize(&sizes).median)
+}
+
+impl fmt::Display for CuDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let size = |size: Option<usize>| {
+            size.map_or("size unknown".to_string(), |s| format!("{} bytes", s))
+        };
+        if let Some(variant) = &self.variant {
+            writeln!(
+                f,
+                "Program: {} ({})",
+                self.before_program,
+                size(self.before_size)
+            )?;
+            writeln!(f, "Before: without {}", variant)?;
+            writeln!(f, "After:  with {}", variant)?;
+        } else {
+            writeln!(
+                f,
+                "Before: {} ({})",
+                self.before_program,
+                size(self.before_size)
+            )?;
+            write!(
+                f,
+                "After:  {} ({}",
+                self.after_program,
+                size(self.after_size)
+            )?;
+            if let (Some(before), Some(after)) = (self.before_size, self.after_size) {
+                write!(f, ", {:+} bytes", after as i64 - before as i64)?;
+            }
+            writeln!(f, ")")?;
+        }
+
+        write!(
+            f,
+            "{:<20} {:>10} {:>10} {:>10}  change",
+            "workload", "before", "after", "delta"
+        )?;
+        for row in &self.workloads {
+            write!(
+                f,
+                "\n{:<20} {:>10.0} {:>10.0} {:>+10.1}  ",
+                row.workload,
+                row.before.median,
+                row.after.median,
+                row.after.median - row.before.median
+            )?;
+            match &row.comparison {
+                Some(comparison) => write!(f, "{}", comparison)?,
+                None => write!(f, "no steady-state samples")?,
+            }
+        }
+        // Only variants like v0 messages change the transaction itself
+        for row in &self.workloads {
+            if let (Some(before), Some(after)) =
+                (row.before_transaction_size, row.after_transaction_size)
+            {
+                if before != after {
+                    write!(
+                        f,
+                        "\n{}: median transaction size {:.0} -> {:.0} bytes ({:+.0})",
+                        row.workload,
+                        before,
+                        after,
+                        after - before
+                    )?;
+                }
+            }
+        }
+        for caveat in &self.caveats {
+            write!(f, "\nWARNING: {}", caveat)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{fixtures::RunFixture, leader::LeaderReport, size::ProgramSizeInfo};
+
+    fn report(program_id: Pubkey, cu: u64, size: usize) -> CuReport {
+        let fixture = RunFixture {
+            program_id,
+            samples: 40,
+            base_compute_units: cu,
+            jitter: 0,
+            outlier_rate: 0.0,
+            failure_rate: 0.0,
+            ..RunFixture::default()
+        };
+        CuReport {
+            run: fixture.run(),
+            payer: Pubkey::new_unique(),
+            program_size: Some(ProgramSizeInfo {
+                program_data_size: size,
+                total_size: size + 45,
+                ..ProgramSizeInfo::default()
+            }),
+            clock: None,
+            accounting: None,
+            calibration: None,
+            leaders: LeaderReport::default(),
+            log_mismatches: Vec::new(),
+            burn_fit: None,
+            guard_unique_nonces: None,
+            attribution_gaps: Vec::new(),
+            invocation_tree: None,
+            deployment: None,
+            deployment_changes: Vec::new(),
+            slos: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_diff_per_workload() {
+        let (before, after) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut pairs = vec![
+            (
+                "counter".to_string(),
+                report(before, 1_400, 18_000),
+                report(after, 1_450, 100_000),
+            ),
+            (
+                "noop".to_string(),
+                report(before, 300, 18_000),
+                report(after, 300, 100_000),
+            ),
+        ];
+        let (_, before_report, after_report) = &mut pairs[0];
+        for (report, size) in [(before_report, 300), (after_report, 240)] {
+            for sample in report.run.samples_mut() {
+                sample.transaction_size = Some(size);
+            }
+        }
+
+        let diff = CuDiff::new(&pairs).unwrap();
+        assert_eq!(diff.after_size, Some(100_000));
+        assert_eq!(diff.workloads[0].after_transaction_size, Some(240.0));
+        assert_eq!(diff.workloads[1].after_transaction_size, None);
+        assert_eq!(
+            diff.workloads[0].after.median - diff.workloads[0].before.median,
+            50.0
+        );
+        assert!(diff.workloads[0]
+            .comparison
+            .as_ref()
+            .unwrap()
+            .is_significant());
+        assert!(!diff.workloads[1]
+            .comparison
+            .as_ref()
+            .unwrap()
+            .is_significant());
+        assert!(diff.caveats.is_empty());
+        assert_eq!(diff.regressions().collect::<Vec<_>>(), ["counter"]);
+
+        let table = diff.to_string();
+        assert!(table.contains("+82000 bytes"));
+        assert!(table.contains("counter: median transaction size 300 -> 240 bytes (-60)"));
+        assert!(CuDiff::new(&[]).is_none());
+    }
+}
@@ -0,0 +1,63 @@
+//! Accessors over `solana-transaction-status` types whose shape changes between
+//! Agave releases, such as `OptionSerializer` fields of the meta. Only the 2.x
+//! line pinned in `Cargo.toml` is built; reading meta through these helpers
+//! keeps its shape in this one module for a move to a later release.
+
+use crate::sdk::TransactionError;
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, UiInnerInstructions, UiLoadedAddresses,
+    UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
+
+/// Collapses `OptionSerializer` into a plain `Option`, treating `Skip` as absent.
+pub trait OptionSerializerExt<T> {
+    fn as_option(&self) -> Option<&T>;
+}
+
+impl<T> OptionSerializerExt<T> for OptionSerializer<T> {
+    fn as_option(&self) -> Option<&T> {
+        match self {
+            OptionSerializer::Some(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+pub fn compute_units_consumed(meta: &UiTransactionStatusMeta) -> Option<u64> {
+    meta.compute_units_consumed.as_option().copied()
+}
+
+pub fn log_messages(meta: &UiTransactionStatusMeta) -> &[String] {
+    meta.log_messages
+        .as_option()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+pub fn inner_instructions(meta: &UiTransactionStatusMeta) -> Option<&[UiInnerInstructions]> {
+    meta.inner_instructions.as_option().map(Vec::as_slice)
+}
+
+pub fn loaded_addresses(meta: &UiTransactionStatusMeta) -> Option<&UiLoadedAddresses> {
+    meta.loaded_addresses.as_option()
+}
+
+/// `(pre, post)` token balances; empty when the meta does not record them.
+pub fn token_balances(
+    meta: &UiTransactionStatusMeta,
+) -> (&[UiTransactionTokenBalance], &[UiTransactionTokenBalance]) {
+    (
+        token_balance_list(&meta.pre_token_balances),
+        token_balance_list(&meta.post_token_balances),
+    )
+}
+
+fn token_balance_list(
+    balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>,
+) -> &[UiTransactionTokenBalance] {
+    balances.as_option().map(Vec::as_slice).unwrap_or_default()
+}
+
+pub fn transaction_error(meta: &UiTransactionStatusMeta) -> Option<TransactionError> {
+    meta.err.clone()
+}
@@ -0,0 +1,423 @@
+//! Benchmark settings read from a file, so an experiment is a file to check
+//! in rather than a code edit or a long line of environment variables.
+//!
+//! Keys are the lowercase names of the environment variables read by
+//! [`BenchConfig::from_env`](crate::client::BenchConfig::from_env), which
+//! still override the file. TOML files (`.toml`) hold `key = value` lines and
+//! YAML files (`.yaml`, `.yml`) `key: value` lines; in both, values are
+//! strings, numbers, booleans or lists: `[a, b]`, which TOML files may spread
+//! over several lines, or `- item` lines in YAML. Tables and nested mappings are not supported.
+//!
+//! [`ConfigFile::load`] checks every value before a run starts, e.g. that
+//! `rpc_url` is an http(s) URL and `program_id` a pubkey, and reports all the
+//! settings that fail at once, by key.
+//!
+//! ```toml
+//! rpc_url = "https://api.devnet.solana.com"
+//! commitment = "finalized"
+//! program_id = "H2GM7Vci4vVTWUfM4CQ5gWuUXF56wgXC4CkUY2Ea7mgC"
+//! workloads = ["counter", "sha256"]
+//! iterations = 500
+//! payer_keypair = "~/.config/solana/id.json"
+//! output = "runs/devnet.json"
+//! slos = ["p99_cu < 5000", "landing_rate > 99%"]
+//! ```
+
+use crate::{
+    cluster::Cluster, deadline::PhaseDeadlines, export::ExportFormat, leader::LeaderTargeting,
+    sdk::CommitmentConfig, slo::Slo,
+};
+use solana_program::pubkey::Pubkey;
+use std::{collections::BTreeMap, error::Error, fmt::Display, fs, path::Path, str::FromStr};
+
+/// Every key a config file may set.
+pub const KEYS: &[&str] = &[
+    "archival_rpc_url",
+    "cluster",
+    "commitment",
+    "compute_unit_limit",
+    "compute_unit_price",
+    "diagnostics_dir",
+    "extra_program_ids",
+    "force_lock",
+    "in_process",
+    "iterations",
+    "leader_target",
+    "lookup_table",
+    "min_payer_balance",
+    "output",
+    "output_format",
+    "payer_keypair",
+    "phase_deadlines",
+    "program_id",
+    "rpc_rate_limit",
+    "rpc_url",
+    "send_interval_ms",
+    "simulate_before_send",
+    "simulate_only",
+    "slos",
+    "slot_window_batch",
+    "snapshot_accounts",
+    "stop_file",
+    "workload",
+    "workloads",
+];
+
+/// Keys whose values are lists.
+const LIST_KEYS: &[&str] = &[
+    "extra_program_ids",
+    "slos",
+    "snapshot_accounts",
+    "workloads",
+];
+
+/// Settings of a config file, lists joined with commas as in the
+/// environment variables they stand in for.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigFile {
+    values: BTreeMap<String, String>,
+}
+
+impl ConfigFile {
+    /// Reads `path` as TOML or YAML, depending on its extension, and
+    /// [validates](ConfigFile::validate) it.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        let parsed = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::parse_toml(&text),
+            Some("yaml" | "yml") => Self::parse_yaml(&text),
+            _ => return Err(format!("{} must end in .toml, .yaml or .yml", path.display()).into()),
+        };
+        parsed
+            .and_then(|file| file.validate().map(|()| file))
+            .map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// Checks each value parses as its setting expects, listing every one
+    /// that does not with its key and what was expected.
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        let problems: Vec<String> = self
+            .values
+            .iter()
+            .filter_map(|(key, value)| {
+                check_value(key, value)
+                    .err()
+                    .map(|e| format!("{} = {:?}: {}", key, value, e))
+            })
+            .collect();
+        match problems.len() {
+            0 => Ok(()),
+            1 => Err(problems[0].clone().into()),
+            count => {
+                Err(format!("{} invalid settings:\n  {}", count, problems.join("\n  ")).into())
+            }
+        }
+    }
+
+    pub fn parse_toml(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut file = Self::default();
+        let mut lines = text.lines().enumerate();
+        while let Some((number, line)) = lines.next() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected key = value", number + 1))?;
+            // Lists may continue over several lines
+            let mut value = value.trim().to_string();
+            while value.starts_with('[') && !value.ends_with(']') {
+                let (_, next) = lines
+                    .next()
+                    .ok_or_else(|| format!("line {}: unclosed list", number + 1))?;
+                value.push(',');
+                value.push_str(strip_comment(next).trim());
+            }
+            file.insert(key.trim(), parse_value(&value), number + 1)?;
+        }
+        Ok(file)
+    }
+
+    pub fn parse_yaml(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut file = Self::default();
+        // Key whose `- item` lines are being collected, with its line number
+        let mut list: Option<(String, Vec<String>, usize)> = None;
+        for (number, line) in text.lines().enumerate() {
+            let line = strip_comment(line);
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed == "---" {
+                continue;
+            }
+            if let (Some(item), Some((_, items, _))) = (trimmed.strip_prefix('-'), &mut list) {
+                items.push(unquote(item.trim()).to_string());
+                continue;
+            }
+            if let Some((key, items, number)) = list.take() {
+                file.insert(&key, items.join(","), number)?;
+            }
+            if line.starts_with(char::is_whitespace) {
+                return Err(
+                    format!("line {}: nested mappings are not supported", number + 1).into(),
+                );
+            }
+            let (key, value) = trimmed
+                .split_once(':')
+                .ok_or_else(|| format!("line {}: expected key: value", number + 1))?;
+            match value.trim() {
+                "" => list = Some((key.trim().to_string(), Vec::new(), number + 1)),
+                value => file.insert(key.trim(), parse_value(value), number + 1)?,
+            }
+        }
+        if let Some((key, items, number)) = list {
+            file.insert(&key, items.join(","), number)?;
+        }
+        Ok(file)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Sets `key` to `value`, replacing any earlier value, if the key is
+    /// known and the value is what it takes.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), Box<dyn Error>> {
+        if !KEYS.contains(&key) {
+            return Err(format!("unknown setting {:?}", key).into());
+        }
+        check_value(key, value).map_err(|e| format!("{} = {:?}: {}", key, value, e))?;
+        self.values.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    /// These settings as a TOML file [`ConfigFile::parse_toml`] reads back.
+    pub fn to_toml(&self) -> String {
+        let mut toml = String::new();
+        for (key, value) in &self.values {
+            let value = if LIST_KEYS.contains(&key.as_str()) {
+                let items: Vec<String> = value
+                    .split(',')
+                    .filter(|item| !item.is_empty())
+                    .map(|item| format!("\"{}\"", item))
+                    .collect();
+                format!("[{}]", items.join(", "))
+            } else if value.parse::<f64>().is_ok() || value.parse::<bool>().is_ok() {
+                value.clone()
+            } else {
+                format!("\"{}\"", value)
+            };
+            toml.push_str(&format!("{} = {}\n", key, value));
+        }
+        toml
+    }
+
+    /// These settings, with those of `other` taking precedence.
+    pub fn overlay(mut self, other: ConfigFile) -> Self {
+        self.values.extend(other.values);
+        self
+    }
+
+    fn insert(&mut self, key: &str, value: String, line: usize) -> Result<(), Box<dyn Error>> {
+        if !KEYS.contains(&key) {
+            return Err(format!("line {}: unknown setting {:?}", line, key).into());
+        }
+        if self.values.insert(key.to_string(), value).is_some() {
+            return Err(format!("line {}: {} is set twice", line, key).into());
+        }
+        Ok(())
+    }
+}
+
+/// Checks `value` is what setting `key` takes.
+fn check_value(key: &str, value: &str) -> Result<(), String> {
+    fn parses<T: FromStr>(value: &str) -> Result<(), String>
+    where
+        T::Err: Display,
+    {
+        value
+            .trim()
+            .parse::<T>()
+            .map(drop)
+            .map_err(|e| e.to_string())
+    }
+    match key {
+        "rpc_url" | "archival_rpc_url" => check_url(value),
+        "program_id" => parses::<Pubkey>(value),
+        "extra_program_ids" | "snapshot_accounts" => {
+            value.split(',').try_for_each(parses::<Pubkey>)
+        }
+        "slos" => value.split(',').try_for_each(parses::<Slo>),
+        "cluster" => parses::<Cluster>(value),
+        "commitment" => parses::<CommitmentConfig>(value),
+        "output_format" => parses::<ExportFormat>(value),
+        "leader_target" => parses::<LeaderTargeting>(value),
+        "phase_deadlines" => PhaseDeadlines::parse(value)
+            .map(drop)
+            .map_err(|e| e.to_string()),
+        "compute_unit_limit" => parses::<u32>(value),
+        "iterations" | "min_payer_balance" | "compute_unit_price" | "rpc_rate_limit"
+        | "send_interval_ms" => parses::<u64>(value),
+        "slot_window_batch" => parses::<usize>(value),
+        "force_lock" | "in_process" | "lookup_table" | "simulate_before_send" | "simulate_only" => {
+            parses::<bool>(value)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// An `http://` or `https://` URL with a host.
+fn check_url(value: &str) -> Result<(), String> {
+    let host = value
+        .strip_prefix("http://")
+        .or_else(|| value.strip_prefix("https://"))
+        .map(|rest| rest.split(['/', '?', '#']).next().unwrap_or_default())
+        .unwrap_or_default();
+    if host.is_empty() || value.contains(char::is_whitespace) {
+        return Err("expected an http:// or https:// URL".to_string());
+    }
+    Ok(())
+}
+
+/// Drops a `#` comment, unless the `#` is quoted or inside a word, as in a URL
+/// fragment.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '#') if previous.is_whitespace() => return &line[..index],
+            _ => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+/// A scalar, or an inline list joined with commas.
+fn parse_value(value: &str) -> String {
+    match value
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        Some(items) => items
+            .split(',')
+            .map(|item| unquote(item.trim()))
+            .filter(|item| !item.is_empty())
+            .collect::<Vec<_>>()
+            .join(","),
+        None => unquote(value).to_string(),
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_toml_and_yaml_agree() {
+        let toml = ConfigFile::parse_toml(
+            r#"
+            # devnet sweep
+            rpc_url = "https://api.devnet.solana.com/#main"
+            iterations = 500 # per workload
+            workloads = [
+                "counter", # the default
+                'sha256'
+            ]
+            force_lock = true
+            "#,
+        )
+        .unwrap();
+        let yaml = ConfigFile::parse_yaml(
+            "---\n\
+             # devnet sweep\n\
+             rpc_url: \"https://api.devnet.solana.com/#main\"\n\
+             iterations: 500 # per workload\n\
+             workloads:\n  - counter\n  - 'sha256'\n\
+             force_lock: true\n",
+        )
+        .unwrap();
+
+        assert_eq!(toml, yaml);
+        assert_eq!(
+            toml.get("rpc_url"),
+            Some("https://api.devnet.solana.com/#main")
+        );
+        assert_eq!(toml.get("iterations"), Some("500"));
+        assert_eq!(toml.get("workloads"), Some("counter,sha256"));
+        assert_eq!(toml.get("output"), None);
+
+        assert!(ConfigFile::parse_toml("iteration = 5").is_err());
+        assert!(ConfigFile::parse_toml("workload = \"a\"\nworkload = \"b\"").is_err());
+        assert!(ConfigFile::parse_toml("[cluster]").is_err());
+        assert!(ConfigFile::parse_yaml("cluster:\n  rpc_url: x").is_err());
+    }
+
+    #[test]
+    fn test_set_and_write_back() {
+        let mut file = ConfigFile::parse_toml("workloads = [\"counter\", \"sha256\"]").unwrap();
+        file.set("rpc_url", "http://127.0.0.1:8899").unwrap();
+        file.set("iterations", "10").unwrap();
+        file.set("simulate_only", "true").unwrap();
+        file.set("payer_keypair", "~/.config/solana/id.json")
+            .unwrap();
+        assert!(file.set("program_id", "bogus").is_err());
+        assert!(file.set("rpc", "http://127.0.0.1:8899").is_err());
+
+        let toml = file.to_toml();
+        assert!(
+            toml.contains("workloads = [\"counter\", \"sha256\"]\n"),
+            "{}",
+            toml
+        );
+        assert!(toml.contains("iterations = 10\n"), "{}", toml);
+        assert!(toml.contains("simulate_only = true\n"), "{}", toml);
+        assert_eq!(ConfigFile::parse_toml(&toml).unwrap(), file);
+    }
+
+    #[test]
+    fn test_validate_names_every_bad_setting() {
+        let file = ConfigFile::parse_toml(
+            r#"
+            rpc_url = "api.devnet.solana.com"
+            archival_rpc_url = "https://archive.example.com/rpc"
+            program_id = "H2GM7Vci4vVTWUfM4CQ5gWuUXF56wgXC4CkUY2Ea7mgC"
+            extra_program_ids = ["11111111111111111111111111111111", "not-a-key"]
+            cluster = "mainnet"
+            force_lock = "yes"
+            iterations = 500
+            "#,
+        )
+        .unwrap();
+        let e = file.validate().unwrap_err().to_string();
+        assert!(e.starts_with("4 invalid settings:"), "{}", e);
+        for key in ["rpc_url", "extra_program_ids", "cluster", "force_lock"] {
+            assert!(
+                e.contains(&format!("\n  {} = ", key)),
+                "{} missing from {}",
+                key,
+                e
+            );
+        }
+        assert!(
+            e.contains("rpc_url = \"api.devnet.solana.com\": expected an http:// or https:// URL")
+        );
+
+        let file = ConfigFile::parse_yaml("simulate_only: true\ncommitment: confirmed\n").unwrap();
+        assert!(file.validate().is_ok());
+    }
+}
@@ -0,0 +1,71 @@
+//! External control of a running benchmark.
+
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// Asks a run to stop sending, verify what it already sent and exit cleanly.
+/// Triggered either programmatically or by creating a sentinel file, which lets
+/// CI jobs on shared runners stop a run without killing the process.
+#[derive(Debug, Clone, Default)]
+pub struct StopSignal {
+    requested: Arc<AtomicBool>,
+    sentinel: Option<PathBuf>,
+}
+
+impl StopSignal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also treats the existence of `path` as a stop request.
+    pub fn with_sentinel(path: impl Into<PathBuf>) -> Self {
+        Self {
+            requested: Arc::default(),
+            sentinel: Some(path.into()),
+        }
+    }
+
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_requested(&self) -> bool {
+        if self.requested.load(Ordering::Relaxed) {
+            return true;
+        }
+        let sentinel_found = self.sentinel.as_ref().is_some_and(|path| path.exists());
+        if sentinel_found {
+            self.request();
+        }
+        sentinel_found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_stop_signal() {
+        let path = std::env::temp_dir().join(format!("cu-bench-stop-{}", std::process::id()));
+        let signal = StopSignal::with_sentinel(&path);
+        let handle = signal.clone();
+        assert!(!handle.is_requested());
+
+        fs::write(&path, "").unwrap();
+        assert!(handle.is_requested());
+        fs::remove_file(&path).unwrap();
+        // Stays requested once observed
+        assert!(signal.is_requested());
+
+        let manual = StopSignal::new();
+        manual.clone().request();
+        assert!(manual.is_requested());
+    }
+}
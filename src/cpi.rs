@@ -0,0 +1,44 @@
+//! CPI overhead: [`BenchInstruction::Cpi`] invokes the callee program, passed
+//! as the only account, with one level less until it reaches depth 0. The
+//! callee is this program or another deployment of it, such as a padded build,
+//! to see whether callee size changes the cost of a call.
+//!
+//! Every level logs `Cpi depth: {depth}` and, around its invoke, the remaining
+//! CU, next to the runtime's per-invocation `consumed` lines.
+
+use crate::instruction::BenchInstruction;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    log::sol_log_compute_units,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Nested invokes allowed below the top-level instruction.
+pub const MAX_CPI_DEPTH: u8 = 4;
+
+pub fn process_cpi(accounts: &[AccountInfo], depth: u8) -> ProgramResult {
+    if depth > MAX_CPI_DEPTH {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    msg!("Cpi depth: {}", depth);
+    if depth == 0 {
+        return Ok(());
+    }
+    let callee = next_account_info(&mut accounts.iter())?;
+    sol_log_compute_units();
+    invoke(&cpi_chain(callee.key, callee.key, depth - 1), accounts)?;
+    sol_log_compute_units();
+    Ok(())
+}
+
+/// Builds a [`BenchInstruction::Cpi`] of `depth` levels for `program_id`,
+/// calling into `callee`.
+pub fn cpi_chain(program_id: &Pubkey, callee: &Pubkey, depth: u8) -> Instruction {
+    BenchInstruction::Cpi { depth }
+        .to_instruction(*program_id, vec![AccountMeta::new_readonly(*callee, false)])
+}
@@ -0,0 +1,164 @@
+//! Curve25519 syscall costs: [`BenchInstruction::Curve`] runs `count` point
+//! additions, or one multiscalar multiplication over `count` points, on the
+//! Edwards or Ristretto group, and logs
+//! `Curve: {op} x{count} checksum {checksum}`.
+//!
+//! These are the group operations behind confidential transfers and other
+//! privacy protocols. They need the `curve25519` feature, which pulls in
+//! `solana-curve25519`; builds without it reject the instruction. Off chain
+//! the same functions run on `curve25519-dalek`, so the harness knows the
+//! expected checksum when it has the feature too.
+//!
+//! [`BenchInstruction::Curve`]: crate::instruction::BenchInstruction::Curve
+
+use solana_program::program_error::ProgramError;
+use std::fmt;
+
+/// Most additions or multiplication points one instruction asks for; the
+/// points and scalars of a multiplication this size fill half the heap.
+pub const MAX_CURVE_POINTS: u16 = 256;
+
+/// Compressed Edwards basepoint.
+#[cfg(feature = "curve25519")]
+const EDWARDS_BASEPOINT: [u8; 32] = [
+    0x58, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+    0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+];
+/// Compressed Ristretto basepoint.
+#[cfg(feature = "curve25519")]
+const RISTRETTO_BASEPOINT: [u8; 32] = [
+    0xe2, 0xf2, 0xae, 0x0a, 0x6a, 0xbc, 0x4e, 0x71, 0xa8, 0x84, 0xa9, 0x61, 0xc5, 0x00, 0x51, 0x5f,
+    0x58, 0xe3, 0x0b, 0x6a, 0xa5, 0x82, 0xdd, 0x8d, 0xb6, 0xa6, 0x59, 0x45, 0xe0, 0x8d, 0x2d, 0x76,
+];
+
+/// Group operation run by [`BenchInstruction::Curve`](crate::instruction::BenchInstruction::Curve).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveOp {
+    /// `count` Edwards additions of the basepoint.
+    EdwardsAdd,
+    /// One Edwards multiscalar multiplication over `count` points.
+    EdwardsMsm,
+    /// `count` Ristretto additions of the basepoint.
+    RistrettoAdd,
+    /// One Ristretto multiscalar multiplication over `count` points.
+    RistrettoMsm,
+}
+
+impl CurveOp {
+    pub const ALL: [CurveOp; 4] = [
+        Self::EdwardsAdd,
+        Self::EdwardsMsm,
+        Self::RistrettoAdd,
+        Self::RistrettoMsm,
+    ];
+}
+
+impl fmt::Display for CurveOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::EdwardsAdd => "edwards-add",
+            Self::EdwardsMsm => "edwards-msm",
+            Self::RistrettoAdd => "ristretto-add",
+            Self::RistrettoMsm => "ristretto-msm",
+        })
+    }
+}
+
+/// Body of [`BenchInstruction::Curve`](crate::instruction::BenchInstruction::Curve).
+/// Additions start from the basepoint `B` and end at `(count + 1)B`; the
+/// multiplication weighs `count` copies of `B` by 1 to `count`, for
+/// `count(count + 1)/2 B`. Returns the first 8 bytes of the compressed result.
+/// Fails with `InvalidInstructionData` past [`MAX_CURVE_POINTS`] or without
+/// the `curve25519` feature.
+pub fn curve(op: CurveOp, count: u16) -> Result<u64, ProgramError> {
+    if count > MAX_CURVE_POINTS {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    curve_ops(op, count)
+}
+
+#[cfg(feature = "curve25519")]
+fn curve_ops(op: CurveOp, count: u16) -> Result<u64, ProgramError> {
+    use solana_curve25519::{
+        edwards::{add_edwards, multiscalar_multiply_edwards, PodEdwardsPoint},
+        ristretto::{add_ristretto, multiscalar_multiply_ristretto, PodRistrettoPoint},
+        scalar::PodScalar,
+    };
+    use std::hint::black_box;
+
+    let scalars = || -> Vec<PodScalar> {
+        (1..=count)
+            .map(|weight| {
+                let mut scalar = [0; 32];
+                scalar[..2].copy_from_slice(&weight.to_le_bytes());
+                PodScalar(scalar)
+            })
+            .collect()
+    };
+    let result = match op {
+        CurveOp::EdwardsAdd => {
+            let basepoint = PodEdwardsPoint(EDWARDS_BASEPOINT);
+            let mut sum = basepoint;
+            for _ in 0..count {
+                sum = add_edwards(black_box(&sum), &basepoint)
+                    .ok_or(ProgramError::InvalidArgument)?;
+            }
+            sum.0
+        }
+        CurveOp::EdwardsMsm => {
+            let points = vec![PodEdwardsPoint(EDWARDS_BASEPOINT); count.into()];
+            multiscalar_multiply_edwards(&scalars(), black_box(&points))
+                .ok_or(ProgramError::InvalidArgument)?
+                .0
+        }
+        CurveOp::RistrettoAdd => {
+            let basepoint = PodRistrettoPoint(RISTRETTO_BASEPOINT);
+            let mut sum = basepoint;
+            for _ in 0..count {
+                sum = add_ristretto(black_box(&sum), &basepoint)
+                    .ok_or(ProgramError::InvalidArgument)?;
+            }
+            sum.0
+        }
+        CurveOp::RistrettoMsm => {
+            let points = vec![PodRistrettoPoint(RISTRETTO_BASEPOINT); count.into()];
+            multiscalar_multiply_ristretto(&scalars(), black_box(&points))
+                .ok_or(ProgramError::InvalidArgument)?
+                .0
+        }
+    };
+    Ok(u64::from_le_bytes(result[..8].try_into().unwrap()))
+}
+
+#[cfg(not(feature = "curve25519"))]
+fn curve_ops(_op: CurveOp, _count: u16) -> Result<u64, ProgramError> {
+    Err(ProgramError::InvalidInstructionData)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_additions_and_multiplication_agree() {
+        assert_eq!(
+            curve(CurveOp::EdwardsAdd, MAX_CURVE_POINTS + 1),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        if !cfg!(feature = "curve25519") {
+            assert_eq!(
+                curve(CurveOp::RistrettoMsm, 1),
+                Err(ProgramError::InvalidInstructionData)
+            );
+            return;
+        }
+        // 3B both ways: B + B + B, and 1B + 2B
+        assert_eq!(curve(CurveOp::EdwardsAdd, 2), curve(CurveOp::EdwardsMsm, 2));
+        assert_eq!(
+            curve(CurveOp::RistrettoAdd, 2),
+            curve(CurveOp::RistrettoMsm, 2)
+        );
+        assert_ne!(curve(CurveOp::EdwardsAdd, 2), curve(CurveOp::EdwardsAdd, 3));
+        assert!(curve(CurveOp::RistrettoMsm, MAX_CURVE_POINTS).is_ok());
+    }
+}
@@ -0,0 +1,293 @@
+//! Per-phase deadlines for RPC runs. A phase that overruns its deadline ends
+//! the run, but first writes a diagnostics bundle with the signatures still
+//! pending, the last RPC errors and the node's health, so a hung run can be
+//! debugged after the fact instead of being killed blind.
+
+use crate::{
+    error::BenchError,
+    redact::RedactingWriter,
+    sdk::{RpcClient, Signature},
+};
+use solana_program::pubkey::Pubkey;
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// RPC errors kept for the diagnostics bundle.
+const MAX_RECENT_ERRORS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Airdrop to a fresh payer.
+    Funding,
+    /// Workload setup, which creates the accounts the workload needs.
+    Deploy,
+    Send,
+    Confirm,
+    /// Fetching landed transactions for verification.
+    Fetch,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 5] = [
+        Phase::Funding,
+        Phase::Deploy,
+        Phase::Send,
+        Phase::Confirm,
+        Phase::Fetch,
+    ];
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Phase::Funding => "funding",
+            Phase::Deploy => "deploy",
+            Phase::Send => "send",
+            Phase::Confirm => "confirm",
+            Phase::Fetch => "fetch",
+        })
+    }
+}
+
+/// Longest each phase may take; phases without a deadline may run forever.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PhaseDeadlines {
+    deadlines: Vec<(Phase, Duration)>,
+}
+
+impl PhaseDeadlines {
+    pub fn with(mut self, phase: Phase, deadline: Duration) -> Self {
+        self.deadlines.retain(|(other, _)| *other != phase);
+        self.deadlines.push((phase, deadline));
+        self
+    }
+
+    pub fn get(&self, phase: Phase) -> Option<Duration> {
+        self.deadlines
+            .iter()
+            .find(|(other, _)| *other == phase)
+            .map(|(_, deadline)| *deadline)
+    }
+
+    /// Parses `phase=seconds` pairs separated by commas, e.g.
+    /// `funding=60,confirm=120`.
+    pub fn parse(spec: &str) -> Result<Self, Box<dyn Error>> {
+        let mut deadlines = Self::default();
+        for pair in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+        {
+            let (name, seconds) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("deadline {:?} is not phase=seconds", pair))?;
+            let phase = Phase::ALL
+                .into_iter()
+                .find(|phase| phase.to_string() == name.trim())
+                .ok_or_else(|| format!("unknown phase {:?}", name.trim()))?;
+            let seconds: f64 = seconds.trim().parse()?;
+            deadlines = deadlines.with(phase, Duration::try_from_secs_f64(seconds)?);
+        }
+        Ok(deadlines)
+    }
+}
+
+/// Tracks the phase a run is in against its deadline, and remembers recent
+/// RPC errors for the diagnostics bundle.
+#[derive(Debug)]
+pub struct PhaseClock {
+    deadlines: PhaseDeadlines,
+    diagnostics_dir: PathBuf,
+    program_id: Pubkey,
+    phase: Phase,
+    started: Instant,
+    recent_errors: VecDeque<String>,
+}
+
+impl PhaseClock {
+    pub fn new(deadlines: PhaseDeadlines, diagnostics_dir: PathBuf, program_id: Pubkey) -> Self {
+        Self {
+            deadlines,
+            diagnostics_dir,
+            program_id,
+            phase: Phase::Funding,
+            started: Instant::now(),
+            recent_errors: VecDeque::new(),
+        }
+    }
+
+    pub fn enter(&mut self, phase: Phase) {
+        self.phase = phase;
+        self.started = Instant::now();
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Remembers an error; the bundle it may end up in is redacted as a whole.
+    pub fn record_error(&mut self, error: impl Into<String>) {
+        if self.recent_errors.len() == MAX_RECENT_ERRORS {
+            self.recent_errors.pop_front();
+        }
+        self.recent_errors.push_back(error.into());
+    }
+
+    pub fn is_overdue(&self) -> bool {
+        self.deadlines
+            .get(self.phase)
+            .is_some_and(|deadline| self.started.elapsed() > deadline)
+    }
+
+    /// Fails once the current phase is past its deadline, after writing a
+    /// diagnostics bundle for `pending` into the diagnostics directory.
+    pub fn check(
+        &self,
+        rpc_client: &RpcClient,
+        pending: impl IntoIterator<Item = Signature>,
+    ) -> Result<(), BenchError> {
+        if !self.is_overdue() {
+            return Ok(());
+        }
+        let diagnostics = Diagnostics::collect(self, rpc_client, pending.into_iter().collect());
+        Err(BenchError::Timeout {
+            phase: self.phase,
+            deadline: self.deadlines.get(self.phase).unwrap_or_default(),
+            diagnostics: diagnostics
+                .write(&self.diagnostics_dir)
+                .map_err(|e| e.to_string()),
+        })
+    }
+}
+
+/// State of a run at the moment a phase overran its deadline.
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    pub program_id: Pubkey,
+    pub phase: Phase,
+    pub elapsed: Duration,
+    pub pending_signatures: Vec<Signature>,
+    /// Oldest first.
+    pub recent_errors: Vec<String>,
+    /// `ok`, or why the node reports itself unhealthy or could not be asked.
+    pub node_health: String,
+    pub node_slot: Option<u64>,
+    pub node_version: Option<String>,
+}
+
+impl Diagnostics {
+    pub fn collect(
+        clock: &PhaseClock,
+        rpc_client: &RpcClient,
+        pending_signatures: Vec<Signature>,
+    ) -> Self {
+        Self {
+            program_id: clock.program_id,
+            phase: clock.phase,
+            elapsed: clock.started.elapsed(),
+            pending_signatures,
+            recent_errors: clock.recent_errors.iter().cloned().collect(),
+            node_health: match rpc_client.get_health() {
+                Ok(()) => "ok".to_string(),
+                Err(e) => e.to_string(),
+            },
+            node_slot: rpc_client.get_slot().ok(),
+            node_version: rpc_client
+                .get_version()
+                .ok()
+                .map(|version| version.solana_core),
+        }
+    }
+
+    /// Writes the bundle, redacted, to a new file in `dir` and returns its
+    /// path.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        let since = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let path = dir.join(format!(
+            "cu-bench-{}-{}-{}.txt",
+            self.program_id, self.phase, since
+        ));
+        fs::create_dir_all(dir)?;
+        let mut out = RedactingWriter::new(fs::File::create(&path)?);
+        write!(out, "{}", self)?;
+        out.flush()?;
+        Ok(path)
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "program: {}", self.program_id)?;
+        writeln!(f, "phase: {} after {:?}", self.phase, self.elapsed)?;
+        writeln!(f, "node health: {}", self.node_health)?;
+        if let Some(slot) = self.node_slot {
+            writeln!(f, "node slot: {}", slot)?;
+        }
+        if let Some(version) = &self.node_version {
+            writeln!(f, "node version: {}", version)?;
+        }
+        writeln!(f, "recent RPC errors: {}", self.recent_errors.len())?;
+        for error in &self.recent_errors {
+            writeln!(f, "  {}", error)?;
+        }
+        writeln!(f, "pending signatures: {}", self.pending_signatures.len())?;
+        for signature in &self.pending_signatures {
+            writeln!(f, "  {}", signature)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_overdue_phase_writes_diagnostics() {
+        let deadlines = PhaseDeadlines::parse("funding=0, confirm=1.5").unwrap();
+        assert_eq!(
+            deadlines.get(Phase::Confirm),
+            Some(Duration::from_millis(1_500))
+        );
+        assert_eq!(deadlines.get(Phase::Send), None);
+        assert!(PhaseDeadlines::parse("land=10").is_err());
+        assert!(PhaseDeadlines::parse("send").is_err());
+
+        let dir = std::env::temp_dir().join(format!("cu-bench-deadline-{}", std::process::id()));
+        let mut clock = PhaseClock::new(deadlines, dir.clone(), Pubkey::new_unique());
+        clock.record_error("connection refused");
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(clock.is_overdue());
+        clock.enter(Phase::Send);
+        assert!(!clock.is_overdue());
+        clock.enter(Phase::Funding);
+
+        // Nothing listens here, so health and slot queries fail quickly
+        let rpc_client = RpcClient::new("http://127.0.0.1:1".to_string());
+        let pending = Signature::new_unique();
+        let e = clock.check(&rpc_client, [pending]).unwrap_err();
+        assert!(matches!(
+            e,
+            BenchError::Timeout {
+                phase: Phase::Funding,
+                ..
+            }
+        ));
+        let e = e.to_string();
+        assert!(e.starts_with("funding phase exceeded its 0ns deadline; diagnostics in"));
+
+        let bundle = fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        let contents = fs::read_to_string(&bundle).unwrap();
+        assert!(contents.contains("phase: funding"));
+        assert!(contents.contains("  connection refused"));
+        assert!(contents.contains(&format!("  {}", pending)));
+        assert!(!contents.contains("node health: ok"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,227 @@
+//! Identity of the deployed binary, so comparisons notice when a program was
+//! redeployed or moved between loaders instead of silently comparing
+//! different code.
+//!
+//! Immutable programs, deployed without an upgrade authority or through a
+//! non-upgradeable loader, are measured like any other. They cannot be
+//! redeployed in place, so size sweeps over them need one program id per
+//! build, as `compare` and `matrix` already do.
+
+use crate::{error::BenchError, sdk::RpcClient, size::LoaderV4Header};
+use solana_program::{
+    bpf_loader, bpf_loader_deprecated,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    hash::{hash, Hash},
+    loader_v4,
+    pubkey::Pubkey,
+};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ProgramFingerprint {
+    pub program_id: Pubkey,
+    /// Loader owning the program account.
+    pub loader: Pubkey,
+    /// Upgradeable programs only.
+    pub programdata_address: Option<Pubkey>,
+    /// Slot of the last deployment, for upgradeable and loader-v4 programs.
+    pub deployment_slot: Option<u64>,
+    /// `None` for immutable programs, including finalized loader-v4 ones.
+    pub upgrade_authority: Option<Pubkey>,
+    /// SHA-256 of the executable bytes, excluding loader metadata.
+    pub code_hash: Hash,
+    pub code_size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DeploymentChange {
+    LoaderMigrated {
+        from: Pubkey,
+        to: Pubkey,
+    },
+    ProgramDataMoved {
+        from: Option<Pubkey>,
+        to: Option<Pubkey>,
+    },
+    Redeployed {
+        from_slot: Option<u64>,
+        to_slot: Option<u64>,
+    },
+    /// Same deployment metadata but different bytes.
+    CodeChanged,
+}
+
+impl fmt::Display for DeploymentChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::LoaderMigrated { from, to } => {
+                write!(f, "loader migrated from {} to {}", from, to)
+            }
+            Self::ProgramDataMoved { from, to } => {
+                write!(f, "programdata moved from {:?} to {:?}", from, to)
+            }
+            Self::Redeployed { from_slot, to_slot } => {
+                write!(f, "redeployed (slot {:?} -> {:?})", from_slot, to_slot)
+            }
+            Self::CodeChanged => write!(f, "program code changed"),
+        }
+    }
+}
+
+impl ProgramFingerprint {
+    pub fn fetch(rpc_client: &RpcClient, program_id: &Pubkey) -> Result<Self, BenchError> {
+        let account = rpc_client.get_account(program_id)?;
+        let loader = account.owner;
+
+        let (programdata_address, deployment_slot, upgrade_authority, code) =
+            if loader == bpf_loader_upgradeable::id() {
+                let UpgradeableLoaderState::Program {
+                    programdata_address,
+                } = bincode::deserialize(&account.data)?
+                else {
+                    return Err(BenchError::NotAProgram {
+                        address: *program_id,
+                        reason: "not a program account",
+                    });
+                };
+                let programdata = rpc_client.get_account(&programdata_address)?;
+                let UpgradeableLoaderState::ProgramData {
+                    slot,
+                    upgrade_authority_address,
+                } = bincode::deserialize(&programdata.data)?
+                else {
+                    return Err(BenchError::NotAProgram {
+                        address: *program_id,
+                        reason: "invalid program data account",
+                    });
+                };
+                let metadata_size = UpgradeableLoaderState::size_of_programdata_metadata();
+                let code = programdata.data.get(metadata_size..).unwrap_or_default();
+                (
+                    Some(programdata_address),
+                    Some(slot),
+                    upgrade_authority_address,
+                    code.to_vec(),
+                )
+            } else if loader == loader_v4::id() {
+                let header = LoaderV4Header::parse(&account.data)?;
+                let authority =
+                    (header.status != LoaderV4Header::FINALIZED).then_some(header.authority);
+                let code = account.data[LoaderV4Header::LEN..].to_vec();
+                (None, Some(header.slot), authority, code)
+            } else if loader == bpf_loader::id() || loader == bpf_loader_deprecated::id() {
+                (None, None, None, account.data)
+            } else {
+                return Err(BenchError::UnsupportedLoader {
+                    program_id: *program_id,
+                    owner: loader,
+                });
+            };
+
+        Ok(Self {
+            program_id: *program_id,
+            loader,
+            programdata_address,
+            deployment_slot,
+            upgrade_authority,
+            code_hash: hash(&code),
+            code_size: code.len(),
+        })
+    }
+
+    /// Whether the program can no longer be upgraded.
+    pub fn is_immutable(&self) -> bool {
+        self.upgrade_authority.is_none()
+    }
+
+    /// What differs in `self` compared with the `earlier` fingerprint of the
+    /// same program. Empty when both describe the same binary.
+    pub fn changes_since(&self, earlier: &Self) -> Vec<DeploymentChange> {
+        let mut changes = Vec::new();
+        if self.loader != earlier.loader {
+            changes.push(DeploymentChange::LoaderMigrated {
+                from: earlier.loader,
+                to: self.loader,
+            });
+        }
+        if self.programdata_address != earlier.programdata_address {
+            changes.push(DeploymentChange::ProgramDataMoved {
+                from: earlier.programdata_address,
+                to: self.programdata_address,
+            });
+        }
+        if self.deployment_slot != earlier.deployment_slot {
+            changes.push(DeploymentChange::Redeployed {
+                from_slot: earlier.deployment_slot,
+                to_slot: self.deployment_slot,
+            });
+        }
+        if changes.is_empty() && self.code_hash != earlier.code_hash {
+            changes.push(DeploymentChange::CodeChanged);
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_changes_since() {
+        let program_id = Pubkey::new_unique();
+        let original = ProgramFingerprint {
+            program_id,
+            loader: bpf_loader_upgradeable::id(),
+            programdata_address: Some(Pubkey::new_unique()),
+            deployment_slot: Some(10),
+            upgrade_authority: Some(Pubkey::new_unique()),
+            code_hash: hash(b"v1"),
+            code_size: 2,
+        };
+        assert!(original.changes_since(&original).is_empty());
+        assert!(!original.is_immutable());
+
+        // Dropping the authority freezes the program without touching its code
+        let frozen = ProgramFingerprint {
+            upgrade_authority: None,
+            ..original.clone()
+        };
+        assert!(frozen.is_immutable());
+        assert!(frozen.changes_since(&original).is_empty());
+
+        let redeployed = ProgramFingerprint {
+            deployment_slot: Some(20),
+            code_hash: hash(b"v2"),
+            ..original.clone()
+        };
+        assert_eq!(
+            redeployed.changes_since(&original),
+            [DeploymentChange::Redeployed {
+                from_slot: Some(10),
+                to_slot: Some(20)
+            }]
+        );
+
+        let migrated = ProgramFingerprint {
+            loader: bpf_loader::id(),
+            programdata_address: None,
+            deployment_slot: None,
+            upgrade_authority: None,
+            ..original.clone()
+        };
+        assert_eq!(migrated.changes_since(&original).len(), 3);
+        assert!(migrated.is_immutable());
+
+        let patched = ProgramFingerprint {
+            code_hash: hash(b"patched"),
+            ..original.clone()
+        };
+        assert_eq!(
+            patched.changes_since(&original),
+            [DeploymentChange::CodeChanged]
+        );
+    }
+}
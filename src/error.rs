@@ -0,0 +1,229 @@
+//! Typed errors for the client helpers, so callers can tell a missing program
+//! from a flaky RPC node or an overrun deadline without parsing messages.
+//!
+//! Runs still return `Box<dyn Error>`; failures that came from these helpers
+//! can be recovered with `downcast_ref::<BenchError>()`, and [`exit_code`]
+//! maps them to the exit code of the `cu-bench` binary.
+
+use crate::{deadline::Phase, sdk::ClientError};
+use solana_program::pubkey::{ParsePubkeyError, Pubkey};
+use std::{error::Error, fmt, path::PathBuf, time::Duration};
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BenchError {
+    /// An RPC request failed; boxed as client errors are large.
+    RpcFailure(Box<ClientError>),
+    /// The account exists but does not hold a program in a shape its loader
+    /// would accept.
+    NotAProgram {
+        address: Pubkey,
+        reason: &'static str,
+    },
+    /// The account is owned by something other than a known BPF loader.
+    UnsupportedLoader {
+        program_id: Pubkey,
+        owner: Pubkey,
+    },
+    /// Account data could not be decoded.
+    DeserializeFailed(String),
+    InvalidPubkey(String),
+    UnknownWorkload(String),
+    /// Flags, environment or config file could not be read into a
+    /// `BenchConfig`.
+    Config(String),
+    /// A comparison found these workloads significantly more expensive after
+    /// the change than before.
+    Regression {
+        workloads: Vec<String>,
+    },
+    /// The run missed these configured SLOs.
+    SloFailed {
+        slos: Vec<String>,
+    },
+    /// A phase overran its deadline. `diagnostics` is the bundle written for
+    /// it, or why it could not be written.
+    Timeout {
+        phase: Phase,
+        deadline: Duration,
+        diagnostics: Result<PathBuf, String>,
+    },
+}
+
+impl BenchError {
+    /// The RPC error behind this one, if any.
+    pub fn client_error(&self) -> Option<&ClientError> {
+        match self {
+            Self::RpcFailure(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// The process exit code for this error; see [`exit_code`].
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Config(_) | Self::InvalidPubkey(_) | Self::UnknownWorkload(_) => EXIT_CONFIG,
+            Self::RpcFailure(_) => EXIT_RPC,
+            Self::Regression { .. } => EXIT_REGRESSION,
+            Self::SloFailed { .. } => EXIT_SLO,
+            Self::Timeout { .. } => EXIT_TIMEOUT,
+            Self::NotAProgram { .. }
+            | Self::UnsupportedLoader { .. }
+            | Self::DeserializeFailed(_) => EXIT_FAILURE,
+        }
+    }
+}
+
+/// Any failure without a more specific code below.
+pub const EXIT_FAILURE: u8 = 1;
+/// Bad flags, environment or config file; nothing was sent.
+pub const EXIT_CONFIG: u8 = 2;
+/// An RPC request failed, e.g. the cluster is unreachable.
+pub const EXIT_RPC: u8 = 3;
+/// `compare` found a workload significantly more expensive after the change.
+pub const EXIT_REGRESSION: u8 = 4;
+/// A phase overran its deadline.
+pub const EXIT_TIMEOUT: u8 = 5;
+/// The run missed one of its configured SLOs.
+pub const EXIT_SLO: u8 = 6;
+
+/// The exit code for a run that failed with `e`: that of the [`BenchError`]
+/// it holds, [`EXIT_RPC`] for a bare RPC error, [`EXIT_FAILURE`] otherwise.
+pub fn exit_code(e: &(dyn Error + 'static)) -> u8 {
+    if let Some(e) = e.downcast_ref::<BenchError>() {
+        e.exit_code()
+    } else if e.is::<ClientError>() {
+        EXIT_RPC
+    } else {
+        EXIT_FAILURE
+    }
+}
+
+impl fmt::Display for BenchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::RpcFailure(e) => write!(f, "RPC request failed: {}", e),
+            Self::NotAProgram { address, reason } => {
+                write!(f, "{} is not a program: {}", address, reason)
+            }
+            Self::UnsupportedLoader { program_id, owner } => write!(
+                f,
+                "Not a BPF program: {} is owned by {}, which is not a known loader",
+                program_id, owner
+            ),
+            Self::DeserializeFailed(e) => write!(f, "failed to deserialize account data: {}", e),
+            Self::InvalidPubkey(e) => write!(f, "invalid pubkey: {}", e),
+            Self::UnknownWorkload(name) => write!(f, "unknown workload {}", name),
+            Self::Config(e) => write!(f, "{}", e),
+            Self::Regression { workloads } => {
+                write!(f, "CU regressed in {}", workloads.join(", "))
+            }
+            Self::SloFailed { slos } => write!(f, "SLOs missed: {}", slos.join(", ")),
+            Self::Timeout {
+                phase,
+                deadline,
+                diagnostics,
+            } => {
+                write!(f, "{} phase exceeded its {:?} deadline", phase, deadline)?;
+                match diagnostics {
+                    Ok(path) => write!(f, "; diagnostics in {}", path.display()),
+                    Err(e) => write!(f, "; failed to write diagnostics: {}", e),
+                }
+            }
+        }
+    }
+}
+
+impl Error for BenchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::RpcFailure(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<ClientError> for BenchError {
+    fn from(e: ClientError) -> Self {
+        Self::RpcFailure(Box::new(e))
+    }
+}
+
+impl From<bincode::Error> for BenchError {
+    fn from(e: bincode::Error) -> Self {
+        Self::DeserializeFailed(e.to_string())
+    }
+}
+
+impl From<ParsePubkeyError> for BenchError {
+    fn from(e: ParsePubkeyError) -> Self {
+        Self::InvalidPubkey(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sdk::ClientErrorKind;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_errors_survive_boxing() {
+        fn lookup(address: &str) -> Result<Pubkey, Box<dyn Error>> {
+            let address = Pubkey::from_str(address).map_err(BenchError::from)?;
+            Err(BenchError::NotAProgram {
+                address,
+                reason: "not a program account",
+            }
+            .into())
+        }
+
+        let e = lookup(&Pubkey::default().to_string()).unwrap_err();
+        assert!(matches!(
+            e.downcast_ref::<BenchError>(),
+            Some(BenchError::NotAProgram { address, .. }) if *address == Pubkey::default()
+        ));
+        assert!(matches!(
+            lookup("not base58!").unwrap_err().downcast_ref(),
+            Some(BenchError::InvalidPubkey(_))
+        ));
+
+        let rpc = BenchError::from(ClientError::from(ClientErrorKind::Custom(
+            "node is behind".to_string(),
+        )));
+        assert!(rpc.client_error().is_some());
+        assert!(rpc.source().unwrap().to_string().contains("node is behind"));
+    }
+    #[test]
+    fn test_exit_codes() {
+        let codes = [
+            exit_code(&BenchError::Config("bad CLUSTER".to_string())),
+            exit_code(&ClientError::from(ClientErrorKind::Custom(
+                "refused".to_string(),
+            ))),
+            exit_code(&BenchError::Regression {
+                workloads: vec!["noop".to_string()],
+            }),
+            exit_code(&BenchError::Timeout {
+                phase: Phase::Send,
+                deadline: Duration::from_secs(1),
+                diagnostics: Err("disk full".to_string()),
+            }),
+            exit_code(&BenchError::SloFailed {
+                slos: vec!["p99_cu < 5000".to_string()],
+            }),
+            exit_code(&*Box::<dyn Error>::from("no samples")),
+        ];
+        assert_eq!(
+            codes,
+            [
+                EXIT_CONFIG,
+                EXIT_RPC,
+                EXIT_REGRESSION,
+                EXIT_TIMEOUT,
+                EXIT_SLO,
+                EXIT_FAILURE
+            ]
+        );
+    }
+}
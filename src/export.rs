@@ -0,0 +1,441 @@
+//! Per-transaction records of a run as CSV or JSON, for analysis in pandas or
+//! spreadsheets, and an append-only time series for dashboards.
+//!
+//! [`CsvSampleWriter`] and [`CsvSampleReader`] write and read the CSV form a
+//! sample at a time, so archived runs of any length are summarized or
+//! compared in memory proportional to what is kept of each sample.
+
+use crate::{
+    client::CuReport,
+    metrics::Metric,
+    redact::RedactingWriter,
+    run::Run,
+    sample::{Sample, SampleTag},
+};
+use std::{
+    error::Error,
+    fmt::Display,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufWriter, Lines, Write},
+    path::Path,
+    str::FromStr,
+};
+
+pub const CSV_HEADER: &str =
+    "index,counter,signature,slot,compute_units,fee,transaction_size,tags,instruction,syscall_compute_units";
+
+/// Columns of the time series read by Grafana's Infinity datasource (CSV
+/// type, `time` parsed as a Unix timestamp in milliseconds).
+pub const TIMESERIES_HEADER: &str =
+    "time,program_id,program_size,slot,compute_units,fee,steady_state,signature";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    /// Picks the format from a `.csv` or `.json` extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "unknown export format {:?}; expected csv or json",
+                name
+            )),
+        }
+    }
+}
+
+/// Writes `run` to `path` in the format implied by its extension.
+pub fn export(path: impl AsRef<Path>, run: &Run) -> Result<(), Box<dyn Error>> {
+    export_as(path, None, run)
+}
+
+/// Writes `run` to `path` in `format`, or the one implied by the extension
+/// when `None`, through the redactor, as failure tags carry error text.
+pub fn export_as(
+    path: impl AsRef<Path>,
+    format: Option<ExportFormat>,
+    run: &Run,
+) -> Result<(), Box<dyn Error>> {
+    let path = path.as_ref();
+    let format = format
+        .or_else(|| ExportFormat::from_path(path))
+        .ok_or_else(|| format!("{} must end in .csv or .json", path.display()))?;
+    let mut out = RedactingWriter::new(BufWriter::new(File::create(path)?));
+    match format {
+        ExportFormat::Csv => write_csv(&mut out, run)?,
+        ExportFormat::Json => write_json(&mut out, run)?,
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// One row per sample after [`CSV_HEADER`]. Missing values are empty and tags
+/// are `;`-separated, so steady-state samples have an empty `tags` column;
+/// `instruction` is the sample's label, e.g. `BurnCompute iterations=1000`,
+/// and `syscall_compute_units` what [`crate::predict`] expects its syscalls
+/// to cost.
+pub fn write_csv(out: &mut impl Write, run: &Run) -> io::Result<()> {
+    let mut writer = CsvSampleWriter::new(out)?;
+    for sample in run {
+        writer.write(sample)?;
+    }
+    Ok(())
+}
+
+/// Writes the CSV form of [`write_csv`] one sample at a time, for runs that
+/// are not held in memory as a whole.
+pub struct CsvSampleWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> CsvSampleWriter<W> {
+    /// Starts the file with [`CSV_HEADER`].
+    pub fn new(mut out: W) -> io::Result<Self> {
+        writeln!(out, "{}", CSV_HEADER)?;
+        Ok(Self { out })
+    }
+
+    pub fn write(&mut self, sample: &Sample) -> io::Result<()> {
+        let record = Record::new(sample);
+        writeln!(
+            self.out,
+            "{},{},{},{},{},{},{},{},{},{}",
+            sample.index,
+            or_empty(record.counter),
+            sample.signature,
+            sample.slot,
+            or_empty(sample.compute_units),
+            or_empty(record.fee),
+            or_empty(sample.transaction_size),
+            record.tags.join(";"),
+            or_empty(sample.instruction.as_ref()),
+            or_empty(sample.syscall_compute_units)
+        )
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// Reads the CSV form of [`write_csv`] back one sample at a time. Samples
+/// carry what the CSV holds: the counter and fee come back as the `count` and
+/// `fee` metrics, and block times, timings and balance changes are empty.
+pub struct CsvSampleReader<R> {
+    lines: Lines<R>,
+    line: usize,
+}
+
+impl<R: BufRead> CsvSampleReader<R> {
+    /// Checks `input` starts with [`CSV_HEADER`].
+    pub fn new(input: R) -> Result<Self, Box<dyn Error>> {
+        let mut lines = input.lines();
+        if lines.next().transpose()?.as_deref() != Some(CSV_HEADER) {
+            return Err("expected the CSV export of run or sweep".into());
+        }
+        Ok(Self { lines, line: 1 })
+    }
+}
+
+impl CsvSampleReader<io::BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::new(io::BufReader::new(file)).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+}
+
+impl<R: BufRead> Iterator for CsvSampleReader<R> {
+    type Item = Result<Sample, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = match self.lines.next()? {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e.into())),
+        };
+        self.line += 1;
+        Some(parse_row(&line).map_err(|e| format!("line {}: {}", self.line, e).into()))
+    }
+}
+
+fn parse_row(line: &str) -> Result<Sample, Box<dyn Error>> {
+    let columns: Vec<&str> = line.split(',').collect();
+    let [index, counter, signature, slot, compute_units, fee, transaction_size, tags, instruction, syscall_compute_units] =
+        columns[..]
+    else {
+        return Err(format!("expected 10 columns, got {}", columns.len()).into());
+    };
+    fn optional<T: FromStr>(value: &str) -> Result<Option<T>, T::Err> {
+        (!value.is_empty()).then(|| value.parse()).transpose()
+    }
+    let mut sample = Sample::new(
+        index.parse()?,
+        signature.parse()?,
+        slot.parse()?,
+        optional(compute_units)?,
+    );
+    sample.transaction_size = optional(transaction_size)?;
+    sample.instruction = optional(instruction)?;
+    sample.syscall_compute_units = optional(syscall_compute_units)?;
+    for (name, value) in [("count", counter), ("fee", fee)] {
+        if let Some(value) = optional(value)? {
+            sample.metrics.push(Metric {
+                name: name.to_string(),
+                value,
+            });
+        }
+    }
+    for tag in tags.split(';').filter(|tag| !tag.is_empty()) {
+        sample.tags.insert(match tag {
+            "Warmup" => SampleTag::Warmup,
+            "Congestion" => SampleTag::Congestion,
+            "EpochBoundary" => SampleTag::EpochBoundary,
+            "Outlier" => SampleTag::Outlier,
+            _ => return Err(format!("unknown tag {:?}", tag).into()),
+        });
+    }
+    Ok(sample)
+}
+
+/// A JSON array with one object per sample; missing values are `null`.
+pub fn write_json(out: &mut impl Write, run: &Run) -> io::Result<()> {
+    write!(out, "[")?;
+    for (i, sample) in run.samples().enumerate() {
+        let record = Record::new(sample);
+        let tags: Vec<String> = record
+            .tags
+            .iter()
+            .map(|tag| format!("\"{}\"", tag))
+            .collect();
+        write!(
+            out,
+            "{}\n  {{\"index\": {}, \"counter\": {}, \"signature\": \"{}\", \"slot\": {}, \
+             \"compute_units\": {}, \"fee\": {}, \"transaction_size\": {}, \"tags\": [{}], \
+             \"instruction\": {}, \"syscall_compute_units\": {}}}",
+            if i == 0 { "" } else { "," },
+            sample.index,
+            or_null(record.counter),
+            sample.signature,
+            sample.slot,
+            or_null(sample.compute_units),
+            or_null(record.fee),
+            or_null(sample.transaction_size),
+            tags.join(", "),
+            or_null(
+                sample
+                    .instruction
+                    .as_ref()
+                    .map(|label| format!("\"{}\"", label))
+            ),
+            or_null(sample.syscall_compute_units)
+        )?;
+    }
+    writeln!(out, "\n]")
+}
+
+/// Appends one row per landed sample of `report` to the time series at
+/// `path`, writing the header first if the file is new. Samples without a
+/// block time are skipped, as a time series cannot place them.
+pub fn append_timeseries(path: impl AsRef<Path>, report: &CuReport) -> Result<(), Box<dyn Error>> {
+    let path = path.as_ref();
+    let is_new = !path.exists() || path.metadata()?.len() == 0;
+    let mut out = RedactingWriter::new(BufWriter::new(
+        OpenOptions::new().create(true).append(true).open(path)?,
+    ));
+    if is_new {
+        writeln!(out, "{}", TIMESERIES_HEADER)?;
+    }
+    write_timeseries_rows(&mut out, report)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn write_timeseries_rows(out: &mut impl Write, report: &CuReport) -> io::Result<()> {
+    let program_size = report.program_size.map(|info| info.program_data_size);
+    for sample in &report.run {
+        let (Some(block_time), Some(compute_units)) = (sample.block_time, sample.compute_units)
+        else {
+            continue;
+        };
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            block_time * 1_000,
+            report.run.program_id,
+            or_empty(program_size),
+            sample.slot,
+            compute_units,
+            or_empty(Record::new(sample).fee),
+            sample.tags.is_empty(),
+            sample.signature
+        )?;
+    }
+    Ok(())
+}
+
+/// Fields derived from a sample's metrics and tags.
+struct Record {
+    counter: Option<u64>,
+    fee: Option<u64>,
+    tags: Vec<String>,
+}
+
+impl Record {
+    fn new(sample: &Sample) -> Self {
+        Self {
+            counter: sample.metric("count").map(|count| count as u64),
+            fee: sample.metric("fee").map(|fee| fee as u64),
+            tags: sample.tags.iter().map(|tag| format!("{:?}", tag)).collect(),
+        }
+    }
+}
+
+fn or_empty(value: Option<impl Display>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn or_null(value: Option<impl Display>) -> String {
+    value
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "null".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        leader::LeaderReport, metrics::MetricSink, sample::tag_warmup, sdk::Signature,
+        size::ProgramSizeInfo,
+    };
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_csv_and_json_records() {
+        let mut run = Run::new(Pubkey::new_unique());
+        let mut counted = Sample::new(0, Signature::default(), 7, Some(1_200));
+        let mut sink = MetricSink::default();
+        sink.emit("count", 0.0);
+        sink.emit("fee", 5_000.0);
+        counted.metrics = sink.into_metrics();
+        counted.transaction_size = Some(215);
+        counted.instruction = Some("MemOps op=memcpy len=8192 reps=16".to_string());
+        counted.syscall_compute_units = Some(512);
+        run.push(counted);
+        run.push(Sample::new(1, Signature::default(), 8, None));
+        tag_warmup(run.samples_mut(), 1);
+
+        let signature = Signature::default();
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &run).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(
+            csv,
+            format!(
+                "{}\n0,0,{},7,1200,5000,215,Warmup,MemOps op=memcpy len=8192 reps=16,512\n1,,{},8,,,,,,\n",
+                CSV_HEADER, signature, signature
+            )
+        );
+
+        let mut json = Vec::new();
+        write_json(&mut json, &run).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.starts_with("[\n  {\"index\": 0, \"counter\": 0,"));
+        assert!(json.contains(
+            "\"fee\": 5000, \"transaction_size\": 215, \"tags\": [\"Warmup\"], \
+             \"instruction\": \"MemOps op=memcpy len=8192 reps=16\", \"syscall_compute_units\": 512}"
+        ));
+        assert!(json.contains(
+            "\"slot\": 8, \"compute_units\": null, \"fee\": null, \"transaction_size\": null, \
+             \"tags\": [], \"instruction\": null, \"syscall_compute_units\": null}\n]"
+        ));
+
+        let mut read = CsvSampleReader::new(csv.as_bytes()).unwrap();
+        let first = read.next().unwrap().unwrap();
+        assert_eq!(first.compute_units, Some(1_200));
+        assert_eq!(first.metric("fee"), Some(5_000.0));
+        assert_eq!(first.syscall_compute_units, Some(512));
+        assert!(first.has_tag(SampleTag::Warmup));
+        assert_eq!(
+            first.instruction.as_deref(),
+            Some("MemOps op=memcpy len=8192 reps=16")
+        );
+        let second = read.next().unwrap().unwrap();
+        assert_eq!((second.index, second.compute_units), (1, None));
+        assert!(second.tags.is_empty() && second.metrics.is_empty());
+        assert!(read.next().is_none());
+        let bad = format!("{}\n0,,x,7,,,,,,\n", CSV_HEADER);
+        let e = CsvSampleReader::new(bad.as_bytes())
+            .unwrap()
+            .next()
+            .unwrap();
+        assert!(e.unwrap_err().to_string().starts_with("line 2: "));
+        assert!(CsvSampleReader::new("index,slot\n".as_bytes()).is_err());
+
+        assert_eq!(
+            ExportFormat::from_path(Path::new("out/run.json")),
+            Some(ExportFormat::Json)
+        );
+        assert_eq!(ExportFormat::from_path(Path::new("run.txt")), None);
+    }
+
+    #[test]
+    fn test_timeseries_appends() {
+        let program_id = Pubkey::new_unique();
+        let mut run = Run::new(program_id);
+        let mut timed = Sample::new(0, Signature::default(), 7, Some(1_200));
+        timed.block_time = Some(1_700_000_000);
+        run.push(timed);
+        run.push(Sample::new(1, Signature::default(), 8, Some(1_300)));
+        let report = CuReport {
+            run,
+            payer: Pubkey::new_unique(),
+            program_size: Some(ProgramSizeInfo {
+                program_data_size: 18_000,
+                total_size: 18_045,
+                ..ProgramSizeInfo::default()
+            }),
+            clock: None,
+            accounting: None,
+            calibration: None,
+            leaders: LeaderReport::default(),
+            log_mismatches: Vec::new(),
+            burn_fit: None,
+            guard_unique_nonces: None,
+            attribution_gaps: Vec::new(),
+            invocation_tree: None,
+            deployment: None,
+            deployment_changes: Vec::new(),
+            slos: Vec::new(),
+        };
+
+        let path = std::env::temp_dir().join(format!("timeseries-{}.csv", program_id));
+        append_timeseries(&path, &report).unwrap();
+        append_timeseries(&path, &report).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let row = format!(
+            "1700000000000,{},18000,7,1200,,true,{}\n",
+            program_id,
+            Signature::default()
+        );
+        assert_eq!(contents, format!("{}\n{}{}", TIMESERIES_HEADER, row, row));
+    }
+}
@@ -0,0 +1,246 @@
+//! Classification of failed samples, so runs with failures stay analyzable.
+
+use crate::{
+    instruction::{BenchInstruction, COMPUTE_BUDGET_PROGRAM_ID},
+    sdk::{
+        ClientError, ClientErrorKind, RpcError, RpcResponseErrorData, Signature, TransactionError,
+    },
+};
+use solana_program::{
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+};
+use std::{collections::BTreeMap, fmt};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum FailureClass {
+    /// Rejected by the RPC node's preflight simulation.
+    PreflightFailure,
+    BlockhashExpired,
+    ComputeBudgetExceeded,
+    /// The program returned `ProgramError::Custom(code)`.
+    ProgramError(u32),
+    /// Sent, but never observed as confirmed.
+    NotLanded,
+    RpcTransport,
+    Other(String),
+}
+
+impl FailureClass {
+    pub fn from_transaction_error(err: &TransactionError) -> Self {
+        match err {
+            TransactionError::BlockhashNotFound => Self::BlockhashExpired,
+            TransactionError::InstructionError(
+                _,
+                InstructionError::ComputationalBudgetExceeded,
+            ) => Self::ComputeBudgetExceeded,
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+                Self::ProgramError(*code)
+            }
+            other => Self::Other(other.to_string()),
+        }
+    }
+
+    pub fn from_client_error(err: &ClientError) -> Self {
+        match err.kind() {
+            ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                data: RpcResponseErrorData::SendTransactionPreflightFailure(_),
+                ..
+            }) => Self::PreflightFailure,
+            ClientErrorKind::TransactionError(err) => Self::from_transaction_error(err),
+            ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) => Self::RpcTransport,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for FailureClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::PreflightFailure => write!(f, "preflight failure"),
+            Self::BlockhashExpired => write!(f, "blockhash expired"),
+            Self::ComputeBudgetExceeded => write!(f, "CU exceeded"),
+            Self::ProgramError(code) => write!(f, "program error {}", code),
+            Self::NotLanded => write!(f, "not landed"),
+            Self::RpcTransport => write!(f, "RPC transport error"),
+            Self::Other(description) => write!(f, "other: {}", description),
+        }
+    }
+}
+
+/// Failed sample counts per class.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FailureCounts(BTreeMap<FailureClass, usize>);
+
+impl FailureCounts {
+    pub fn record(&mut self, class: FailureClass) {
+        *self.0.entry(class).or_default() += 1;
+    }
+
+    pub fn get(&self, class: &FailureClass) -> usize {
+        self.0.get(class).copied().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> usize {
+        self.0.values().sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&FailureClass, usize)> {
+        self.0.iter().map(|(class, count)| (class, *count))
+    }
+}
+
+impl fmt::Display for FailureCounts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "no failures");
+        }
+        let classes: Vec<String> = self
+            .iter()
+            .map(|(class, count)| format!("{}: {}", class, count))
+            .collect();
+        write!(f, "{}", classes.join(", "))
+    }
+}
+
+/// A failed transaction traced to the instruction that failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionFailure {
+    pub sample_index: u64,
+    pub signature: Signature,
+    /// Position of the instruction in the workload's transaction.
+    pub instruction_index: u8,
+    /// See [`describe_instruction`].
+    pub instruction: String,
+    pub error: InstructionError,
+}
+
+impl InstructionFailure {
+    /// Locates `err` among `instructions`, the descriptions of the
+    /// transaction's instructions in order. `None` when the transaction
+    /// failed outside any instruction, e.g. on an expired blockhash.
+    pub fn locate(
+        sample_index: u64,
+        signature: Signature,
+        err: &TransactionError,
+        instructions: &[String],
+    ) -> Option<Self> {
+        let TransactionError::InstructionError(index, error) = err else {
+            return None;
+        };
+        Some(Self {
+            sample_index,
+            signature,
+            instruction_index: *index,
+            instruction: instructions
+                .get(usize::from(*index))
+                .cloned()
+                .unwrap_or_else(|| "unknown instruction".to_string()),
+            error: error.clone(),
+        })
+    }
+}
+
+impl fmt::Display for InstructionFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "transaction {} failed in instruction {} ({}): {}",
+            self.sample_index + 1,
+            self.instruction_index,
+            self.instruction,
+            self.error
+        )
+    }
+}
+
+/// Names `instruction` for failure reports: the [`BenchInstruction`] variant
+/// for instructions to one of `bench_programs`, the compute budget request, or
+/// otherwise the program it invokes.
+pub fn describe_instruction(instruction: &Instruction, bench_programs: &[Pubkey]) -> String {
+    if instruction.program_id == COMPUTE_BUDGET_PROGRAM_ID {
+        let request = match instruction.data.first() {
+            Some(1) => "RequestHeapFrame",
+            Some(2) => "SetComputeUnitLimit",
+            Some(3) => "SetComputeUnitPrice",
+            Some(4) => "SetLoadedAccountsDataSizeLimit",
+            _ => "unknown request",
+        };
+        return format!("ComputeBudget {}", request);
+    }
+    if bench_programs.contains(&instruction.program_id) {
+        if let Ok(bench) = BenchInstruction::unpack(&instruction.data) {
+            return bench.name().to_string();
+        }
+    }
+    format!("instruction to {}", instruction.program_id)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_classify_and_count() {
+        let mut counts = FailureCounts::default();
+        for err in [
+            TransactionError::BlockhashNotFound,
+            TransactionError::InstructionError(0, InstructionError::ComputationalBudgetExceeded),
+            TransactionError::InstructionError(1, InstructionError::Custom(6)),
+            TransactionError::InstructionError(0, InstructionError::Custom(6)),
+        ] {
+            counts.record(FailureClass::from_transaction_error(&err));
+        }
+        counts.record(FailureClass::NotLanded);
+
+        assert_eq!(counts.total(), 5);
+        assert_eq!(counts.get(&FailureClass::ProgramError(6)), 2);
+        assert_eq!(
+            counts.to_string(),
+            "blockhash expired: 1, CU exceeded: 1, program error 6: 2, not landed: 1"
+        );
+    }
+
+    #[test]
+    fn test_locate_failed_instruction() {
+        let program_id = Pubkey::new_unique();
+        let instructions: Vec<String> = [
+            crate::instruction::set_compute_unit_limit(200_000),
+            BenchInstruction::BurnCompute { iterations: 10 }.to_instruction(program_id, vec![]),
+            BenchInstruction::Noop.to_instruction(Pubkey::default(), vec![]),
+        ]
+        .iter()
+        .map(|instruction| describe_instruction(instruction, &[program_id]))
+        .collect();
+        assert_eq!(
+            instructions[..2],
+            ["ComputeBudget SetComputeUnitLimit", "BurnCompute"]
+        );
+        assert_eq!(
+            instructions[2],
+            format!("instruction to {}", Pubkey::default())
+        );
+
+        let signature = Signature::default();
+        let failure = InstructionFailure::locate(
+            4,
+            signature,
+            &TransactionError::InstructionError(1, InstructionError::Custom(6)),
+            &instructions,
+        )
+        .unwrap();
+        assert_eq!(failure.instruction, "BurnCompute");
+        assert_eq!(
+            failure.to_string(),
+            "transaction 5 failed in instruction 1 (BurnCompute): custom program error: 0x6"
+        );
+        assert!(InstructionFailure::locate(
+            0,
+            signature,
+            &TransactionError::BlockhashNotFound,
+            &instructions
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,226 @@
+//! Canned runs and confirmed transactions for tests of tools built on this
+//! crate, so they can exercise reports without a validator. Output is fully
+//! determined by the [`RunFixture`] settings, including the seed.
+
+use crate::{
+    failure::FailureClass,
+    run::Run,
+    sample::Sample,
+    sdk::{Signature, TransactionError},
+    stats::SplitMix64,
+    tx_view::ConfirmedTxView,
+};
+use solana_program::{instruction::InstructionError, pubkey::Pubkey};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction,
+    EncodedTransactionWithStatusMeta, TransactionStatusMeta, UiTransactionStatusMeta,
+};
+
+/// Default per-instruction CU limit the fixtures report in their logs.
+const CU_LIMIT: u64 = 200_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunFixture {
+    pub program_id: Pubkey,
+    pub samples: u64,
+    /// Typical CU of a successful sample.
+    pub base_compute_units: u64,
+    /// Successful samples vary uniformly within `± jitter` of the base.
+    pub jitter: u64,
+    /// Share of successful samples with roughly triple the base CU.
+    pub outlier_rate: f64,
+    /// Share of samples that fail, spread over a mix of failure classes.
+    pub failure_rate: f64,
+    pub first_slot: u64,
+    pub fee: u64,
+    pub seed: u64,
+}
+
+impl Default for RunFixture {
+    fn default() -> Self {
+        Self {
+            program_id: Pubkey::new_from_array([7; 32]),
+            samples: 100,
+            base_compute_units: 1_500,
+            jitter: 30,
+            outlier_rate: 0.02,
+            failure_rate: 0.05,
+            first_slot: 1_000,
+            fee: 5_000,
+            seed: 0,
+        }
+    }
+}
+
+/// What happened to one generated transaction.
+#[derive(Debug, Clone, PartialEq)]
+enum Outcome {
+    Landed {
+        compute_units: u64,
+        err: Option<TransactionError>,
+    },
+    Dropped(FailureClass),
+}
+
+impl RunFixture {
+    /// A run as the harness would have recorded it, failures included.
+    pub fn run(&self) -> Run {
+        let mut run = Run::new(self.program_id);
+        for (index, outcome) in self.outcomes().into_iter().enumerate() {
+            let index = index as u64;
+            match outcome {
+                Outcome::Landed { compute_units, err } => {
+                    if let Some(err) = &err {
+                        run.failures
+                            .record(FailureClass::from_transaction_error(err));
+                    }
+                    run.push(Sample::new(
+                        index,
+                        signature(index),
+                        self.slot(index),
+                        Some(compute_units),
+                    ));
+                }
+                Outcome::Dropped(class) => run.failures.record(class),
+            }
+        }
+        run.attempted = self.samples;
+        run
+    }
+
+    /// Confirmed transactions for every sample that landed, with the logs the
+    /// counter instruction produces and a status meta matching the sample.
+    pub fn transactions(&self) -> Vec<ConfirmedTxView> {
+        self.outcomes()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, outcome)| match outcome {
+                Outcome::Landed { compute_units, err } => {
+                    Some(self.transaction(index as u64, compute_units, err))
+                }
+                Outcome::Dropped(_) => None,
+            })
+            .collect()
+    }
+
+    fn outcomes(&self) -> Vec<Outcome> {
+        let mut rng = SplitMix64(self.seed);
+        (0..self.samples)
+            .map(|_| {
+                if rng.unit() < self.failure_rate {
+                    return match rng.below(4) {
+                        0 => Outcome::Dropped(FailureClass::BlockhashExpired),
+                        1 => Outcome::Dropped(FailureClass::NotLanded),
+                        2 => Outcome::Landed {
+                            compute_units: CU_LIMIT,
+                            err: Some(TransactionError::InstructionError(
+                                0,
+                                InstructionError::ComputationalBudgetExceeded,
+                            )),
+                        },
+                        _ => Outcome::Landed {
+                            compute_units: self.base_compute_units / 2,
+                            err: Some(TransactionError::InstructionError(
+                                0,
+                                InstructionError::Custom(1),
+                            )),
+                        },
+                    };
+                }
+                let spread = rng.below(2 * self.jitter as usize + 1) as u64;
+                let mut compute_units =
+                    (self.base_compute_units + spread).saturating_sub(self.jitter);
+                if rng.unit() < self.outlier_rate {
+                    compute_units *= 3;
+                }
+                Outcome::Landed {
+                    compute_units,
+                    err: None,
+                }
+            })
+            .collect()
+    }
+
+    fn slot(&self, index: u64) -> u64 {
+        self.first_slot + index / 4
+    }
+
+    fn transaction(
+        &self,
+        index: u64,
+        compute_units: u64,
+        err: Option<TransactionError>,
+    ) -> ConfirmedTxView {
+        let program = self.program_id;
+        let mut logs = vec![
+            format!("Program {} invoke [1]", program),
+            format!("Program log: Count: {}", index),
+            format!(
+                "Program {} consumed {} of {} compute units",
+                program, compute_units, CU_LIMIT
+            ),
+        ];
+        logs.push(match &err {
+            None => format!("Program {} success", program),
+            Some(err) => format!("Program {} failed: {}", program, err),
+        });
+
+        let meta = TransactionStatusMeta {
+            status: err.map_or(Ok(()), Err),
+            fee: self.fee,
+            pre_balances: vec![1_000_000_000, 1],
+            post_balances: vec![1_000_000_000 - self.fee, 1],
+            log_messages: Some(logs),
+            compute_units_consumed: Some(compute_units),
+            ..TransactionStatusMeta::default()
+        };
+        ConfirmedTxView::new(EncodedConfirmedTransactionWithStatusMeta {
+            slot: self.slot(index),
+            transaction: EncodedTransactionWithStatusMeta {
+                transaction: EncodedTransaction::LegacyBinary(String::new()),
+                meta: Some(UiTransactionStatusMeta::from(meta)),
+                version: None,
+            },
+            block_time: Some(1_700_000_000 + self.slot(index) as i64 * 2 / 5),
+        })
+    }
+}
+
+/// Distinct, stable signature per sample index.
+fn signature(index: u64) -> Signature {
+    let mut bytes = [0u8; 64];
+    bytes[..8].copy_from_slice(&index.to_le_bytes());
+    Signature::from(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fixture_run_matches_transactions() {
+        let fixture = RunFixture {
+            failure_rate: 0.2,
+            seed: 3,
+            ..RunFixture::default()
+        };
+        let run = fixture.run();
+        let transactions = fixture.transactions();
+
+        assert_eq!(run.len(), transactions.len());
+        assert_eq!(run.len() as u64 + dropped(&run), fixture.samples);
+        assert!(run.failures.total() > 0);
+        for (sample, tx) in run.samples().zip(&transactions) {
+            assert_eq!(sample.compute_units, tx.cu_consumed());
+            assert_eq!(sample.slot, tx.slot());
+            assert_eq!(tx.fee(), Some(fixture.fee));
+        }
+
+        assert_eq!(fixture.run().failures, run.failures);
+    }
+
+    fn dropped(run: &Run) -> u64 {
+        (run.failures.get(&FailureClass::BlockhashExpired)
+            + run.failures.get(&FailureClass::NotLanded)) as u64
+    }
+}
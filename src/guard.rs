@@ -0,0 +1,144 @@
+//! Replay guard: a per-payer bitmap PDA recording which nonces already landed,
+//! so throughput experiments can tell unique landings from duplicates.
+//!
+//! [`BenchInstruction::GuardedIncrement`] takes the accounts
+//! `[payer (signer, writable), guard PDA (writable), system program]`.
+//! The guard is created on first use and a nonce seen before fails the
+//! transaction with [`REPLAYED_NONCE`].
+
+use crate::instruction::BenchInstruction;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+    sysvar::Sysvar,
+};
+
+pub const GUARD_SEED: &[u8] = b"guard";
+pub const GUARD_BITMAP_LEN: usize = 1024;
+/// Nonces range over `0..GUARD_CAPACITY`.
+pub const GUARD_CAPACITY: u64 = GUARD_BITMAP_LEN as u64 * 8;
+/// Custom program error returned for a nonce that already landed.
+pub const REPLAYED_NONCE: u32 = 1;
+
+pub fn find_guard_address(program_id: &Pubkey, payer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GUARD_SEED, payer.as_ref()], program_id)
+}
+
+pub fn process_guarded_increment(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    nonce: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer = next_account_info(account_info_iter)?;
+    let guard = next_account_info(account_info_iter)?;
+    let system = next_account_info(account_info_iter)?;
+
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (guard_address, bump) = find_guard_address(program_id, payer.key);
+    if *guard.key != guard_address || !system_program::check_id(system.key) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if nonce >= GUARD_CAPACITY {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    if guard.data_is_empty() {
+        let lamports = Rent::get()?.minimum_balance(GUARD_BITMAP_LEN);
+        invoke_signed(
+            &create_account(
+                payer.key,
+                guard.key,
+                lamports,
+                GUARD_BITMAP_LEN as u64,
+                program_id,
+            ),
+            &[payer.clone(), guard.clone(), system.clone()],
+            &[&[GUARD_SEED, payer.key.as_ref(), &[bump]]],
+        )?;
+    } else if guard.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let mut bitmap = guard.try_borrow_mut_data()?;
+    let (byte, bit) = ((nonce / 8) as usize, 1u8 << (nonce % 8));
+    if bitmap[byte] & bit != 0 {
+        return Err(ProgramError::Custom(REPLAYED_NONCE));
+    }
+    bitmap[byte] |= bit;
+    Ok(())
+}
+
+pub fn is_seen(bitmap: &[u8], nonce: u64) -> bool {
+    bitmap
+        .get((nonce / 8) as usize)
+        .is_some_and(|byte| byte & (1 << (nonce % 8)) != 0)
+}
+
+/// Distinct nonces recorded in the bitmap.
+pub fn seen_count(bitmap: &[u8]) -> u32 {
+    bitmap.iter().map(|byte| byte.count_ones()).sum()
+}
+
+/// Builds the guarded increment instruction for `nonce`.
+pub fn guarded_increment(program_id: &Pubkey, payer: &Pubkey, nonce: u64) -> Instruction {
+    let (guard, _) = find_guard_address(program_id, payer);
+    BenchInstruction::GuardedIncrement { nonce }.to_instruction(
+        *program_id,
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(guard, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// System program `CreateAccount`, encoded by hand to keep the on-chain build
+/// free of extra dependencies. Also creates the [`crate::registry`] PDA.
+pub(crate) fn create_account(
+    payer: &Pubkey,
+    account: &Pubkey,
+    lamports: u64,
+    space: u64,
+    owner: &Pubkey,
+) -> Instruction {
+    let mut data = Vec::with_capacity(52);
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&lamports.to_le_bytes());
+    data.extend_from_slice(&space.to_le_bytes());
+    data.extend_from_slice(owner.as_ref());
+    Instruction::new_with_bytes(
+        system_program::id(),
+        &data,
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*account, true),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bitmap_accessors() {
+        let mut bitmap = vec![0u8; GUARD_BITMAP_LEN];
+        bitmap[0] = 0b0000_0101;
+        bitmap[1] = 0b1000_0000;
+        assert!(is_seen(&bitmap, 0));
+        assert!(!is_seen(&bitmap, 1));
+        assert!(is_seen(&bitmap, 2));
+        assert!(is_seen(&bitmap, 15));
+        assert!(!is_seen(&bitmap, GUARD_CAPACITY));
+        assert_eq!(seen_count(&bitmap), 3);
+    }
+}
@@ -0,0 +1,656 @@
+//! Instructions understood by the benchmark program. The first byte selects the
+//! variant and the rest is its little-endian payload, so one deployment can
+//! serve every CU scenario.
+
+use crate::{curve::CurveOp, math::MathOp, pda::PdaMode, registry::RunSummary};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BenchInstruction {
+    /// Does nothing; measures the fixed cost of invoking the program.
+    Noop,
+    /// Logs `Count: {counter}`.
+    LogCounter { counter: u64 },
+    /// Logs like [`BenchInstruction::LogCounter`] after recording `nonce` in
+    /// the replay guard, see [`crate::guard`].
+    GuardedIncrement { nonce: u64 },
+    /// Spins a wrapping checksum loop `iterations` times and logs
+    /// `Iterations: {iterations} Checksum: {checksum}`.
+    BurnCompute { iterations: u32 },
+    /// Hashes `data` with the `sol_sha256` syscall and logs `Sha256: {hash}`.
+    /// The data is the rest of the payload, so its length sets the cost.
+    Sha256 { data: Vec<u8> },
+    /// Like [`BenchInstruction::Sha256`] with `sol_keccak256`, logging
+    /// `Keccak256: {hash}`.
+    Keccak256 { data: Vec<u8> },
+    /// Recovers the signer of `hash` with `sol_secp256k1_recover` and logs
+    /// `Recovered: {success}`; an invalid signature is logged, not an error.
+    Secp256k1Recover {
+        hash: [u8; 32],
+        recovery_id: u8,
+        signature: [u8; 64],
+    },
+    /// Runs the `op` memory syscall over `len`-byte heap buffers `reps` times
+    /// and logs `MemOps: {op} {len} bytes x{reps}`. Both buffers share the
+    /// 32 KiB heap, so `len` must stay below 16 KiB.
+    MemOps { op: MemOp, len: u32, reps: u16 },
+    /// Makes `count` heap allocations of `size` bytes following `pattern` and
+    /// logs `Allocated: {count} x {size} bytes ({pattern})`.
+    HeapAlloc {
+        pattern: AllocPattern,
+        count: u16,
+        size: u32,
+    },
+    /// Invokes the program in the first account with `depth - 1`, down to 0,
+    /// see [`crate::cpi`].
+    Cpi { depth: u8 },
+    /// Reads the key, lamports and data length of every account passed and
+    /// logs `Touched: {count} accounts, {lamports} lamports, {bytes} bytes`.
+    TouchAccounts,
+    /// Appends `summary` to the publisher's registry, see [`crate::registry`].
+    /// Only builds with the `registry` feature accept it.
+    RecordRun { summary: RunSummary },
+    /// Derives a PDA from `seeds` seeds of `seed_len` bytes `reps` times, see
+    /// [`crate::pda`]. `bump` is the bump seed [`PdaMode::Create`] appends.
+    DerivePda {
+        mode: PdaMode,
+        seeds: u8,
+        seed_len: u8,
+        reps: u16,
+        bump: u8,
+    },
+    /// Checks the only account passed is the PDA of `seeds` seeds of
+    /// `seed_len` bytes, re-derived, or with `bump` when it is given, and
+    /// logs `Pda verified: {find|cached} {seeds}x{seed_len} bytes`. See
+    /// [`crate::pda`].
+    VerifyPda {
+        seeds: u8,
+        seed_len: u8,
+        bump: Option<u8>,
+    },
+    /// Runs `iterations` steps of the `op` kernel, see [`crate::math`].
+    Math { op: MathOp, iterations: u32 },
+    /// Runs `count` steps of the `op` curve25519 group operation, see
+    /// [`crate::curve`].
+    Curve { op: CurveOp, count: u16 },
+    /// Checks the signer holds at least `min_amount` of `mint` and logs
+    /// `Gate passed: {amount} tokens`, see [`crate::token_gate`]. Only builds
+    /// with the `token-gate` feature accept it.
+    TokenGated { mint: Pubkey, min_amount: u64 },
+}
+
+/// Memory syscall exercised by [`BenchInstruction::MemOps`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemOp {
+    Memcpy,
+    Memset,
+    Memmove,
+    Memcmp,
+}
+
+impl MemOp {
+    pub const ALL: [MemOp; 4] = [Self::Memcpy, Self::Memset, Self::Memmove, Self::Memcmp];
+
+    fn from_u8(value: u8) -> Result<Self, ProgramError> {
+        Self::ALL
+            .get(usize::from(value))
+            .copied()
+            .ok_or(ProgramError::InvalidInstructionData)
+    }
+}
+
+/// Allocation pattern of [`BenchInstruction::HeapAlloc`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocPattern {
+    /// `count` boxed slices, all alive until the instruction ends.
+    Boxes,
+    /// One `Vec` grown by `count` unreserved pushes of `size` bytes.
+    VecPush,
+}
+
+impl AllocPattern {
+    pub const ALL: [AllocPattern; 2] = [Self::Boxes, Self::VecPush];
+}
+
+impl fmt::Display for AllocPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Boxes => "boxes",
+            Self::VecPush => "vec-push",
+        })
+    }
+}
+
+impl fmt::Display for MemOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Memcpy => "memcpy",
+            Self::Memset => "memset",
+            Self::Memmove => "memmove",
+            Self::Memcmp => "memcmp",
+        })
+    }
+}
+
+impl BenchInstruction {
+    pub const NOOP: u8 = 0;
+    pub const LOG_COUNTER: u8 = 1;
+    pub const GUARDED_INCREMENT: u8 = 2;
+    pub const BURN_COMPUTE: u8 = 3;
+    pub const SHA256: u8 = 4;
+    pub const KECCAK256: u8 = 5;
+    pub const SECP256K1_RECOVER: u8 = 6;
+    pub const MEM_OPS: u8 = 7;
+    pub const HEAP_ALLOC: u8 = 8;
+    pub const CPI: u8 = 9;
+    pub const TOUCH_ACCOUNTS: u8 = 10;
+    pub const RECORD_RUN: u8 = 11;
+    pub const DERIVE_PDA: u8 = 12;
+    pub const VERIFY_PDA: u8 = 13;
+    pub const MATH: u8 = 14;
+    pub const CURVE: u8 = 15;
+    pub const TOKEN_GATED: u8 = 16;
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        let (&tag, payload) = data
+            .split_first()
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        Ok(match tag {
+            Self::NOOP => Self::Noop,
+            Self::LOG_COUNTER => Self::LogCounter {
+                counter: read_u64(payload)?,
+            },
+            Self::GUARDED_INCREMENT => Self::GuardedIncrement {
+                nonce: read_u64(payload)?,
+            },
+            Self::BURN_COMPUTE => Self::BurnCompute {
+                iterations: read_u32(payload)?,
+            },
+            Self::SHA256 => Self::Sha256 {
+                data: payload.to_vec(),
+            },
+            Self::KECCAK256 => Self::Keccak256 {
+                data: payload.to_vec(),
+            },
+            Self::SECP256K1_RECOVER => {
+                let (hash, rest) = read_array(payload)?;
+                let (&recovery_id, rest) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                Self::Secp256k1Recover {
+                    hash,
+                    recovery_id,
+                    signature: read_array(rest)?.0,
+                }
+            }
+            Self::MEM_OPS => {
+                let (&op, rest) = payload
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let (len, rest) = read_array(rest)?;
+                Self::MemOps {
+                    op: MemOp::from_u8(op)?,
+                    len: u32::from_le_bytes(len),
+                    reps: u16::from_le_bytes(read_array(rest)?.0),
+                }
+            }
+            Self::HEAP_ALLOC => {
+                let (&pattern, rest) = payload
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let (count, rest) = read_array(rest)?;
+                Self::HeapAlloc {
+                    pattern: *AllocPattern::ALL
+                        .get(usize::from(pattern))
+                        .ok_or(ProgramError::InvalidInstructionData)?,
+                    count: u16::from_le_bytes(count),
+                    size: u32::from_le_bytes(read_array(rest)?.0),
+                }
+            }
+            Self::CPI => Self::Cpi {
+                depth: *payload
+                    .first()
+                    .ok_or(ProgramError::InvalidInstructionData)?,
+            },
+            Self::TOUCH_ACCOUNTS => Self::TouchAccounts,
+            Self::RECORD_RUN => Self::RecordRun {
+                summary: RunSummary::unpack(payload)?,
+            },
+            Self::DERIVE_PDA => {
+                let ([mode, seeds, seed_len], rest) = read_array(payload)?;
+                let (reps, rest) = read_array(rest)?;
+                Self::DerivePda {
+                    mode: *PdaMode::ALL
+                        .get(usize::from(mode))
+                        .ok_or(ProgramError::InvalidInstructionData)?,
+                    seeds,
+                    seed_len,
+                    reps: u16::from_le_bytes(reps),
+                    bump: *rest.first().ok_or(ProgramError::InvalidInstructionData)?,
+                }
+            }
+            Self::VERIFY_PDA => {
+                let ([seeds, seed_len], rest) = read_array(payload)?;
+                Self::VerifyPda {
+                    seeds,
+                    seed_len,
+                    bump: rest.first().copied(),
+                }
+            }
+            Self::MATH => {
+                let (&op, rest) = payload
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                Self::Math {
+                    op: *MathOp::ALL
+                        .get(usize::from(op))
+                        .ok_or(ProgramError::InvalidInstructionData)?,
+                    iterations: read_u32(rest)?,
+                }
+            }
+            Self::CURVE => {
+                let ([op], rest) = read_array(payload)?;
+                let (count, _) = read_array(rest)?;
+                Self::Curve {
+                    op: *CurveOp::ALL
+                        .get(usize::from(op))
+                        .ok_or(ProgramError::InvalidInstructionData)?,
+                    count: u16::from_le_bytes(count),
+                }
+            }
+            Self::TOKEN_GATED => {
+                let (mint, rest) = read_array(payload)?;
+                Self::TokenGated {
+                    mint: Pubkey::new_from_array(mint),
+                    min_amount: read_u64(rest)?,
+                }
+            }
+            _ => return Err(ProgramError::InvalidInstructionData),
+        })
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = vec![self.tag()];
+        match self {
+            Self::Noop | Self::TouchAccounts => {}
+            Self::LogCounter { counter } => data.extend_from_slice(&counter.to_le_bytes()),
+            Self::GuardedIncrement { nonce } => data.extend_from_slice(&nonce.to_le_bytes()),
+            Self::BurnCompute { iterations } => data.extend_from_slice(&iterations.to_le_bytes()),
+            Self::Sha256 { data: payload } | Self::Keccak256 { data: payload } => {
+                data.extend_from_slice(payload)
+            }
+            Self::Secp256k1Recover {
+                hash,
+                recovery_id,
+                signature,
+            } => {
+                data.extend_from_slice(hash);
+                data.push(*recovery_id);
+                data.extend_from_slice(signature);
+            }
+            Self::MemOps { op, len, reps } => {
+                data.push(*op as u8);
+                data.extend_from_slice(&len.to_le_bytes());
+                data.extend_from_slice(&reps.to_le_bytes());
+            }
+            Self::HeapAlloc {
+                pattern,
+                count,
+                size,
+            } => {
+                data.push(*pattern as u8);
+                data.extend_from_slice(&count.to_le_bytes());
+                data.extend_from_slice(&size.to_le_bytes());
+            }
+            Self::Cpi { depth } => data.push(*depth),
+            Self::RecordRun { summary } => summary.pack_into(&mut data),
+            Self::DerivePda {
+                mode,
+                seeds,
+                seed_len,
+                reps,
+                bump,
+            } => {
+                data.extend_from_slice(&[*mode as u8, *seeds, *seed_len]);
+                data.extend_from_slice(&reps.to_le_bytes());
+                data.push(*bump);
+            }
+            Self::VerifyPda {
+                seeds,
+                seed_len,
+                bump,
+            } => {
+                data.extend_from_slice(&[*seeds, *seed_len]);
+                data.extend(bump);
+            }
+            Self::Math { op, iterations } => {
+                data.push(*op as u8);
+                data.extend_from_slice(&iterations.to_le_bytes());
+            }
+            Self::Curve { op, count } => {
+                data.push(*op as u8);
+                data.extend_from_slice(&count.to_le_bytes());
+            }
+            Self::TokenGated { mint, min_amount } => {
+                data.extend_from_slice(mint.as_ref());
+                data.extend_from_slice(&min_amount.to_le_bytes());
+            }
+        }
+        data
+    }
+
+    pub fn tag(&self) -> u8 {
+        match self {
+            Self::Noop => Self::NOOP,
+            Self::LogCounter { .. } => Self::LOG_COUNTER,
+            Self::GuardedIncrement { .. } => Self::GUARDED_INCREMENT,
+            Self::BurnCompute { .. } => Self::BURN_COMPUTE,
+            Self::Sha256 { .. } => Self::SHA256,
+            Self::Keccak256 { .. } => Self::KECCAK256,
+            Self::Secp256k1Recover { .. } => Self::SECP256K1_RECOVER,
+            Self::MemOps { .. } => Self::MEM_OPS,
+            Self::HeapAlloc { .. } => Self::HEAP_ALLOC,
+            Self::Cpi { .. } => Self::CPI,
+            Self::TouchAccounts => Self::TOUCH_ACCOUNTS,
+            Self::RecordRun { .. } => Self::RECORD_RUN,
+            Self::DerivePda { .. } => Self::DERIVE_PDA,
+            Self::VerifyPda { .. } => Self::VERIFY_PDA,
+            Self::Math { .. } => Self::MATH,
+            Self::Curve { .. } => Self::CURVE,
+            Self::TokenGated { .. } => Self::TOKEN_GATED,
+        }
+    }
+
+    /// The variant name, e.g. `LogCounter`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Noop => "Noop",
+            Self::LogCounter { .. } => "LogCounter",
+            Self::GuardedIncrement { .. } => "GuardedIncrement",
+            Self::BurnCompute { .. } => "BurnCompute",
+            Self::Sha256 { .. } => "Sha256",
+            Self::Keccak256 { .. } => "Keccak256",
+            Self::Secp256k1Recover { .. } => "Secp256k1Recover",
+            Self::MemOps { .. } => "MemOps",
+            Self::HeapAlloc { .. } => "HeapAlloc",
+            Self::Cpi { .. } => "Cpi",
+            Self::TouchAccounts => "TouchAccounts",
+            Self::RecordRun { .. } => "RecordRun",
+            Self::DerivePda { .. } => "DerivePda",
+            Self::VerifyPda { .. } => "VerifyPda",
+            Self::Math { .. } => "Math",
+            Self::Curve { .. } => "Curve",
+            Self::TokenGated { .. } => "TokenGated",
+        }
+    }
+
+    pub fn to_instruction(&self, program_id: Pubkey, accounts: Vec<AccountMeta>) -> Instruction {
+        Instruction::new_with_bytes(program_id, &self.pack(), accounts)
+    }
+}
+
+/// The name and decoded parameters, e.g. `MemOps op=memcpy len=1024 reps=4`,
+/// to label samples by. Hashed data shows as its length.
+impl fmt::Display for BenchInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())?;
+        match self {
+            Self::Noop | Self::TouchAccounts => Ok(()),
+            Self::LogCounter { counter } => write!(f, " counter={}", counter),
+            Self::GuardedIncrement { nonce } => write!(f, " nonce={}", nonce),
+            Self::BurnCompute { iterations } => write!(f, " iterations={}", iterations),
+            Self::Sha256 { data } | Self::Keccak256 { data } => write!(f, " len={}", data.len()),
+            Self::Secp256k1Recover { recovery_id, .. } => {
+                write!(f, " recovery_id={}", recovery_id)
+            }
+            Self::MemOps { op, len, reps } => write!(f, " op={} len={} reps={}", op, len, reps),
+            Self::HeapAlloc {
+                pattern,
+                count,
+                size,
+            } => write!(f, " pattern={} count={} size={}", pattern, count, size),
+            Self::Cpi { depth } => write!(f, " depth={}", depth),
+            Self::RecordRun { summary } => write!(
+                f,
+                " samples={} median_cu={}",
+                summary.sample_count, summary.median_compute_units
+            ),
+            Self::DerivePda {
+                mode,
+                seeds,
+                seed_len,
+                reps,
+                bump,
+            } => write!(
+                f,
+                " mode={} seeds={} seed_len={} reps={} bump={}",
+                mode, seeds, seed_len, reps, bump
+            ),
+            Self::VerifyPda {
+                seeds,
+                seed_len,
+                bump,
+            } => {
+                write!(f, " seeds={} seed_len={}", seeds, seed_len)?;
+                match bump {
+                    Some(bump) => write!(f, " bump={}", bump),
+                    None => Ok(()),
+                }
+            }
+            Self::Math { op, iterations } => write!(f, " op={} iterations={}", op, iterations),
+            Self::Curve { op, count } => write!(f, " op={} count={}", op, count),
+            Self::TokenGated { min_amount, .. } => write!(f, " min_amount={}", min_amount),
+        }
+    }
+}
+
+/// Labels a transaction by the bench instructions among `instructions`, the
+/// ones to a program in `bench_programs` that decode, `;`-separated.
+pub fn label_instructions(
+    instructions: &[Instruction],
+    bench_programs: &[Pubkey],
+) -> Option<String> {
+    let labels: Vec<String> = instructions
+        .iter()
+        .filter(|instruction| bench_programs.contains(&instruction.program_id))
+        .filter_map(|instruction| BenchInstruction::unpack(&instruction.data).ok())
+        .map(|instruction| instruction.to_string())
+        .collect();
+    (!labels.is_empty()).then(|| labels.join(";"))
+}
+
+fn read_u64(payload: &[u8]) -> Result<u64, ProgramError> {
+    payload
+        .get(..8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+fn read_u32(payload: &[u8]) -> Result<u32, ProgramError> {
+    payload
+        .get(..4)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u32::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+/// Splits a fixed-size array off the front of `payload`.
+fn read_array<const N: usize>(payload: &[u8]) -> Result<([u8; N], &[u8]), ProgramError> {
+    let bytes = payload
+        .get(..N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    Ok((bytes, &payload[N..]))
+}
+
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey =
+    solana_program::pubkey!("ComputeBudget111111111111111111111111111111");
+
+/// Compute budget `SetComputeUnitLimit`, encoded by hand since the
+/// compute budget interface is not part of `solana-program`.
+pub fn set_compute_unit_limit(units: u32) -> Instruction {
+    let mut data = vec![2];
+    data.extend_from_slice(&units.to_le_bytes());
+    Instruction::new_with_bytes(COMPUTE_BUDGET_PROGRAM_ID, &data, vec![])
+}
+
+/// Compute budget `SetComputeUnitPrice`: the priority fee, in micro-lamports
+/// per requested compute unit.
+pub fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+    let mut data = vec![3];
+    data.extend_from_slice(&micro_lamports.to_le_bytes());
+    Instruction::new_with_bytes(COMPUTE_BUDGET_PROGRAM_ID, &data, vec![])
+}
+
+/// Whether `instruction` is a compute budget `SetComputeUnitLimit`.
+pub fn sets_compute_unit_limit(instruction: &Instruction) -> bool {
+    instruction.program_id == COMPUTE_BUDGET_PROGRAM_ID && instruction.data.first() == Some(&2)
+}
+
+/// Whether `instruction` is a compute budget `SetComputeUnitPrice`.
+pub fn sets_compute_unit_price(instruction: &Instruction) -> bool {
+    instruction.program_id == COMPUTE_BUDGET_PROGRAM_ID && instruction.data.first() == Some(&3)
+}
+
+/// Compute budget `RequestHeapFrame`: `bytes` of heap, a multiple of 1 KiB
+/// between 32 KiB and 256 KiB. Only the `bump-allocator` build uses more
+/// than the default 32 KiB.
+pub fn request_heap_frame(bytes: u32) -> Instruction {
+    let mut data = vec![1];
+    data.extend_from_slice(&bytes.to_le_bytes());
+    Instruction::new_with_bytes(COMPUTE_BUDGET_PROGRAM_ID, &data, vec![])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack_round_trip() {
+        for instruction in [
+            BenchInstruction::Noop,
+            BenchInstruction::LogCounter { counter: 7 },
+            BenchInstruction::GuardedIncrement { nonce: u64::MAX },
+            BenchInstruction::BurnCompute { iterations: 1_000 },
+            BenchInstruction::Sha256 { data: vec![] },
+            BenchInstruction::Keccak256 { data: vec![7; 300] },
+            BenchInstruction::Secp256k1Recover {
+                hash: [1; 32],
+                recovery_id: 1,
+                signature: [2; 64],
+            },
+            BenchInstruction::MemOps {
+                op: MemOp::Memcmp,
+                len: 4_096,
+                reps: 3,
+            },
+            BenchInstruction::HeapAlloc {
+                pattern: AllocPattern::VecPush,
+                count: 8,
+                size: 1_024,
+            },
+            BenchInstruction::Cpi { depth: 4 },
+            BenchInstruction::TouchAccounts,
+            BenchInstruction::RecordRun {
+                summary: RunSummary {
+                    run_id: [9; 32],
+                    sample_count: 100,
+                    median_compute_units: 1_500,
+                },
+            },
+            BenchInstruction::DerivePda {
+                mode: PdaMode::Create,
+                seeds: 15,
+                seed_len: 32,
+                reps: 300,
+                bump: 254,
+            },
+            BenchInstruction::VerifyPda {
+                seeds: 2,
+                seed_len: 8,
+                bump: None,
+            },
+            BenchInstruction::VerifyPda {
+                seeds: 2,
+                seed_len: 8,
+                bump: Some(253),
+            },
+            BenchInstruction::Math {
+                op: MathOp::U256Div,
+                iterations: 64,
+            },
+            BenchInstruction::Curve {
+                op: CurveOp::RistrettoMsm,
+                count: 256,
+            },
+            BenchInstruction::TokenGated {
+                mint: Pubkey::new_unique(),
+                min_amount: 1,
+            },
+        ] {
+            let data = instruction.pack();
+            assert_eq!(data[0], instruction.tag());
+            assert_eq!(BenchInstruction::unpack(&data), Ok(instruction));
+        }
+
+        assert_eq!(
+            BenchInstruction::LogCounter { counter: 7 }.pack()[1..],
+            7u64.to_le_bytes()
+        );
+        for invalid in [
+            &[][..],
+            &[BenchInstruction::LOG_COUNTER, 1, 2],
+            &[BenchInstruction::SECP256K1_RECOVER; 97],
+            &[BenchInstruction::MEM_OPS, 4, 0, 0, 0, 0, 0, 0],
+            &[BenchInstruction::CPI],
+            &[BenchInstruction::RECORD_RUN; 44],
+            &[BenchInstruction::DERIVE_PDA, 2, 1, 1, 1, 0, 255],
+            &[BenchInstruction::VERIFY_PDA, 1],
+            &[BenchInstruction::MATH, 6, 1, 0, 0, 0],
+            &[BenchInstruction::CURVE, 4, 1, 0],
+            &[BenchInstruction::CURVE, 0, 1],
+            &[BenchInstruction::TOKEN_GATED; 40],
+            &[0xff],
+        ] {
+            assert_eq!(
+                BenchInstruction::unpack(invalid),
+                Err(ProgramError::InvalidInstructionData)
+            );
+        }
+    }
+    #[test]
+    fn test_label_instructions() {
+        let program_id = Pubkey::new_unique();
+        let instructions = [
+            set_compute_unit_limit(200_000),
+            BenchInstruction::MemOps {
+                op: MemOp::Memcpy,
+                len: 1_024,
+                reps: 4,
+            }
+            .to_instruction(program_id, vec![]),
+            BenchInstruction::Sha256 { data: vec![0; 64] }.to_instruction(program_id, vec![]),
+        ];
+        assert_eq!(
+            label_instructions(&instructions, &[program_id]).as_deref(),
+            Some("MemOps op=memcpy len=1024 reps=4;Sha256 len=64")
+        );
+        assert_eq!(label_instructions(&instructions[..1], &[program_id]), None);
+        assert_eq!(
+            BenchInstruction::VerifyPda {
+                seeds: 2,
+                seed_len: 8,
+                bump: Some(253),
+            }
+            .to_string(),
+            "VerifyPda seeds=2 seed_len=8 bump=253"
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Leader-schedule-aware sending and per-leader reporting, to detect CU or
+//! landing effects that depend on which validator produced the block.
+
+use crate::sdk::{CommitmentConfig, RpcClient};
+use solana_program::pubkey::Pubkey;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    str::FromStr,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Slot leaders for a contiguous range of upcoming slots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeaderSchedule {
+    first_slot: u64,
+    leaders: Vec<Pubkey>,
+}
+
+impl LeaderSchedule {
+    pub fn new(first_slot: u64, leaders: Vec<Pubkey>) -> Self {
+        Self {
+            first_slot,
+            leaders,
+        }
+    }
+
+    /// Fetches leaders for the next `slots` slots (the RPC caps this at 5000).
+    pub fn fetch(rpc_client: &RpcClient, slots: u64) -> Result<Self, Box<dyn Error>> {
+        let first_slot = rpc_client.get_slot_with_commitment(CommitmentConfig::processed())?;
+        let leaders = rpc_client.get_slot_leaders(first_slot, slots.min(5000))?;
+        Ok(Self::new(first_slot, leaders))
+    }
+
+    pub fn leader_at(&self, slot: u64) -> Option<Pubkey> {
+        let offset = usize::try_from(slot.checked_sub(self.first_slot)?).ok()?;
+        self.leaders.get(offset).copied()
+    }
+
+    pub fn contains(&self, leader: &Pubkey) -> bool {
+        self.leaders.contains(leader)
+    }
+}
+
+/// How sends are distributed over leaders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LeaderTargeting {
+    /// Only send while this validator is leader.
+    Only(Pubkey),
+    /// Send at most this many transactions per leader rotation.
+    Spread { per_leader: usize },
+}
+
+/// A validator identity, or `spread:N`.
+impl FromStr for LeaderTargeting {
+    type Err = String;
+
+    fn from_str(target: &str) -> Result<Self, Self::Err> {
+        match target.strip_prefix("spread:") {
+            Some(n) => n
+                .parse()
+                .map(|per_leader| Self::Spread { per_leader })
+                .map_err(|e| e.to_string()),
+            None => target.parse().map(Self::Only).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Holds each send back until the current leader matches the targeting.
+#[derive(Debug, Clone)]
+pub struct LeaderGate {
+    schedule: LeaderSchedule,
+    targeting: LeaderTargeting,
+    current: Option<Pubkey>,
+    sent_to_current: usize,
+}
+
+impl LeaderGate {
+    pub fn new(schedule: LeaderSchedule, targeting: LeaderTargeting) -> Self {
+        Self {
+            schedule,
+            targeting,
+            current: None,
+            sent_to_current: 0,
+        }
+    }
+
+    /// Blocks until the next send is allowed and returns the leader it targets.
+    pub fn wait_turn(
+        &mut self,
+        rpc_client: &RpcClient,
+        timeout: Duration,
+    ) -> Result<Pubkey, Box<dyn Error>> {
+        if let LeaderTargeting::Only(target) = self.targeting {
+            if !self.schedule.contains(&target) {
+                return Err(format!("{} is not leader in the fetched schedule", target).into());
+            }
+        }
+
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            let slot = rpc_client.get_slot_with_commitment(CommitmentConfig::processed())?;
+            let leader = self
+                .schedule
+                .leader_at(slot)
+                .ok_or_else(|| format!("slot {} is past the fetched leader schedule", slot))?;
+            if self.admit(leader) {
+                return Ok(leader);
+            }
+            sleep(POLL_INTERVAL);
+        }
+        Err(format!("no send slot for {:?} within {:?}", self.targeting, timeout).into())
+    }
+
+    fn admit(&mut self, leader: Pubkey) -> bool {
+        match self.targeting {
+            LeaderTargeting::Only(target) => leader == target,
+            LeaderTargeting::Spread { per_leader } => {
+                if self.current != Some(leader) {
+                    self.current = Some(leader);
+                    self.sent_to_current = 0;
+                }
+                if self.sent_to_current < per_leader.max(1) {
+                    self.sent_to_current += 1;
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LeaderStats {
+    /// Sends made while this validator was leader.
+    pub sent: usize,
+    /// Samples that landed in a block this validator produced.
+    pub landed: usize,
+    pub compute_units: Vec<u64>,
+}
+
+impl LeaderStats {
+    pub fn mean_compute_units(&self) -> Option<f64> {
+        if self.compute_units.is_empty() {
+            return None;
+        }
+        Some(self.compute_units.iter().sum::<u64>() as f64 / self.compute_units.len() as f64)
+    }
+}
+
+/// Sent, landed and CU figures per leader identity.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LeaderReport(BTreeMap<Pubkey, LeaderStats>);
+
+impl LeaderReport {
+    pub fn record_sent(&mut self, leader: Pubkey) {
+        self.0.entry(leader).or_default().sent += 1;
+    }
+
+    pub fn record_landed(&mut self, leader: Pubkey, compute_units: Option<u64>) {
+        let stats = self.0.entry(leader).or_default();
+        stats.landed += 1;
+        stats.compute_units.extend(compute_units);
+    }
+
+    pub fn get(&self, leader: &Pubkey) -> Option<&LeaderStats> {
+        self.0.get(leader)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Pubkey, &LeaderStats)> {
+        self.0.iter()
+    }
+}
+
+impl fmt::Display for LeaderReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (leader, stats) in self.iter() {
+            write!(
+                f,
+                "\n  {}: sent {}, landed {}",
+                leader, stats.sent, stats.landed
+            )?;
+            if let Some(mean) = stats.mean_compute_units() {
+                write!(f, ", mean CU {:.1}", mean)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_schedule_lookup_and_spread() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let schedule = LeaderSchedule::new(100, vec![a, a, b, b]);
+        assert_eq!(schedule.leader_at(99), None);
+        assert_eq!(schedule.leader_at(101), Some(a));
+        assert_eq!(schedule.leader_at(102), Some(b));
+        assert_eq!(schedule.leader_at(104), None);
+
+        let mut gate = LeaderGate::new(schedule, LeaderTargeting::Spread { per_leader: 2 });
+        let admitted: Vec<bool> = [a, a, a, b, b, b].map(|l| gate.admit(l)).to_vec();
+        assert_eq!(admitted, [true, true, false, true, true, false]);
+
+        let mut report = LeaderReport::default();
+        report.record_sent(a);
+        report.record_sent(a);
+        report.record_landed(a, Some(300));
+        report.record_landed(a, Some(500));
+        assert_eq!(report.get(&a).unwrap().mean_compute_units(), Some(400.0));
+        assert_eq!(report.get(&b), None);
+    }
+}
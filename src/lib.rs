@@ -1,182 +1,411 @@
+//! On-chain benchmark program plus off-chain tooling for measuring how compute
+//! unit consumption relates to program size.
+//!
+//! Downstream crates should import from [`prelude`], which is covered by semver.
+//! Other public modules are usable but may grow; those behind the `unstable`
+//! feature (`search`, `snapshot`) can change in any release.
+
+/// Ends the section `$name` of the per-section CU breakdown when built with
+/// `section-markers`: logs `Section: $name` and the CU remaining, which
+/// [`attribution::parse_invocations`] turns into [`attribution::InvocationNode::sections`].
+/// Each marker costs about 200 CU, counted in the section it ends.
+macro_rules! section {
+    ($name:literal) => {
+        if cfg!(feature = "section-markers") {
+            solana_program::log::sol_log(concat!("Section: ", $name));
+            solana_program::log::sol_log_compute_units();
+        }
+    };
+}
+
+use instruction::{AllocPattern, BenchInstruction, MemOp};
 use solana_program::{
-    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, msg, pubkey::Pubkey,
+    account_info::AccountInfo,
+    entrypoint,
+    entrypoint::ProgramResult,
+    hash, keccak, msg,
+    program_memory::{sol_memcmp, sol_memcpy, sol_memmove, sol_memset},
+    pubkey::Pubkey,
+    secp256k1_recover::secp256k1_recover,
 };
 
+#[cfg(not(target_os = "solana"))]
+pub mod accounting;
+#[cfg(feature = "bump-allocator")]
+pub mod allocator;
+#[cfg(not(target_os = "solana"))]
+pub mod attribution;
+#[cfg(all(feature = "program-test", not(target_os = "solana")))]
+pub mod banks;
+#[cfg(not(target_os = "solana"))]
+pub mod blockhash;
+#[cfg(not(target_os = "solana"))]
+pub mod calibration;
+#[cfg(not(target_os = "solana"))]
+pub mod capacity;
+#[cfg(not(target_os = "solana"))]
+pub mod client;
+#[cfg(not(target_os = "solana"))]
+pub mod clock;
+#[cfg(not(target_os = "solana"))]
+pub mod cluster;
+#[cfg(not(target_os = "solana"))]
+pub mod compare;
+#[cfg(not(target_os = "solana"))]
+pub(crate) mod compat;
+#[cfg(not(target_os = "solana"))]
+pub mod config_file;
+#[cfg(not(target_os = "solana"))]
+pub mod control;
+pub mod cpi;
+pub mod curve;
+#[cfg(not(target_os = "solana"))]
+pub mod deadline;
+#[cfg(not(target_os = "solana"))]
+pub mod deployment;
+#[cfg(not(target_os = "solana"))]
+pub mod error;
+#[cfg(not(target_os = "solana"))]
+pub mod export;
+#[cfg(not(target_os = "solana"))]
+pub mod failure;
+#[cfg(not(target_os = "solana"))]
+pub mod fixtures;
+pub mod guard;
+pub mod instruction;
+#[cfg(not(target_os = "solana"))]
+pub mod leader;
+#[cfg(not(target_os = "solana"))]
+pub mod lock;
+#[cfg(not(target_os = "solana"))]
+pub mod lookup_table;
+pub mod math;
+#[cfg(not(target_os = "solana"))]
+pub mod matrix;
+#[cfg(not(target_os = "solana"))]
+pub mod metrics;
+pub mod padding;
+#[cfg(all(feature = "keyring", not(target_os = "solana")))]
+pub mod payer;
+pub mod pda;
+#[cfg(not(target_os = "solana"))]
+pub mod predict;
+#[cfg(not(target_os = "solana"))]
+pub mod prelude;
+#[cfg(not(target_os = "solana"))]
+pub mod profile;
+#[cfg(not(target_os = "solana"))]
+pub mod program_ids;
+#[cfg(not(target_os = "solana"))]
+pub mod redact;
+pub mod registry;
+#[cfg(not(target_os = "solana"))]
+pub mod run;
+#[cfg(not(target_os = "solana"))]
+pub mod sample;
+#[cfg(all(feature = "scripting", not(target_os = "solana")))]
+pub mod script;
+#[cfg(not(target_os = "solana"))]
+pub mod sdk;
+#[cfg(all(any(feature = "unstable", test), not(target_os = "solana")))]
+pub mod search;
+#[cfg(all(feature = "threaded-sender", not(target_os = "solana")))]
+pub mod sender;
+#[cfg(not(target_os = "solana"))]
+pub mod signer_source;
+#[cfg(not(target_os = "solana"))]
+pub mod size;
+#[cfg(not(target_os = "solana"))]
+pub mod slo;
+#[cfg(all(any(feature = "unstable", test), not(target_os = "solana")))]
+pub mod snapshot;
+#[cfg(not(target_os = "solana"))]
+pub mod stats;
+#[cfg(not(target_os = "solana"))]
+pub mod survey;
+#[cfg(not(target_os = "solana"))]
+pub mod synthetic;
+#[cfg(all(feature = "otlp", not(target_os = "solana")))]
+pub mod telemetry;
+#[cfg(not(target_os = "solana"))]
+pub mod test_utils;
+pub mod token_gate;
+#[cfg(not(target_os = "solana"))]
+pub mod tx_view;
+#[cfg(not(target_os = "solana"))]
+pub mod window;
+#[cfg(not(target_os = "solana"))]
+pub mod workload;
+
 entrypoint!(process_instruction);
 
+#[cfg(all(feature = "bump-allocator", target_os = "solana"))]
+#[global_allocator]
+static ALLOCATOR: allocator::UpwardBumpAllocator = allocator::UpwardBumpAllocator;
+
 pub fn process_instruction(
-    _program_id: &Pubkey,
-    _accounts: &[AccountInfo],
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    let counter = instruction_data
-        .get(..8)
-        .map(|slice| u64::from_le_bytes(slice.try_into().unwrap()))
-        .unwrap_or(0);
-    msg!("Count: {}", counter);
+    padding::retain();
+    section!("entrypoint");
+    let instruction = BenchInstruction::unpack(instruction_data)?;
+    section!("unpack");
+    match instruction {
+        BenchInstruction::Noop => section!("execute"),
+        BenchInstruction::LogCounter { counter } => {
+            section!("execute");
+            msg!("Count: {}", counter);
+        }
+        BenchInstruction::GuardedIncrement { nonce } => {
+            guard::process_guarded_increment(program_id, accounts, nonce)?;
+            section!("execute");
+            msg!("Count: {}", nonce);
+        }
+        BenchInstruction::BurnCompute { iterations } => {
+            let checksum = burn_compute(iterations);
+            section!("execute");
+            msg!("Iterations: {} Checksum: {}", iterations, checksum);
+        }
+        BenchInstruction::Sha256 { data } => {
+            let hash = hash::hash(&data);
+            section!("execute");
+            msg!("Sha256: {}", hash);
+        }
+        BenchInstruction::Keccak256 { data } => {
+            let hash = keccak::hash(&data);
+            section!("execute");
+            msg!("Keccak256: {}", hash);
+        }
+        BenchInstruction::Secp256k1Recover {
+            hash,
+            recovery_id,
+            signature,
+        } => {
+            let recovered = secp256k1_recover(&hash, recovery_id, &signature).is_ok();
+            section!("execute");
+            msg!("Recovered: {}", recovered);
+        }
+        BenchInstruction::MemOps { op, len, reps } => {
+            mem_ops(op, len as usize, reps);
+            section!("execute");
+            msg!("MemOps: {} {} bytes x{}", op, len, reps);
+        }
+        BenchInstruction::HeapAlloc {
+            pattern,
+            count,
+            size,
+        } => {
+            heap_alloc(pattern, count, size as usize);
+            section!("execute");
+            msg!("Allocated: {} x {} bytes ({})", count, size, pattern);
+        }
+        BenchInstruction::Cpi { depth } => {
+            cpi::process_cpi(accounts, depth)?;
+            section!("execute");
+        }
+        BenchInstruction::TouchAccounts => {
+            let (lamports, bytes) = touch_accounts(accounts);
+            section!("execute");
+            msg!(
+                "Touched: {} accounts, {} lamports, {} bytes",
+                accounts.len(),
+                lamports,
+                bytes
+            );
+        }
+        BenchInstruction::DerivePda {
+            mode,
+            seeds,
+            seed_len,
+            reps,
+            bump,
+        } => {
+            let (_, bump) = pda::derive_pdas(program_id, mode, seeds, seed_len, reps, bump)?;
+            section!("execute");
+            msg!(
+                "Pda: {} {}x{} bytes x{} bump {}",
+                mode,
+                seeds,
+                seed_len,
+                reps,
+                bump
+            );
+        }
+        BenchInstruction::VerifyPda {
+            seeds,
+            seed_len,
+            bump,
+        } => {
+            pda::process_verify_pda(program_id, accounts, seeds, seed_len, bump)?;
+            section!("execute");
+            msg!(
+                "Pda verified: {} {}x{} bytes",
+                if bump.is_some() { "cached" } else { "find" },
+                seeds,
+                seed_len
+            );
+        }
+        BenchInstruction::Math { op, iterations } => {
+            let checksum = math::math(op, iterations)?;
+            section!("execute");
+            msg!("Math: {} x{} checksum {}", op, iterations, checksum);
+        }
+        BenchInstruction::Curve { op, count } => {
+            let checksum = curve::curve(op, count)?;
+            section!("execute");
+            msg!("Curve: {} x{} checksum {}", op, count, checksum);
+        }
+        #[cfg(feature = "registry")]
+        BenchInstruction::RecordRun { summary } => {
+            registry::process_record_run(program_id, accounts, &summary)?;
+            section!("execute");
+            msg!(
+                "Recorded: {} samples, median {} CU",
+                summary.sample_count,
+                summary.median_compute_units
+            );
+        }
+        #[cfg(not(feature = "registry"))]
+        BenchInstruction::RecordRun { .. } => {
+            return Err(solana_program::program_error::ProgramError::InvalidInstructionData)
+        }
+        #[cfg(feature = "token-gate")]
+        BenchInstruction::TokenGated { mint, min_amount } => {
+            let amount = token_gate::process_token_gated(accounts, &mint, min_amount)?;
+            section!("execute");
+            msg!("Gate passed: {} tokens", amount);
+        }
+        #[cfg(not(feature = "token-gate"))]
+        BenchInstruction::TokenGated { .. } => {
+            return Err(solana_program::program_error::ProgramError::InvalidInstructionData)
+        }
+    }
+    section!("log");
     Ok(())
 }
 
-#[cfg(test)]
-mod test {
-    use solana_client::rpc_client::RpcClient;
-    use solana_sdk::{
-        bpf_loader_upgradeable::UpgradeableLoaderState,
-        commitment_config::CommitmentConfig,
-        instruction::Instruction,
-        message::Message,
-        pubkey::Pubkey,
-        signature::{Keypair, Signer},
-        transaction::Transaction,
-    };
-    use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
-    use std::{str::FromStr, thread::sleep, time::Duration};
-    use tracing::{info, warn};
-
-    fn get_program_size(
-        rpc_client: &RpcClient,
-        program_id: &str,
-    ) -> Result<(usize, usize), Box<dyn std::error::Error>> {
-        let program_pubkey = Pubkey::from_str(program_id)?;
-        let program_account = rpc_client.get_account(&program_pubkey)?;
-
-        let (program_data_size, total_size) =
-            if program_account.owner == solana_sdk::bpf_loader::id() {
-                (program_account.data.len(), program_account.data.len())
-            } else if program_account.owner == solana_sdk::bpf_loader_upgradeable::id() {
-                // Deserialize the program account state
-                let state: UpgradeableLoaderState = bincode::deserialize(&program_account.data)?;
-
-                match state {
-                    UpgradeableLoaderState::Program {
-                        programdata_address,
-                    } => {
-                        let program_data_account = rpc_client.get_account(&programdata_address)?;
-                        let program_data: UpgradeableLoaderState =
-                            bincode::deserialize(&program_data_account.data)?;
-
-                        match program_data {
-                            UpgradeableLoaderState::ProgramData {
-                                slot: _,
-                                upgrade_authority_address: _,
-                            } => {
-                                let metadata_size =
-                                    UpgradeableLoaderState::size_of_programdata_metadata();
-                                let program_size = program_data_account.data.len() - metadata_size;
-                                (program_size, program_data_account.data.len())
-                            }
-                            _ => return Err("Invalid program data account".into()),
-                        }
-                    }
-                    _ => return Err("Not a program account".into()),
-                }
-            } else {
-                return Err("Not a BPF program".into());
-            };
-
-        Ok((program_data_size, total_size))
+/// Deterministic busy loop behind [`BenchInstruction::BurnCompute`]; each
+/// iteration costs the same, so CU grows linearly with `iterations`.
+pub fn burn_compute(iterations: u32) -> u64 {
+    let mut checksum = 0u64;
+    for i in 0..iterations {
+        checksum = std::hint::black_box(checksum.rotate_left(5) ^ u64::from(i))
+            .wrapping_mul(0x9e37_79b9_7f4a_7c15);
     }
+    checksum
+}
 
-    #[test]
-    fn test_multiple_transactions() {
-        // Initialize tracing
-        tracing_subscriber::fmt::init();
-
-        let rpc_url = "http://127.0.0.1:8899".to_string();
-        let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
-
-        let program_id = "H2GM7Vci4vVTWUfM4CQ5gWuUXF56wgXC4CkUY2Ea7mgC";
-
-        // Get program size
-        match get_program_size(&rpc_client, program_id) {
-            Ok((program_size, total_size)) => {
-                info!("Program data size: {} bytes", program_size);
-                info!("Total account size: {} bytes", total_size);
+/// Body of [`BenchInstruction::MemOps`]: `reps` calls of the `op` syscall
+/// over two `len`-byte buffers. Returns the first byte of the destination.
+pub fn mem_ops(op: MemOp, len: usize, reps: u16) -> u8 {
+    // Mutable only because older `sol_memmove` takes a `*mut` source
+    let mut src = vec![1u8; len];
+    let mut dst = vec![0u8; len];
+    for _ in 0..reps {
+        match op {
+            MemOp::Memcpy => sol_memcpy(&mut dst, &src, len),
+            MemOp::Memset => sol_memset(&mut dst, 2, len),
+            // SAFETY: both buffers hold `len` bytes and do not overlap
+            MemOp::Memmove => unsafe { sol_memmove(dst.as_mut_ptr(), src.as_mut_ptr(), len) },
+            MemOp::Memcmp => {
+                std::hint::black_box(sol_memcmp(&dst, &src, len));
             }
-            Err(e) => warn!("Failed to get program size: {}", e),
         }
+    }
+    dst.first().copied().unwrap_or_default()
+}
 
-        let program_pubkey = program_id.parse().unwrap();
-        let payer = Keypair::new();
-
-        info!("Requesting airdrop for {}", payer.pubkey());
-        let airdrop_signature = rpc_client
-            .request_airdrop(&payer.pubkey(), 1_000_000_000)
-            .unwrap();
+/// Body of [`BenchInstruction::TouchAccounts`]: total lamports and data
+/// length, with every key read as well.
+pub fn touch_accounts(accounts: &[AccountInfo]) -> (u64, usize) {
+    accounts.iter().fold((0, 0), |(lamports, bytes), account| {
+        std::hint::black_box(account.key);
+        (lamports + account.lamports(), bytes + account.data_len())
+    })
+}
 
-        loop {
-            if let Ok(_) = rpc_client.confirm_transaction(&airdrop_signature) {
-                if let Ok(balance) = rpc_client.get_balance(&payer.pubkey()) {
-                    if balance > 0 {
-                        info!("Airdrop confirmed! Balance: {}", balance);
-                        break;
-                    }
-                }
+/// Body of [`BenchInstruction::HeapAlloc`]. Returns the bytes allocated.
+pub fn heap_alloc(pattern: AllocPattern, count: u16, size: usize) -> usize {
+    match pattern {
+        AllocPattern::Boxes => {
+            let boxes: Vec<Box<[u8]>> = (0..count)
+                .map(|_| vec![0u8; size].into_boxed_slice())
+                .collect();
+            std::hint::black_box(&boxes).iter().map(|b| b.len()).sum()
+        }
+        AllocPattern::VecPush => {
+            let chunk = vec![0u8; size];
+            let mut pushed = Vec::new();
+            for _ in 0..count {
+                pushed.extend_from_slice(&chunk);
             }
-            sleep(Duration::from_millis(100));
+            std::hint::black_box(&pushed).len()
         }
+    }
+}
 
-        let mut signatures = Vec::with_capacity(100);
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "otlp")]
+    use crate::telemetry::init_tracing;
+    use crate::{
+        client::{BenchConfig, BenchRunner},
+        size::get_program_size,
+        survey::{SizeSurvey, SURVEY_RPC_URL, WELL_KNOWN_PROGRAMS},
+    };
+    use solana_client::rpc_client::RpcClient;
+    use solana_sdk::commitment_config::CommitmentConfig;
+    use tracing::info;
 
-        // Get blockhash once before the loop
-        let recent_blockhash = rpc_client.get_latest_blockhash().unwrap();
+    /// Logs the size distribution of well-known mainnet programs and where this
+    /// program falls in it. Run with `cargo test -- --ignored test_survey`, or
+    /// use `cu-bench survey`.
+    #[test]
+    #[ignore]
+    fn test_survey_program_sizes() {
+        tracing_subscriber::fmt::init();
+        let rpc_url =
+            std::env::var("SURVEY_RPC_URL").unwrap_or_else(|_| SURVEY_RPC_URL.to_string());
+        let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
 
-        // Send all transactions first
-        for i in 0..100 {
-            let instruction_data = (i as u64).to_le_bytes().to_vec();
-            let instruction =
-                Instruction::new_with_bytes(program_pubkey, &instruction_data, vec![]);
+        let survey = SizeSurvey::fetch(&rpc_client, WELL_KNOWN_PROGRAMS);
+        info!("{}", survey);
 
-            let message = Message::new_with_blockhash(
-                &[instruction],
-                Some(&payer.pubkey()),
-                &recent_blockhash,
-            );
-            let transaction = Transaction::new(&[&payer], message, recent_blockhash);
-
-            match rpc_client.send_transaction(&transaction) {
-                Ok(signature) => {
-                    info!("Transaction {} sent: {}", i, signature);
-                    signatures.push((i, signature));
-                }
-                Err(e) => warn!("Failed to send transaction {}: {}", i, e),
+        // Compare against the locally deployed benchmark program, if a validator is up
+        let local_client = RpcClient::new("http://127.0.0.1:8899".to_string());
+        if let Ok(info) = get_program_size(
+            &local_client,
+            "H2GM7Vci4vVTWUfM4CQ5gWuUXF56wgXC4CkUY2Ea7mgC",
+        ) {
+            if let Some(rank) = survey.percentile_rank(info.program_data_size) {
+                info!(
+                    "Benchmark program ({} bytes) is at the {:.0}th percentile",
+                    info.program_data_size, rank
+                );
             }
         }
+    }
 
-        info!("All transactions sent, now verifying...");
-
-        for (i, signature) in signatures {
-            let mut retries = 10;
-            let mut tx_details = None;
+    #[test]
+    fn test_multiple_transactions() {
+        // Initialize tracing; with the otlp feature, OTLP_ENDPOINT also exports
+        // the run, phase and sample spans
+        #[cfg(feature = "otlp")]
+        let _telemetry = init_tracing(std::env::var("OTLP_ENDPOINT").ok().as_deref()).unwrap();
+        #[cfg(not(feature = "otlp"))]
+        tracing_subscriber::fmt::init();
 
-            while retries > 0 {
-                match rpc_client.get_transaction(&signature, UiTransactionEncoding::Base64) {
-                    Ok(details) => {
-                        tx_details = Some(details);
-                        break;
-                    }
-                    Err(_) => {
-                        sleep(Duration::from_millis(50));
-                        retries -= 1;
-                    }
-                }
-            }
+        let report = BenchRunner::new(BenchConfig::from_env().unwrap())
+            .run()
+            .unwrap();
+        info!("{}", report);
 
-            if let Some(details) = tx_details {
-                if let Some(meta) = details.transaction.meta {
-                    match meta.compute_units_consumed {
-                        OptionSerializer::Some(cu) => {
-                            info!(
-                                "Transaction {} (counter: {}): Compute Units used: {}",
-                                i + 1,
-                                i,
-                                cu
-                            );
-                        }
-                        _ => warn!("Transaction {}: Compute units not available", i + 1),
-                    }
-                }
-            }
-        }
+        assert!(
+            report.log_mismatches.is_empty(),
+            "samples missing their expected log: {:?}",
+            report.log_mismatches
+        );
     }
 }
@@ -0,0 +1,149 @@
+//! Advisory lock preventing two harness instances from driving the same program
+//! (and its counter accounts) at once.
+//!
+//! The lock file names the holder's pid and a token unique to the lock. A lock
+//! whose pid is no longer running was left by a crashed run and is taken over
+//! as stale, and a holder only removes the file while it still carries its own
+//! token, so a run whose lock was forced away leaves the new holder's alone.
+
+use solana_program::pubkey::Pubkey;
+use std::{
+    error::Error,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
+
+/// Tells apart locks taken by one process, which share a pid.
+static NEXT_TOKEN: AtomicU64 = AtomicU64::new(0);
+
+/// Held for the duration of a run; the lock file is removed on drop unless
+/// another run has taken it over.
+#[derive(Debug)]
+pub struct ExperimentLock {
+    path: PathBuf,
+    contents: String,
+}
+
+impl ExperimentLock {
+    /// Locks `program_id` using a file in the system temp directory.
+    pub fn acquire(program_id: &Pubkey, force: bool) -> Result<Self, Box<dyn Error>> {
+        let path = std::env::temp_dir().join(format!("cu-bench-{}.lock", program_id));
+        Self::acquire_at(path, force)
+    }
+
+    /// Creates the lock file at `path`. If it already exists the run is refused,
+    /// unless `force` is set, in which case the existing lock is taken over.
+    pub fn acquire_at(path: impl Into<PathBuf>, force: bool) -> Result<Self, Box<dyn Error>> {
+        let path = path.into();
+        let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                let holder = fs::read_to_string(&path).unwrap_or_default();
+                let holder = holder.trim();
+                match holder_pid(holder).and_then(is_running) {
+                    Some(false) => warn!(
+                        "Taking over stale lock {} ({}); its run is no longer running",
+                        path.display(),
+                        holder
+                    ),
+                    _ if !force => {
+                        return Err(format!(
+                            "{} is held by another run ({}); use force to override",
+                            path.display(),
+                            holder
+                        )
+                        .into())
+                    }
+                    _ => warn!("Forcing lock {} away from {}", path.display(), holder),
+                }
+                OpenOptions::new().write(true).truncate(true).open(&path)?
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let since = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let contents = format!(
+            "pid {} since {} token {}",
+            process::id(),
+            since,
+            NEXT_TOKEN.fetch_add(1, Ordering::Relaxed)
+        );
+        writeln!(file, "{}", contents)?;
+        Ok(Self { path, contents })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ExperimentLock {
+    fn drop(&mut self) {
+        let owned =
+            fs::read_to_string(&self.path).is_ok_and(|contents| contents.trim() == self.contents);
+        if owned {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// The pid in the contents of a lock file.
+fn holder_pid(contents: &str) -> Option<u32> {
+    contents
+        .strip_prefix("pid ")?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Whether process `pid` is running; `None` where that cannot be told.
+#[cfg(target_os = "linux")]
+fn is_running(pid: u32) -> Option<bool> {
+    Some(Path::new("/proc").join(pid.to_string()).exists())
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn is_running(pid: u32) -> Option<bool> {
+    let status = process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(process::Stdio::null())
+        .status()
+        .ok()?;
+    Some(status.success())
+}
+
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_experiment_lock() {
+        let path = std::env::temp_dir().join(format!("cu-bench-test-{}.lock", process::id()));
+
+        let lock = ExperimentLock::acquire_at(&path, false).unwrap();
+        assert!(ExperimentLock::acquire_at(&path, false).is_err());
+
+        let forced = ExperimentLock::acquire_at(&path, true).unwrap();
+        drop(lock);
+        assert!(path.exists());
+        drop(forced);
+        assert!(!path.exists());
+
+        // pid_max is at most 2^22, so this pid never runs
+        fs::write(&path, "pid 4294967295 since 0 token 0\n").unwrap();
+        let stale = ExperimentLock::acquire_at(&path, false).unwrap();
+        drop(stale);
+        assert!(!path.exists());
+    }
+}
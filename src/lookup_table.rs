@@ -0,0 +1,194 @@
+//! Address lookup tables for v0 transactions. With `lookup_table` set, the
+//! harness creates a table owned by the payer, adds the accounts each
+//! transaction references before sending it, and sends v0 messages that load
+//! those accounts through the table. A loaded account takes one byte of the
+//! message instead of 32, so a run against a legacy run shows what v0 changes
+//! in transaction size and CU.
+//!
+//! Signers and invoked programs have to stay in the message itself, so only
+//! the other accounts go into the table. Added addresses can be loaded from
+//! the next slot, which the harness waits for. The table is left open after
+//! the run and keeps its rent; close it with `solana address-lookup-table`.
+
+use crate::{
+    blockhash::BlockhashCache,
+    sdk::{CommitmentConfig, Keypair, RpcClient, Signer, Transaction},
+    window::wait_for_next_slot,
+};
+use solana_program::{
+    address_lookup_table::{
+        instruction::{create_lookup_table, extend_lookup_table},
+        state::LOOKUP_TABLE_MAX_ADDRESSES,
+        AddressLookupTableAccount,
+    },
+    instruction::Instruction,
+    pubkey::Pubkey,
+};
+use std::{error::Error, time::Duration};
+use tracing::info;
+
+/// Addresses per extension, which keeps each extension transaction well
+/// under the packet size.
+const EXTEND_CHUNK: usize = 20;
+
+/// A lookup table the harness created, with the addresses it added so far.
+#[derive(Debug, Clone)]
+pub struct LookupTable {
+    address: Pubkey,
+    addresses: Vec<Pubkey>,
+}
+
+impl LookupTable {
+    /// Creates an empty table with `payer` as its authority.
+    pub fn create(
+        rpc_client: &RpcClient,
+        blockhash: &BlockhashCache,
+        payer: &Keypair,
+    ) -> Result<Self, Box<dyn Error>> {
+        // The derivation slot has to be one the node still has a hash for
+        let recent_slot = rpc_client.get_slot_with_commitment(CommitmentConfig::finalized())?;
+        let (instruction, address) =
+            create_lookup_table(payer.pubkey(), payer.pubkey(), recent_slot);
+        send(rpc_client, blockhash, payer, instruction)?;
+        info!("Created lookup table {}", address);
+        Ok(Self {
+            address,
+            addresses: Vec::new(),
+        })
+    }
+
+    pub fn address(&self) -> Pubkey {
+        self.address
+    }
+
+    /// Adds the [`lookup_candidates`] of `instructions` the table does not
+    /// hold yet and waits until they can be loaded. Returns how many were
+    /// added.
+    pub fn extend_for(
+        &mut self,
+        rpc_client: &RpcClient,
+        blockhash: &BlockhashCache,
+        payer: &Keypair,
+        instructions: &[Instruction],
+    ) -> Result<usize, Box<dyn Error>> {
+        let missing: Vec<Pubkey> = lookup_candidates(instructions, &payer.pubkey())
+            .into_iter()
+            .filter(|address| !self.addresses.contains(address))
+            .collect();
+        if missing.is_empty() {
+            return Ok(0);
+        }
+        if self.addresses.len() + missing.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+            return Err(format!(
+                "lookup table {} cannot hold {} more addresses",
+                self.address,
+                missing.len()
+            )
+            .into());
+        }
+        for chunk in missing.chunks(EXTEND_CHUNK) {
+            let instruction = extend_lookup_table(
+                self.address,
+                payer.pubkey(),
+                Some(payer.pubkey()),
+                chunk.to_vec(),
+            );
+            send(rpc_client, blockhash, payer, instruction)?;
+        }
+        info!(
+            "Added {} addresses to lookup table {}",
+            missing.len(),
+            self.address
+        );
+        self.addresses.extend_from_slice(&missing);
+        wait_for_next_slot(rpc_client, Duration::from_secs(30))?;
+        Ok(missing.len())
+    }
+
+    /// The table as v0 message compilation takes it.
+    pub fn account(&self) -> AddressLookupTableAccount {
+        AddressLookupTableAccount {
+            key: self.address,
+            addresses: self.addresses.clone(),
+        }
+    }
+}
+
+/// Accounts of `instructions` a lookup table can serve: all but `payer`,
+/// signers and invoked programs, each once, in order of first use.
+pub fn lookup_candidates(instructions: &[Instruction], payer: &Pubkey) -> Vec<Pubkey> {
+    let mut candidates: Vec<Pubkey> = Vec::new();
+    for meta in instructions
+        .iter()
+        .flat_map(|instruction| &instruction.accounts)
+    {
+        let invoked = instructions
+            .iter()
+            .any(|instruction| instruction.program_id == meta.pubkey);
+        let signs = instructions
+            .iter()
+            .flat_map(|instruction| &instruction.accounts)
+            .any(|other| other.pubkey == meta.pubkey && other.is_signer);
+        if meta.pubkey != *payer && !invoked && !signs && !candidates.contains(&meta.pubkey) {
+            candidates.push(meta.pubkey);
+        }
+    }
+    candidates
+}
+
+fn send(
+    rpc_client: &RpcClient,
+    blockhash: &BlockhashCache,
+    payer: &Keypair,
+    instruction: Instruction,
+) -> Result<(), Box<dyn Error>> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer],
+        blockhash.get().0,
+    );
+    rpc_client.send_and_confirm_transaction(&transaction)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program::instruction::AccountMeta;
+
+    #[test]
+    fn test_lookup_candidates_skip_signers_and_programs() {
+        let (payer, signer, program, callee) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instructions = [
+            Instruction::new_with_bytes(
+                program,
+                &[],
+                vec![
+                    AccountMeta::new(payer, true),
+                    AccountMeta::new(first, false),
+                    AccountMeta::new_readonly(callee, false),
+                    AccountMeta::new_readonly(signer, false),
+                ],
+            ),
+            Instruction::new_with_bytes(
+                callee,
+                &[],
+                vec![
+                    AccountMeta::new_readonly(second, false),
+                    AccountMeta::new(first, false),
+                    AccountMeta::new_readonly(signer, true),
+                    AccountMeta::new_readonly(program, false),
+                ],
+            ),
+        ];
+        assert_eq!(lookup_candidates(&instructions, &payer), [first, second]);
+        assert!(lookup_candidates(&[], &payer).is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+//! Math-heavy handler costs: [`BenchInstruction::Math`] runs `iterations`
+//! steps of one arithmetic kernel and logs
+//! `Math: {op} x{iterations} checksum {checksum}`.
+//!
+//! The u128 and fixed-point kernels compile to the compiler's 128-bit
+//! multiply and divide routines, as DeFi price and interest code does. The
+//! 256-bit kernels use the `uint` crate and need the `bigint` feature; builds
+//! without it reject them. Comparing the program size with and without the
+//! feature shows what pulling in bigint code costs.
+//!
+//! [`BenchInstruction::Math`]: crate::instruction::BenchInstruction::Math
+
+use solana_program::program_error::ProgramError;
+use std::{fmt, hint::black_box};
+
+/// 1.0 in the 18-decimal fixed-point ("WAD") representation.
+pub const WAD: u128 = 1_000_000_000_000_000_000;
+/// 0.9999 as a WAD, the factor of the fixed-point kernels.
+const RATE: u128 = WAD - WAD / 10_000;
+
+#[cfg(feature = "bigint")]
+uint::construct_uint! {
+    /// 256-bit unsigned integer of the `bigint` kernels.
+    pub struct U256(4);
+}
+
+/// Kernel run by [`BenchInstruction::Math`](crate::instruction::BenchInstruction::Math).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathOp {
+    /// Wrapping u128 multiply-accumulate.
+    U128Mul,
+    /// u128 division by a divisor that changes every step.
+    U128Div,
+    /// WAD multiplication, `a * b / WAD`.
+    FixedMul,
+    /// WAD division, `a * WAD / b`.
+    FixedDiv,
+    /// Like [`MathOp::U128Mul`] on 256-bit integers; needs `bigint`.
+    U256Mul,
+    /// Like [`MathOp::U128Div`] on 256-bit integers; needs `bigint`.
+    U256Div,
+}
+
+impl MathOp {
+    pub const ALL: [MathOp; 6] = [
+        Self::U128Mul,
+        Self::U128Div,
+        Self::FixedMul,
+        Self::FixedDiv,
+        Self::U256Mul,
+        Self::U256Div,
+    ];
+}
+
+impl fmt::Display for MathOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::U128Mul => "u128-mul",
+            Self::U128Div => "u128-div",
+            Self::FixedMul => "fixed-mul",
+            Self::FixedDiv => "fixed-div",
+            Self::U256Mul => "u256-mul",
+            Self::U256Div => "u256-div",
+        })
+    }
+}
+
+/// Body of [`BenchInstruction::Math`](crate::instruction::BenchInstruction::Math):
+/// `iterations` steps of `op`, folded into a checksum. The 256-bit kernels
+/// fail with `InvalidInstructionData` without the `bigint` feature.
+pub fn math(op: MathOp, iterations: u32) -> Result<u64, ProgramError> {
+    let mut acc = 0u128;
+    for i in 0..iterations {
+        let i = u128::from(black_box(i));
+        let a = WAD + i * 1_000_000_007;
+        acc = match op {
+            MathOp::U128Mul => acc
+                .wrapping_mul(black_box(0x9e37_79b9_7f4a_7c15_f39c_c060_5ced_c835))
+                .wrapping_add(i),
+            MathOp::U128Div => acc.wrapping_add((u128::MAX - i) / ((i << 40) | 3)),
+            MathOp::FixedMul => acc.wrapping_add(a * black_box(RATE) / WAD),
+            MathOp::FixedDiv => acc.wrapping_add(a * WAD / (RATE + i)),
+            MathOp::U256Mul | MathOp::U256Div => return u256_math(op, iterations),
+        };
+    }
+    Ok(fold(acc))
+}
+
+#[cfg(feature = "bigint")]
+fn u256_math(op: MathOp, iterations: u32) -> Result<u64, ProgramError> {
+    let factor = U256::from(0x9e37_79b9_7f4a_7c15_f39c_c060_5ced_c835u128) << 64;
+    let mut acc = U256::zero();
+    for i in 0..iterations {
+        let i = U256::from(black_box(i));
+        acc = match op {
+            MathOp::U256Mul => {
+                acc.overflowing_mul(black_box(factor))
+                    .0
+                    .overflowing_add(i)
+                    .0
+            }
+            _ => {
+                let quotient = (U256::MAX - i) / ((i << 100) | U256::from(3));
+                acc.overflowing_add(quotient).0
+            }
+        };
+    }
+    Ok(fold(acc.low_u128()) ^ fold((acc >> 128).low_u128()))
+}
+
+#[cfg(not(feature = "bigint"))]
+fn u256_math(_op: MathOp, _iterations: u32) -> Result<u64, ProgramError> {
+    Err(ProgramError::InvalidInstructionData)
+}
+
+fn fold(value: u128) -> u64 {
+    value as u64 ^ (value >> 64) as u64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_math_kernels() {
+        for op in [
+            MathOp::U128Mul,
+            MathOp::U128Div,
+            MathOp::FixedMul,
+            MathOp::FixedDiv,
+        ] {
+            assert_eq!(math(op, 0), Ok(0));
+            assert_eq!(math(op, 100), math(op, 100));
+            assert_ne!(math(op, 100), math(op, 101));
+        }
+        // 1.0 * 0.9999, then 1.000000001000000007 * 0.9999, in WAD
+        assert_eq!(
+            math(MathOp::FixedMul, 2),
+            Ok(fold(RATE + (WAD + 1_000_000_007) * RATE / WAD))
+        );
+        // Far from overflowing, even well past the sweep
+        assert!(math(MathOp::FixedDiv, 1 << 16).is_ok());
+        assert_eq!(math(MathOp::U256Mul, 1).is_ok(), cfg!(feature = "bigint"));
+    }
+}
@@ -0,0 +1,290 @@
+//! Matrix runs: every workload against every deployment, for example builds
+//! padded to different sizes. A cell that fails, because a program is missing
+//! or the RPC keeps failing, is recorded and the matrix moves on, so one bad
+//! point does not throw away hours of measurements.
+//!
+//! Across deployments of different sizes, [`MatrixReport::size_fits`] fits
+//! each workload's CU against program size: the CU-per-KB slope says whether
+//! a bigger program costs more to run at all, and the fit against log size
+//! whether any cost grows more slowly than the binary.
+
+use crate::{
+    client::CuReport,
+    sample::TagFilter,
+    stats::{cu_per_kb_fit, log_fit, LinearFit},
+};
+use solana_program::pubkey::Pubkey;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct MatrixCell {
+    pub program_id: Pubkey,
+    pub workload: String,
+    /// The run, or why it could not be completed.
+    pub outcome: Result<CuReport, String>,
+}
+
+/// Steady-state CU of one workload against program size, over every
+/// deployment whose size was read.
+#[derive(Debug, Clone)]
+pub struct SizeFit {
+    pub workload: String,
+    /// CU against size in KB, so the slope is CU per KB.
+    pub per_kb: LinearFit,
+    /// CU against the natural log of size in KB.
+    pub log: Option<LinearFit>,
+}
+
+impl fmt::Display for SizeFit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fit = &self.per_kb;
+        write!(
+            f,
+            "{}: {:+.3} CU per KB (95% CI {:.3}..{:.3}, r² {:.4}, n {})",
+            self.workload, fit.slope, fit.slope_ci.0, fit.slope_ci.1, fit.r_squared, fit.n
+        )?;
+        if !fit.slope_is_significant() {
+            write!(f, ", not significant")?;
+        }
+        if let Some(log) = &self.log {
+            write!(
+                f,
+                "; {:+.1} CU per doubling of size (r² {:.4})",
+                log.slope * std::f64::consts::LN_2,
+                log.r_squared
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MatrixReport {
+    pub programs: Vec<Pubkey>,
+    pub workloads: Vec<String>,
+    pub cells: Vec<MatrixCell>,
+}
+
+impl MatrixReport {
+    pub fn cell(&self, program_id: &Pubkey, workload: &str) -> Option<&MatrixCell> {
+        self.cells
+            .iter()
+            .find(|cell| cell.program_id == *program_id && cell.workload == workload)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &MatrixCell> {
+        self.cells.iter().filter(|cell| cell.outcome.is_err())
+    }
+
+    /// [`SizeFit`] of each workload that ran on deployments of at least two
+    /// sizes.
+    pub fn size_fits(&self) -> Vec<SizeFit> {
+        let steady_state = TagFilter::steady_state();
+        self.workloads
+            .iter()
+            .filter_map(|workload| {
+                let (mut sizes, mut compute_units) = (Vec::new(), Vec::new());
+                for program_id in &self.programs {
+                    let Some(size) = self.program_size(program_id) else {
+                        continue;
+                    };
+                    if let Some(Ok(report)) =
+                        self.cell(program_id, workload).map(|cell| &cell.outcome)
+                    {
+                        for cu in steady_state.compute_units(&report.run) {
+                            sizes.push(size);
+                            compute_units.push(cu);
+                        }
+                    }
+                }
+                let kb: Vec<f64> = sizes.iter().map(|&size| size as f64 / 1024.0).collect();
+                let cu: Vec<f64> = compute_units.iter().map(|&cu| cu as f64).collect();
+                Some(SizeFit {
+                    workload: workload.clone(),
+                    per_kb: cu_per_kb_fit(&sizes, &compute_units)?,
+                    log: log_fit(&kb, &cu),
+                })
+            })
+            .collect()
+    }
+
+    /// Program data size of `program_id`, from the first run that read it.
+    fn program_size(&self, program_id: &Pubkey) -> Option<usize> {
+        self.cells
+            .iter()
+            .filter(|cell| cell.program_id == *program_id)
+            .find_map(|cell| cell.outcome.as_ref().ok()?.program_size)
+            .map(|info| info.program_data_size)
+    }
+}
+
+/// Median steady-state CU per workload and deployment; failed cells show
+/// `missing` and are listed with their errors below the table.
+impl fmt::Display for MatrixReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:<20}", "workload")?;
+        for (column, program_id) in self.programs.iter().enumerate() {
+            let size = self
+                .program_size(program_id)
+                .map_or("?".to_string(), |size| size.to_string());
+            write!(f, " {:>16}", format!("#{} ({} B)", column + 1, size))?;
+        }
+        for workload in &self.workloads {
+            write!(f, "\n{:<20}", workload)?;
+            for program_id in &self.programs {
+                let value = match self.cell(program_id, workload).map(|cell| &cell.outcome) {
+                    Some(Ok(report)) => {
+                        let stats = report.compute_unit_stats(&TagFilter::steady_state());
+                        if stats.count == 0 {
+                            "no samples".to_string()
+                        } else {
+                            format!("{:.0}", stats.median)
+                        }
+                    }
+                    Some(Err(_)) | None => "missing".to_string(),
+                };
+                write!(f, " {:>16}", value)?;
+            }
+        }
+        for (column, program_id) in self.programs.iter().enumerate() {
+            write!(f, "\n#{}: {}", column + 1, program_id)?;
+        }
+        for fit in self.size_fits() {
+            write!(f, "\nCU against size, {}", fit)?;
+        }
+        for cell in self.failures() {
+            if let Err(e) = &cell.outcome {
+                write!(
+                    f,
+                    "\nFAILED {} on {}: {}",
+                    cell.workload, cell.program_id, e
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{fixtures::RunFixture, leader::LeaderReport, size::ProgramSizeInfo};
+
+    #[test]
+    fn test_missing_cells_are_marked() {
+        let (small, large) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let fixture = RunFixture {
+            program_id: small,
+            base_compute_units: 1_200,
+            jitter: 0,
+            outlier_rate: 0.0,
+            failure_rate: 0.0,
+            ..RunFixture::default()
+        };
+        let report = CuReport {
+            run: fixture.run(),
+            payer: Pubkey::new_unique(),
+            program_size: Some(ProgramSizeInfo {
+                program_data_size: 18_000,
+                total_size: 18_045,
+                ..ProgramSizeInfo::default()
+            }),
+            clock: None,
+            accounting: None,
+            calibration: None,
+            leaders: LeaderReport::default(),
+            log_mismatches: Vec::new(),
+            burn_fit: None,
+            guard_unique_nonces: None,
+            attribution_gaps: Vec::new(),
+            invocation_tree: None,
+            deployment: None,
+            deployment_changes: Vec::new(),
+            slos: Vec::new(),
+        };
+        let matrix = MatrixReport {
+            programs: vec![small, large],
+            workloads: vec!["counter".to_string()],
+            cells: vec![
+                MatrixCell {
+                    program_id: small,
+                    workload: "counter".to_string(),
+                    outcome: Ok(report),
+                },
+                MatrixCell {
+                    program_id: large,
+                    workload: "counter".to_string(),
+                    outcome: Err("Account not found".to_string()),
+                },
+            ],
+        };
+
+        assert_eq!(matrix.failures().count(), 1);
+        let table = matrix.to_string();
+        assert!(table.contains("#1 (18000 B)"));
+        assert!(table.contains("#2 (? B)"));
+        assert!(table.contains("1200          missing"));
+        assert!(table.contains(&format!("FAILED counter on {}: Account not found", large)));
+        assert!(matrix.size_fits().is_empty());
+    }
+
+    #[test]
+    fn test_size_fits() {
+        let cell = |size: usize, cu: u64| {
+            let program_id = Pubkey::new_unique();
+            let fixture = RunFixture {
+                program_id,
+                base_compute_units: cu,
+                jitter: 0,
+                outlier_rate: 0.0,
+                failure_rate: 0.0,
+                ..RunFixture::default()
+            };
+            let report = CuReport {
+                run: fixture.run(),
+                payer: Pubkey::new_unique(),
+                program_size: Some(ProgramSizeInfo {
+                    program_data_size: size,
+                    total_size: size + 45,
+                    ..ProgramSizeInfo::default()
+                }),
+                clock: None,
+                accounting: None,
+                calibration: None,
+                leaders: LeaderReport::default(),
+                log_mismatches: Vec::new(),
+                burn_fit: None,
+                guard_unique_nonces: None,
+                attribution_gaps: Vec::new(),
+                invocation_tree: None,
+                deployment: None,
+                deployment_changes: Vec::new(),
+                slos: Vec::new(),
+            };
+            MatrixCell {
+                program_id,
+                workload: "counter".to_string(),
+                outcome: Ok(report),
+            }
+        };
+        let cells = vec![
+            cell(16_384, 1_200),
+            cell(32_768, 1_200),
+            cell(65_536, 1_200),
+        ];
+        let matrix = MatrixReport {
+            programs: cells.iter().map(|cell| cell.program_id).collect(),
+            workloads: vec!["counter".to_string()],
+            cells,
+        };
+
+        let fits = matrix.size_fits();
+        assert_eq!(fits.len(), 1);
+        assert_eq!(fits[0].per_kb.slope, 0.0);
+        assert!(!fits[0].per_kb.slope_is_significant());
+        assert_eq!(fits[0].log.as_ref().map(|fit| fit.slope), Some(0.0));
+        assert!(matrix
+            .to_string()
+            .contains("CU against size, counter: +0.000 CU per KB"));
+    }
+}
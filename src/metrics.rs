@@ -0,0 +1,136 @@
+//! Pluggable per-transaction metrics. Extractors see every confirmed transaction
+//! and emit named values into the run output, so custom, log-derived numbers can
+//! be reported without changing the harness.
+
+use crate::tx_view::ConfirmedTxView;
+
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Metric {
+    pub name: String,
+    pub value: f64,
+}
+
+/// Collects the metrics emitted by extractors for a single transaction.
+#[derive(Debug, Default)]
+pub struct MetricSink {
+    metrics: Vec<Metric>,
+}
+
+impl MetricSink {
+    pub fn emit(&mut self, name: impl Into<String>, value: f64) {
+        self.metrics.push(Metric {
+            name: name.into(),
+            value,
+        });
+    }
+
+    pub fn into_metrics(self) -> Vec<Metric> {
+        self.metrics
+    }
+}
+
+pub trait MetricExtractor: Send + Sync {
+    fn extract(&self, tx: &ConfirmedTxView, sink: &mut MetricSink);
+}
+
+/// Emits `compute_units` when the node reported them.
+pub struct ComputeUnits;
+
+impl MetricExtractor for ComputeUnits {
+    fn extract(&self, tx: &ConfirmedTxView, sink: &mut MetricSink) {
+        if let Some(cu) = tx.cu_consumed() {
+            sink.emit("compute_units", cu as f64);
+        }
+    }
+}
+
+/// Emits `fee` in lamports.
+pub struct Fee;
+
+impl MetricExtractor for Fee {
+    fn extract(&self, tx: &ConfirmedTxView, sink: &mut MetricSink) {
+        if let Some(fee) = tx.fee() {
+            sink.emit("fee", fee as f64);
+        }
+    }
+}
+
+/// Emits the number following `marker` in the first log line containing it,
+/// e.g. `LogValue::new("count", "Count: ")` for `Program log: Count: 42`.
+pub struct LogValue {
+    name: String,
+    marker: String,
+}
+
+impl LogValue {
+    pub fn new(name: impl Into<String>, marker: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            marker: marker.into(),
+        }
+    }
+}
+
+impl MetricExtractor for LogValue {
+    fn extract(&self, tx: &ConfirmedTxView, sink: &mut MetricSink) {
+        if let Some(value) = tx
+            .logs()
+            .find_map(|line| parse_log_value(line, &self.marker))
+        {
+            sink.emit(self.name.clone(), value);
+        }
+    }
+}
+
+pub(crate) fn parse_log_value(line: &str, marker: &str) -> Option<f64> {
+    let (_, rest) = line.split_once(marker)?;
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+/// Extractors registered for a run, applied in registration order.
+#[derive(Default)]
+pub struct MetricRegistry {
+    extractors: Vec<Box<dyn MetricExtractor>>,
+}
+
+impl MetricRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry preloaded with [`ComputeUnits`] and [`Fee`].
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register(ComputeUnits).register(Fee);
+        registry
+    }
+
+    pub fn register(&mut self, extractor: impl MetricExtractor + 'static) -> &mut Self {
+        self.extractors.push(Box::new(extractor));
+        self
+    }
+
+    pub fn extract(&self, tx: &ConfirmedTxView) -> Vec<Metric> {
+        let mut sink = MetricSink::default();
+        for extractor in &self.extractors {
+            extractor.extract(tx, &mut sink);
+        }
+        sink.into_metrics()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_log_value;
+
+    #[test]
+    fn test_parse_log_value() {
+        assert_eq!(
+            parse_log_value("Program log: Count: 42", "Count: "),
+            Some(42.0)
+        );
+        assert_eq!(parse_log_value("Program log: Count: x", "Count: "), None);
+        assert_eq!(parse_log_value("Program log: other", "Count: "), None);
+    }
+}
@@ -0,0 +1,26 @@
+//! Static padding that inflates the deployed `.so` to a known size, so CU can be
+//! compared between otherwise identical binaries of different sizes. Enable one
+//! of the `pad-100k`, `pad-500k` or `pad-1m` features; if several are enabled
+//! the largest wins.
+
+/// Bytes of padding embedded in this build.
+pub const PADDING_BYTES: usize = if cfg!(feature = "pad-1m") {
+    1024 * 1024
+} else if cfg!(feature = "pad-500k") {
+    500 * 1024
+} else if cfg!(feature = "pad-100k") {
+    100 * 1024
+} else {
+    0
+};
+
+/// Non-zero so it lands in `.rodata` rather than `.bss`.
+static PADDING: [u8; PADDING_BYTES] = [0xa5; PADDING_BYTES];
+
+/// Keeps the padding referenced so the linker cannot discard it. Called from
+/// the entrypoint in every build, so padded and unpadded binaries execute the
+/// same instructions.
+#[inline(always)]
+pub fn retain() {
+    std::hint::black_box(PADDING.as_ptr());
+}
@@ -0,0 +1,124 @@
+//! Payer keys held in the OS credential store (macOS Keychain, Windows
+//! Credential Manager, Secret Service on Linux) rather than as plaintext JSON on
+//! disk, for long-running benchmarks on shared machines. `cu-bench keyring`
+//! stores one from any signer source.
+//!
+//! There is no passphrase-encrypted keypair file: for attended runs
+//! `prompt://` already keeps the key off disk, derived from a seed phrase and
+//! passphrase typed at start, and the credential store covers unattended ones.
+
+use crate::sdk::{read_keypair, Keypair};
+use std::error::Error;
+
+/// Loads the keypair stored under `service` / `account`. The secret holds the
+/// same JSON byte array that `solana-keygen` writes to a keypair file.
+pub fn load_from_keyring(service: &str, account: &str) -> Result<Keypair, Box<dyn Error>> {
+    let secret = keyring::Entry::new(service, account)?
+        .get_password()
+        .map_err(|e| format!("no payer key in keyring for {}/{}: {}", service, account, e))?;
+    read_keypair(&mut secret.as_bytes())
+}
+
+/// Stores `keypair` under `service` / `account`, replacing any existing secret.
+pub fn store_in_keyring(
+    service: &str,
+    account: &str,
+    keypair: &Keypair,
+) -> Result<(), Box<dyn Error>> {
+    keyring::Entry::new(service, account)?.set_password(&encode(keypair))?;
+    Ok(())
+}
+
+fn encode(keypair: &Keypair) -> String {
+    format!("{:?}", keypair.to_bytes())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{sdk::Signer, signer_source::SignerSource};
+    use keyring::credential::{Credential, CredentialApi, CredentialBuilderApi};
+    use std::{
+        any::Any,
+        collections::HashMap,
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    type Secrets = Arc<Mutex<HashMap<(String, String), Vec<u8>>>>;
+
+    /// In-process credential store, so tests do not touch the real one.
+    struct MemoryStore(Secrets);
+
+    struct MemoryCredential {
+        secrets: Secrets,
+        key: (String, String),
+    }
+
+    impl CredentialBuilderApi for MemoryStore {
+        fn build(
+            &self,
+            _target: Option<&str>,
+            service: &str,
+            user: &str,
+        ) -> keyring::Result<Box<Credential>> {
+            Ok(Box::new(MemoryCredential {
+                secrets: self.0.clone(),
+                key: (service.to_string(), user.to_string()),
+            }))
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    impl CredentialApi for MemoryCredential {
+        fn set_secret(&self, secret: &[u8]) -> keyring::Result<()> {
+            let mut secrets = self.secrets.lock().unwrap();
+            secrets.insert(self.key.clone(), secret.to_vec());
+            Ok(())
+        }
+
+        fn get_secret(&self) -> keyring::Result<Vec<u8>> {
+            let secrets = self.secrets.lock().unwrap();
+            secrets
+                .get(&self.key)
+                .cloned()
+                .ok_or(keyring::Error::NoEntry)
+        }
+
+        fn delete_credential(&self) -> keyring::Result<()> {
+            let mut secrets = self.secrets.lock().unwrap();
+            secrets
+                .remove(&self.key)
+                .map(drop)
+                .ok_or(keyring::Error::NoEntry)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn test_encoding_round_trips_through_read_keypair() {
+        let keypair = Keypair::new();
+        let decoded = read_keypair(&mut encode(&keypair).as_bytes()).unwrap();
+        assert_eq!(decoded.to_bytes(), keypair.to_bytes());
+    }
+
+    #[test]
+    fn test_keyring_source_resolves_stored_payer() {
+        keyring::set_default_credential_builder(Box::new(MemoryStore(Default::default())));
+        let payer = Keypair::new();
+        store_in_keyring("cu-bench", "payer", &payer).unwrap();
+
+        let source = SignerSource::parse("keyring://cu-bench/payer").unwrap();
+        assert_eq!(source.load(io::empty()).unwrap().pubkey(), payer.pubkey());
+        assert!(SignerSource::parse("keyring://cu-bench/other")
+            .unwrap()
+            .load(io::empty())
+            .is_err());
+    }
+}
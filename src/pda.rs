@@ -0,0 +1,304 @@
+//! PDA derivation cost: [`BenchInstruction::DerivePda`] derives the same
+//! address `reps` times from `seeds` seeds of `seed_len` bytes each, with
+//! `find_program_address` or with `create_program_address` and a known bump.
+//!
+//! `find_program_address` tries bumps from 255 down until the address falls
+//! off the curve, and each try costs as much as one `create_program_address`,
+//! so its cost depends on the bump it finds; the instruction logs
+//! `Pda: {mode} {seeds}x{seed_len} bytes x{reps} bump {bump}` to tell.
+//!
+//! [`BenchInstruction::VerifyPda`] is the pattern programs actually use:
+//! check that the only account passed is the PDA of the seeds, re-deriving it
+//! with `find_program_address` or, given a cached bump, with one
+//! `create_program_address`. The bump travels in the instruction data, where
+//! a bump stored in account state would be read for a few CU more.
+//! [`verify_pda_pair`] puts both checks in one transaction, and
+//! [`BumpCacheSavings`] reports the difference.
+
+use crate::instruction::BenchInstruction;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::{Pubkey, MAX_SEEDS, MAX_SEED_LEN},
+};
+use std::fmt;
+
+/// Most seeds an instruction can ask for, leaving room for the bump seed.
+pub const MAX_PDA_SEEDS: u8 = MAX_SEEDS as u8 - 1;
+
+/// Derivation syscall exercised by [`BenchInstruction::DerivePda`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdaMode {
+    /// `find_program_address`, which searches for the bump.
+    Find,
+    /// `create_program_address` with the bump passed in the instruction.
+    Create,
+}
+
+impl PdaMode {
+    pub const ALL: [PdaMode; 2] = [Self::Find, Self::Create];
+}
+
+impl fmt::Display for PdaMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Find => "find",
+            Self::Create => "create",
+        })
+    }
+}
+
+/// Seeds of a derivation: seed `i` is `seed_len` bytes of value `i + 1`.
+pub fn pda_seeds(seeds: u8, seed_len: u8) -> Result<Vec<Vec<u8>>, ProgramError> {
+    if seeds > MAX_PDA_SEEDS || usize::from(seed_len) > MAX_SEED_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok((1..=seeds)
+        .map(|value| vec![value; seed_len.into()])
+        .collect())
+}
+
+/// Body of [`BenchInstruction::DerivePda`]: derives the address `reps` times
+/// and returns it with its bump, which is `bump` itself in
+/// [`PdaMode::Create`].
+pub fn derive_pdas(
+    program_id: &Pubkey,
+    mode: PdaMode,
+    seeds: u8,
+    seed_len: u8,
+    reps: u16,
+    bump: u8,
+) -> Result<(Pubkey, u8), ProgramError> {
+    let seeds = pda_seeds(seeds, seed_len)?;
+    let mut seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+    let mut derived = (Pubkey::default(), bump);
+    match mode {
+        PdaMode::Find => {
+            for _ in 0..reps {
+                derived = Pubkey::find_program_address(std::hint::black_box(&seeds), program_id);
+            }
+        }
+        PdaMode::Create => {
+            let bump = [bump];
+            seeds.push(&bump);
+            for _ in 0..reps {
+                derived.0 =
+                    Pubkey::create_program_address(std::hint::black_box(&seeds), program_id)?;
+            }
+        }
+    }
+    Ok(derived)
+}
+
+/// Builds a [`BenchInstruction::DerivePda`] for `program_id`. The bump for
+/// [`PdaMode::Create`] is found here, off chain. Returns the instruction and
+/// the log line it produces.
+pub fn derive_pda(
+    program_id: &Pubkey,
+    mode: PdaMode,
+    seeds: u8,
+    seed_len: u8,
+    reps: u16,
+) -> Result<(Instruction, String), ProgramError> {
+    let seed_bytes = pda_seeds(seeds, seed_len)?;
+    let seed_refs: Vec<&[u8]> = seed_bytes.iter().map(Vec::as_slice).collect();
+    let (_, bump) = Pubkey::find_program_address(&seed_refs, program_id);
+    let instruction = BenchInstruction::DerivePda {
+        mode,
+        seeds,
+        seed_len,
+        reps,
+        bump,
+    }
+    .to_instruction(*program_id, vec![]);
+    let log = format!(
+        "Pda: {} {}x{} bytes x{} bump {}",
+        mode, seeds, seed_len, reps, bump
+    );
+    Ok((instruction, log))
+}
+
+pub fn process_verify_pda(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    seeds: u8,
+    seed_len: u8,
+    bump: Option<u8>,
+) -> ProgramResult {
+    let pda = next_account_info(&mut accounts.iter())?;
+    let seeds = pda_seeds(seeds, seed_len)?;
+    let mut seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+    let derived = match bump {
+        None => Pubkey::find_program_address(&seeds, program_id).0,
+        Some(bump) => {
+            let bump = [bump];
+            seeds.push(&bump);
+            Pubkey::create_program_address(&seeds, program_id)?
+        }
+    };
+    if derived != *pda.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
+
+/// Verifies the PDA of `seeds` seeds of `seed_len` bytes twice: by
+/// re-deriving it, then with the bump found here, off chain.
+pub fn verify_pda_pair(
+    program_id: &Pubkey,
+    seeds: u8,
+    seed_len: u8,
+) -> Result<Vec<Instruction>, ProgramError> {
+    let seed_bytes = pda_seeds(seeds, seed_len)?;
+    let seed_refs: Vec<&[u8]> = seed_bytes.iter().map(Vec::as_slice).collect();
+    let (pda, bump) = Pubkey::find_program_address(&seed_refs, program_id);
+    Ok([None, Some(bump)]
+        .into_iter()
+        .map(|bump| {
+            BenchInstruction::VerifyPda {
+                seeds,
+                seed_len,
+                bump,
+            }
+            .to_instruction(*program_id, vec![AccountMeta::new_readonly(pda, false)])
+        })
+        .collect())
+}
+
+/// Emits `bump_cache_savings_{seeds}x{seed_len}`: CU of the re-deriving
+/// [`BenchInstruction::VerifyPda`] minus CU of the one with a cached bump,
+/// for each seed configuration a transaction verified both ways.
+#[cfg(not(target_os = "solana"))]
+pub struct BumpCacheSavings;
+
+#[cfg(not(target_os = "solana"))]
+impl crate::metrics::MetricExtractor for BumpCacheSavings {
+    fn extract(&self, tx: &crate::tx_view::ConfirmedTxView, sink: &mut crate::metrics::MetricSink) {
+        // (configuration, re-derived CU, cached-bump CU)
+        let mut configs: Vec<(&str, Option<u64>, Option<u64>)> = Vec::new();
+        let mut verified = None;
+        for line in tx.logs() {
+            if let Some((_, rest)) = line.split_once("Pda verified: ") {
+                verified = rest.split_once(' ');
+                continue;
+            }
+            let Some((mode, config)) = verified else {
+                continue;
+            };
+            let Some(units) = consumed_units(line) else {
+                continue;
+            };
+            verified = None;
+            let config = config.trim_end_matches(" bytes");
+            let index = match configs.iter().position(|(seen, ..)| *seen == config) {
+                Some(index) => index,
+                None => {
+                    configs.push((config, None, None));
+                    configs.len() - 1
+                }
+            };
+            match mode {
+                "find" => configs[index].1 = Some(units),
+                _ => configs[index].2 = Some(units),
+            }
+        }
+        for (config, find, cached) in configs {
+            if let (Some(find), Some(cached)) = (find, cached) {
+                sink.emit(
+                    format!("bump_cache_savings_{}", config),
+                    find as f64 - cached as f64,
+                );
+            }
+        }
+    }
+}
+
+/// `X` of a runtime `Program {id} consumed X of Y compute units` line.
+#[cfg(not(target_os = "solana"))]
+fn consumed_units(line: &str) -> Option<u64> {
+    if line.starts_with("Program log:") {
+        return None;
+    }
+    let (_, rest) = line.strip_prefix("Program ")?.split_once(" consumed ")?;
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_and_create_agree() {
+        let program_id = Pubkey::new_unique();
+        let (instruction, log) = derive_pda(&program_id, PdaMode::Create, 4, 8, 3).unwrap();
+        let Ok(BenchInstruction::DerivePda { bump, .. }) =
+            BenchInstruction::unpack(&instruction.data)
+        else {
+            panic!("not a DerivePda instruction");
+        };
+        assert!(log.ends_with(&format!("4x8 bytes x3 bump {}", bump)));
+
+        let found = derive_pdas(&program_id, PdaMode::Find, 4, 8, 3, 0).unwrap();
+        assert_eq!(found.1, bump);
+        assert_eq!(
+            derive_pdas(&program_id, PdaMode::Create, 4, 8, 3, bump),
+            Ok(found)
+        );
+        assert_eq!(
+            pda_seeds(MAX_PDA_SEEDS + 1, 1),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert!(pda_seeds(MAX_PDA_SEEDS, MAX_SEED_LEN as u8).is_ok());
+    }
+
+    #[test]
+    fn test_bump_cache_savings_from_logs() {
+        use crate::{
+            fixtures::RunFixture,
+            metrics::{MetricExtractor, MetricSink},
+        };
+        use solana_transaction_status::option_serializer::OptionSerializer;
+
+        let program = Pubkey::new_unique();
+        let logs = [
+            ("find", 1_000_u64),
+            ("cached", 1_800),
+            ("find", 4_000),
+            ("cached", 2_500),
+        ]
+        .iter()
+        .enumerate()
+        .flat_map(|(i, (mode, consumed))| {
+            let config = if i < 2 { "4x8" } else { "1x32" };
+            [
+                format!("Program {} invoke [1]", program),
+                format!("Program log: Pda verified: {} {} bytes", mode, config),
+                format!(
+                    "Program {} consumed {} of 1400000 compute units",
+                    program, consumed
+                ),
+                format!("Program {} success", program),
+            ]
+        })
+        .collect();
+        let mut inner = RunFixture::default().transactions().remove(0).into_inner();
+        inner.transaction.meta.as_mut().unwrap().log_messages = OptionSerializer::Some(logs);
+
+        let mut sink = MetricSink::default();
+        BumpCacheSavings.extract(&crate::tx_view::ConfirmedTxView::new(inner), &mut sink);
+        let metrics: Vec<(String, f64)> = sink
+            .into_metrics()
+            .into_iter()
+            .map(|metric| (metric.name, metric.value))
+            .collect();
+        assert_eq!(
+            metrics,
+            [
+                ("bump_cache_savings_4x8".to_string(), -800.0),
+                ("bump_cache_savings_1x32".to_string(), 1_500.0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,209 @@
+//! Analytical CU model of the syscall-bound instructions: the syscall costs
+//! of the runtime's default compute budget, priced from an instruction's
+//! decoded parameters. Measured CU should exceed the prediction by a constant,
+//! the program's own fixed cost, so samples straying from that constant show
+//! where the runtime deviates from the documented model.
+
+use crate::{
+    accounting::mem_op_compute_units, curve::CurveOp, instruction::BenchInstruction, run::Run,
+};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use std::fmt;
+
+/// Charge of a `sol_sha256` or `sol_keccak256` call before its input.
+pub const SHA256_BASE_COST: u64 = 85;
+/// Charge per two bytes of hashed input, at least the memory op base cost
+/// per slice.
+pub const SHA256_BYTE_COST: u64 = 1;
+pub const SECP256K1_RECOVER_COST: u64 = 25_000;
+pub const CURVE25519_EDWARDS_ADD_COST: u64 = 473;
+pub const CURVE25519_EDWARDS_MSM_BASE_COST: u64 = 2_273;
+pub const CURVE25519_EDWARDS_MSM_INCREMENTAL_COST: u64 = 758;
+pub const CURVE25519_RISTRETTO_ADD_COST: u64 = 521;
+pub const CURVE25519_RISTRETTO_MSM_BASE_COST: u64 = 2_303;
+pub const CURVE25519_RISTRETTO_MSM_INCREMENTAL_COST: u64 = 788;
+
+/// How far a sample may stray from prediction plus fixed cost before it is
+/// reported as a deviation.
+pub const DEVIATION_TOLERANCE: u64 = 100;
+
+/// CU the documented syscall costs predict for `instruction`, or `None` for
+/// instructions whose cost is mostly program code rather than syscalls.
+pub fn syscall_compute_units(instruction: &BenchInstruction) -> Option<u64> {
+    match instruction {
+        // The program hashes the payload as a single slice
+        BenchInstruction::Sha256 { data } | BenchInstruction::Keccak256 { data } => {
+            let bytes = SHA256_BYTE_COST * (data.len() as u64 / 2);
+            Some(SHA256_BASE_COST + mem_op_compute_units(0).max(bytes))
+        }
+        BenchInstruction::Secp256k1Recover { .. } => Some(SECP256K1_RECOVER_COST),
+        BenchInstruction::MemOps { len, reps, .. } => {
+            Some(u64::from(*reps) * mem_op_compute_units(u64::from(*len)))
+        }
+        BenchInstruction::Curve { op, count } => {
+            let count = u64::from(*count);
+            Some(match op {
+                CurveOp::EdwardsAdd => count * CURVE25519_EDWARDS_ADD_COST,
+                CurveOp::RistrettoAdd => count * CURVE25519_RISTRETTO_ADD_COST,
+                CurveOp::EdwardsMsm => {
+                    CURVE25519_EDWARDS_MSM_BASE_COST
+                        + count.saturating_sub(1) * CURVE25519_EDWARDS_MSM_INCREMENTAL_COST
+                }
+                CurveOp::RistrettoMsm => {
+                    CURVE25519_RISTRETTO_MSM_BASE_COST
+                        + count.saturating_sub(1) * CURVE25519_RISTRETTO_MSM_INCREMENTAL_COST
+                }
+            })
+        }
+        _ => None,
+    }
+}
+
+/// [`syscall_compute_units`] summed over the bench instructions among
+/// `instructions`; `None` unless there is one and the model prices all.
+pub fn predict_instructions(
+    instructions: &[Instruction],
+    bench_programs: &[Pubkey],
+) -> Option<u64> {
+    let mut bench = instructions
+        .iter()
+        .filter(|instruction| bench_programs.contains(&instruction.program_id))
+        .peekable();
+    bench.peek()?;
+    bench
+        .map(|instruction| {
+            BenchInstruction::unpack(&instruction.data)
+                .ok()
+                .as_ref()
+                .and_then(syscall_compute_units)
+        })
+        .sum()
+}
+
+/// A sample whose measured CU strays from the model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deviation {
+    pub index: u64,
+    pub instruction: Option<String>,
+    /// Measured minus predicted CU, less the run's fixed cost.
+    pub residual: i64,
+}
+
+/// Predicted against measured CU over the samples of a run the model prices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ModelCheck {
+    pub samples: usize,
+    /// Median of measured minus predicted CU: what the program spends outside
+    /// the modelled syscalls.
+    pub fixed_cost: i64,
+    pub deviations: Vec<Deviation>,
+}
+
+impl ModelCheck {
+    /// Checks the samples of `run` with both a measurement and a prediction;
+    /// `None` when there are none.
+    pub fn new(run: &Run) -> Option<Self> {
+        let priced: Vec<_> = run
+            .samples()
+            .filter_map(|sample| {
+                let excess = sample.compute_units? as i64 - sample.syscall_compute_units? as i64;
+                Some((sample, excess))
+            })
+            .collect();
+        let mut excesses: Vec<i64> = priced.iter().map(|(_, excess)| *excess).collect();
+        excesses.sort_unstable();
+        let fixed_cost = *excesses.get(excesses.len() / 2)?;
+        let deviations = priced
+            .iter()
+            .map(|(sample, excess)| Deviation {
+                index: sample.index,
+                instruction: sample.instruction.clone(),
+                residual: excess - fixed_cost,
+            })
+            .filter(|deviation| deviation.residual.unsigned_abs() > DEVIATION_TOLERANCE)
+            .collect();
+        Some(Self {
+            samples: priced.len(),
+            fixed_cost,
+            deviations,
+        })
+    }
+}
+
+impl fmt::Display for ModelCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "fixed cost {} CU over {} samples, {} off the model by more than {} CU",
+            self.fixed_cost,
+            self.samples,
+            self.deviations.len(),
+            DEVIATION_TOLERANCE
+        )?;
+        for deviation in &self.deviations {
+            write!(f, "\n  sample {}", deviation.index)?;
+            if let Some(instruction) = &deviation.instruction {
+                write!(f, " ({})", instruction)?;
+            }
+            write!(f, ": {:+} CU", deviation.residual)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{instruction::MemOp, sample::Sample, sdk::Signature};
+
+    #[test]
+    fn test_predict_and_check() {
+        let hash = |len| BenchInstruction::Sha256 { data: vec![0; len] };
+        assert_eq!(syscall_compute_units(&hash(0)), Some(95));
+        assert_eq!(syscall_compute_units(&hash(1_024)), Some(597));
+        let memcpy = BenchInstruction::MemOps {
+            op: MemOp::Memcpy,
+            len: 8_192,
+            reps: 16,
+        };
+        assert_eq!(syscall_compute_units(&memcpy), Some(512));
+        assert_eq!(syscall_compute_units(&BenchInstruction::Noop), None);
+
+        let program_id = Pubkey::new_unique();
+        let instructions = [
+            hash(64).to_instruction(program_id, vec![]),
+            memcpy.to_instruction(program_id, vec![]),
+        ];
+        assert_eq!(
+            predict_instructions(&instructions, &[program_id]),
+            Some(629)
+        );
+        assert_eq!(predict_instructions(&instructions, &[]), None);
+        let noop = BenchInstruction::Noop.to_instruction(program_id, vec![]);
+        assert_eq!(predict_instructions(&[noop], &[program_id]), None);
+
+        let mut run = Run::new(program_id);
+        for (index, (predicted, measured)) in [(95, 1_095), (597, 1_597), (629, 1_900)]
+            .into_iter()
+            .enumerate()
+        {
+            let mut sample = Sample::new(index as u64, Signature::default(), 1, Some(measured));
+            sample.syscall_compute_units = Some(predicted);
+            run.push(sample);
+        }
+        run.push(Sample::new(3, Signature::default(), 1, Some(200)));
+        let check = ModelCheck::new(&run).unwrap();
+        assert_eq!(check.samples, 3);
+        assert_eq!(check.fixed_cost, 1_000);
+        assert_eq!(
+            check.deviations,
+            [Deviation {
+                index: 2,
+                instruction: None,
+                residual: 271,
+            }]
+        );
+        assert!(ModelCheck::new(&Run::new(program_id)).is_none());
+    }
+}
@@ -0,0 +1,21 @@
+//! The stable surface of the crate. Everything re-exported here follows semver;
+//! modules gated behind the `unstable` feature may change in any release.
+//!
+//! ```no_run
+//! use increase_cu_program_size::prelude::*;
+//! ```
+
+pub use crate::{
+    capacity::{BlockLimits, HeadroomReport},
+    client::{BenchConfig, BenchRunner, CuReport},
+    error::BenchError,
+    failure::{FailureClass, FailureCounts},
+    instruction::BenchInstruction,
+    metrics::{Metric, MetricExtractor, MetricRegistry, MetricSink},
+    run::Run,
+    sample::{PhaseTimings, Sample, SampleTag, TagFilter},
+    size::{get_program_size, ProgramSizeInfo},
+    stats::CuStats,
+    tx_view::ConfirmedTxView,
+    workload::{Workload, WorkloadContext, WorkloadRegistry, WorkloadTransaction},
+};
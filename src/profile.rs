@@ -0,0 +1,105 @@
+//! Curated experiment profiles shipped in the binary, so the crate's canonical
+//! experiments are one command to reproduce: `cu-bench profile NAME`, plus
+//! the programs to measure. Each profile is a config file manifest (see
+//! [`crate::config_file`]) and the command it runs; a config file, the
+//! environment and flags still override its settings.
+
+use crate::config_file::ConfigFile;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Profile {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// The `cu-bench` command the profile runs.
+    pub command: &'static str,
+    /// TOML settings.
+    pub manifest: &'static str,
+}
+
+impl Profile {
+    pub fn settings(&self) -> ConfigFile {
+        ConfigFile::parse_toml(self.manifest).expect("shipped profiles are valid")
+    }
+}
+
+pub const PROFILES: &[Profile] = &[
+    Profile {
+        name: "quick-smoke",
+        description: "ten counter transactions, to check a deployment and cluster end to end",
+        command: "run",
+        manifest: r#"
+workload = "counter"
+iterations = 10
+phase_deadlines = "funding=60,send=60,confirm=60,fetch=120"
+"#,
+    },
+    Profile {
+        name: "size-study-small",
+        description: "cheap workloads against each padded build, for a first size-vs-CU curve",
+        command: "matrix",
+        manifest: r#"
+workloads = ["counter", "memcpy", "sha256"]
+iterations = 50
+"#,
+    },
+    Profile {
+        name: "size-study-full",
+        description:
+            "every deterministic workload against each padded build, with simulation cross-checks",
+        command: "matrix",
+        manifest: r#"
+workloads = [
+    "burn-compute", "counter", "guarded-counter", "heap-boxes", "heap-vec-push",
+    "keccak256", "memcpy", "sha256", "touch-accounts",
+]
+iterations = 200
+simulate_before_send = true
+"#,
+    },
+    Profile {
+        name: "syscall-census",
+        description:
+            "every hashing, signature, memory, PDA and curve25519 syscall workload, for per-syscall CU costs",
+        command: "matrix",
+        manifest: r#"
+workloads = [
+    "curve-edwards-add", "curve-edwards-msm", "curve-ristretto-add", "curve-ristretto-msm",
+    "keccak256", "memcmp", "memcpy", "memmove", "memset", "pda-create", "pda-find",
+    "secp256k1-recover", "sha256",
+]
+"#,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Profile> {
+    PROFILES.iter().find(|profile| profile.name == name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::workload::WorkloadRegistry;
+
+    #[test]
+    fn test_profiles_name_real_workloads() {
+        let registry = WorkloadRegistry::with_builtins();
+        for profile in PROFILES {
+            let settings = profile.settings();
+            let workloads = settings
+                .get("workloads")
+                .or(settings.get("workload"))
+                .unwrap();
+            for workload in workloads.split(',') {
+                assert!(
+                    registry.create(workload).is_some(),
+                    "{} names unknown workload {}",
+                    profile.name,
+                    workload
+                );
+            }
+            assert!(["run", "sweep", "matrix"].contains(&profile.command));
+        }
+        assert_eq!(find("quick-smoke").unwrap().command, "run");
+        assert!(find("size-study").is_none());
+    }
+}
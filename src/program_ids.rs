@@ -0,0 +1,98 @@
+//! Predictable program ids for sweep deployments, so each sweep point lands at
+//! an address that scripts and dashboards can reference ahead of time.
+//!
+//! Ids come either from a directory of pre-generated keypair files, or are
+//! derived from a base keypair and an index. The derived keypair of index `i`
+//! is the ed25519 keypair seeded with
+//!
+//! ```text
+//! sha256("cu-bench/program-id/v1" || base secret key (32 bytes) || i as u32 little-endian)
+//! ```
+//!
+//! so anyone holding the base keypair can recreate the whole set, while the
+//! addresses alone reveal nothing about it.
+
+use crate::sdk::{keypair_from_seed, read_keypair, Keypair, Signer};
+use solana_program::{hash::hashv, pubkey::Pubkey};
+use std::{error::Error, fs, path::Path};
+
+pub const DERIVATION_DOMAIN: &[u8] = b"cu-bench/program-id/v1";
+
+/// Program keypair of sweep point `index`, derived from `base`.
+pub fn derive_program_keypair(base: &Keypair, index: u32) -> Keypair {
+    let secret = base.to_bytes();
+    let seed = hashv(&[DERIVATION_DOMAIN, &secret[..32], &index.to_le_bytes()]);
+    keypair_from_seed(seed.as_ref()).expect("a 32-byte seed is always long enough")
+}
+
+/// Program ids of sweep points `0..count`.
+pub fn derive_program_ids(base: &Keypair, count: u32) -> Vec<Pubkey> {
+    (0..count)
+        .map(|index| derive_program_keypair(base, index).pubkey())
+        .collect()
+}
+
+/// Every `*.json` keypair file in `dir`, in file name order, which is the
+/// order of the sweep points.
+pub fn load_keypair_set(dir: &Path) -> Result<Vec<Keypair>, Box<dyn Error>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| {
+            read_keypair(&mut fs::File::open(path)?)
+                .map_err(|e| format!("{}: {}", path.display(), e).into())
+        })
+        .collect()
+}
+
+/// Writes `keypairs` to `dir` as `program-{index}.json`, in the JSON byte
+/// array format `solana program deploy --program-id` reads. Indices are zero
+/// padded so file name order matches [`load_keypair_set`].
+pub fn write_keypair_set(dir: &Path, keypairs: &[Keypair]) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+    for (index, keypair) in keypairs.iter().enumerate() {
+        let path = dir.join(format!("program-{:04}.json", index));
+        fs::write(path, format!("{:?}", keypair.to_bytes().as_slice()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_derived_ids_are_stable_and_round_trip() {
+        let base = keypair_from_seed(&[7; 32]).unwrap();
+        let ids = derive_program_ids(&base, 3);
+        assert_eq!(ids, derive_program_ids(&base, 3));
+        assert_ne!(ids[0], ids[1]);
+        assert_ne!(
+            ids[0],
+            derive_program_ids(&keypair_from_seed(&[8; 32]).unwrap(), 1)[0]
+        );
+
+        let dir = std::env::temp_dir().join(format!("cu-bench-ids-{}", std::process::id()));
+        let keypairs: Vec<Keypair> = (0..3)
+            .map(|index| derive_program_keypair(&base, index))
+            .collect();
+        write_keypair_set(&dir, &keypairs).unwrap();
+        let loaded: Vec<Pubkey> = load_keypair_set(&dir)
+            .unwrap()
+            .iter()
+            .map(Keypair::pubkey)
+            .collect();
+        assert_eq!(loaded, ids);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
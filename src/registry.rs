@@ -0,0 +1,210 @@
+//! Experiment registry: with the `registry` feature the program records run
+//! summaries into a per-publisher PDA, so size-vs-CU results can be published
+//! on chain, next to the transactions they summarize. Builds without the
+//! feature reject [`BenchInstruction::RecordRun`], keeping the benchmarked
+//! binary free of the extra code.
+//!
+//! [`BenchInstruction::RecordRun`] takes the accounts
+//! `[publisher (signer, writable), registry PDA (writable), system program]`.
+//! The registry is created on first use and holds up to [`REGISTRY_CAPACITY`]
+//! entries, after which recording fails with [`REGISTRY_FULL`].
+
+use crate::instruction::BenchInstruction;
+#[cfg(feature = "registry")]
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+};
+
+pub const REGISTRY_SEED: &[u8] = b"registry";
+pub const REGISTRY_CAPACITY: usize = 64;
+/// Entry count, then the entries in the order they were recorded.
+pub const REGISTRY_LEN: usize = 4 + REGISTRY_CAPACITY * RegistryEntry::LEN;
+/// Custom program error returned once the registry holds its capacity.
+pub const REGISTRY_FULL: u32 = 2;
+
+/// What a publisher claims about one run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunSummary {
+    /// Identifies the run, see [`RunSummary::from_report`].
+    pub run_id: [u8; 32],
+    pub sample_count: u32,
+    pub median_compute_units: u64,
+}
+
+impl RunSummary {
+    pub const LEN: usize = 32 + 4 + 8;
+
+    pub fn pack_into(&self, data: &mut Vec<u8>) {
+        data.extend_from_slice(&self.run_id);
+        data.extend_from_slice(&self.sample_count.to_le_bytes());
+        data.extend_from_slice(&self.median_compute_units.to_le_bytes());
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        let data = data
+            .get(..Self::LEN)
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        let (run_id, rest) = data.split_at(32);
+        let (sample_count, median) = rest.split_at(4);
+        Ok(Self {
+            run_id: run_id.try_into().unwrap(),
+            sample_count: u32::from_le_bytes(sample_count.try_into().unwrap()),
+            median_compute_units: u64::from_le_bytes(median.try_into().unwrap()),
+        })
+    }
+}
+
+/// A recorded [`RunSummary`] and the slot it was recorded in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegistryEntry {
+    pub summary: RunSummary,
+    pub slot: u64,
+}
+
+impl RegistryEntry {
+    pub const LEN: usize = RunSummary::LEN + 8;
+}
+
+pub fn find_registry_address(program_id: &Pubkey, publisher: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REGISTRY_SEED, publisher.as_ref()], program_id)
+}
+
+#[cfg(feature = "registry")]
+pub fn process_record_run(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    summary: &RunSummary,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let publisher = next_account_info(account_info_iter)?;
+    let registry = next_account_info(account_info_iter)?;
+    let system = next_account_info(account_info_iter)?;
+
+    if !publisher.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (registry_address, bump) = find_registry_address(program_id, publisher.key);
+    if *registry.key != registry_address || !system_program::check_id(system.key) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if registry.data_is_empty() {
+        let lamports = Rent::get()?.minimum_balance(REGISTRY_LEN);
+        invoke_signed(
+            &crate::guard::create_account(
+                publisher.key,
+                registry.key,
+                lamports,
+                REGISTRY_LEN as u64,
+                program_id,
+            ),
+            &[publisher.clone(), registry.clone(), system.clone()],
+            &[&[REGISTRY_SEED, publisher.key.as_ref(), &[bump]]],
+        )?;
+    } else if registry.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let mut data = registry.try_borrow_mut_data()?;
+    let count = u32::from_le_bytes(data[..4].try_into().unwrap()) as usize;
+    if count == REGISTRY_CAPACITY {
+        return Err(ProgramError::Custom(REGISTRY_FULL));
+    }
+    let mut entry = Vec::with_capacity(RegistryEntry::LEN);
+    summary.pack_into(&mut entry);
+    entry.extend_from_slice(&Clock::get()?.slot.to_le_bytes());
+    let offset = 4 + count * RegistryEntry::LEN;
+    data[offset..offset + RegistryEntry::LEN].copy_from_slice(&entry);
+    data[..4].copy_from_slice(&(count as u32 + 1).to_le_bytes());
+    Ok(())
+}
+
+/// Entries of a registry account, oldest first.
+pub fn parse_registry(data: &[u8]) -> Result<Vec<RegistryEntry>, ProgramError> {
+    let count = data
+        .get(..4)
+        .map(|count| u32::from_le_bytes(count.try_into().unwrap()) as usize)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let entries = data
+        .get(4..4 + count * RegistryEntry::LEN)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    entries
+        .chunks_exact(RegistryEntry::LEN)
+        .map(|entry| {
+            let (summary, slot) = entry.split_at(RunSummary::LEN);
+            Ok(RegistryEntry {
+                summary: RunSummary::unpack(summary)?,
+                slot: u64::from_le_bytes(slot.try_into().unwrap()),
+            })
+        })
+        .collect()
+}
+
+/// Builds the instruction recording `summary` in `publisher`'s registry.
+pub fn record_run(program_id: &Pubkey, publisher: &Pubkey, summary: RunSummary) -> Instruction {
+    let (registry, _) = find_registry_address(program_id, publisher);
+    BenchInstruction::RecordRun { summary }.to_instruction(
+        *program_id,
+        vec![
+            AccountMeta::new(*publisher, true),
+            AccountMeta::new(registry, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+#[cfg(not(target_os = "solana"))]
+impl RunSummary {
+    /// Summary of the steady-state samples of `report`. The run id is the
+    /// SHA-256 of the signatures of every landed sample in order, so anyone can
+    /// check the summary against the transactions it claims to describe.
+    /// `None` when no sample reached steady state.
+    pub fn from_report(report: &crate::client::CuReport) -> Option<Self> {
+        let signatures: Vec<&[u8]> = report
+            .run
+            .samples()
+            .map(|sample| sample.signature.as_ref())
+            .collect();
+        Some(Self {
+            run_id: solana_program::hash::hashv(&signatures).to_bytes(),
+            sample_count: report.steady_state_compute_units().len() as u32,
+            median_compute_units: report.median_compute_units()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_registry_layout() {
+        let summary = RunSummary {
+            run_id: [7; 32],
+            sample_count: 95,
+            median_compute_units: 1_234,
+        };
+        let mut data = 2u32.to_le_bytes().to_vec();
+        for slot in [10u64, 11] {
+            summary.pack_into(&mut data);
+            data.extend_from_slice(&slot.to_le_bytes());
+        }
+        data.resize(REGISTRY_LEN, 0);
+
+        let entries = parse_registry(&data).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1], RegistryEntry { summary, slot: 11 });
+        assert!(parse_registry(&data[..RegistryEntry::LEN]).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+//! In-memory access to a run's samples for downstream Rust tools.
+
+use crate::{
+    failure::{FailureCounts, InstructionFailure},
+    sample::{Sample, TagFilter},
+};
+use solana_program::pubkey::Pubkey;
+
+#[derive(Debug, Clone)]
+pub struct Run {
+    pub program_id: Pubkey,
+    pub failures: FailureCounts,
+    /// Transactions that failed inside an instruction, in send order.
+    pub instruction_failures: Vec<InstructionFailure>,
+    /// Transactions the run tried to send; those that landed are its samples.
+    pub attempted: u64,
+    samples: Vec<Sample>,
+}
+
+impl Run {
+    pub fn new(program_id: Pubkey) -> Self {
+        Self {
+            program_id,
+            failures: FailureCounts::default(),
+            instruction_failures: Vec::new(),
+            attempted: 0,
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, sample: Sample) {
+        self.samples.push(sample);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn samples(&self) -> impl Iterator<Item = &Sample> {
+        self.samples.iter()
+    }
+
+    /// Mutable access for tagging passes such as [`crate::sample::tag_outliers`].
+    pub fn samples_mut(&mut self) -> &mut [Sample] {
+        &mut self.samples
+    }
+
+    /// Share of the attempted transactions that landed; `None` before any
+    /// was attempted.
+    pub fn landing_rate(&self) -> Option<f64> {
+        (self.attempted > 0).then(|| self.samples.len() as f64 / self.attempted as f64)
+    }
+
+    pub fn filter<'a>(&'a self, filter: &'a TagFilter) -> impl Iterator<Item = &'a Sample> {
+        self.samples().filter(move |sample| filter.matches(sample))
+    }
+}
+
+impl IntoIterator for Run {
+    type Item = Sample;
+    type IntoIter = std::vec::IntoIter<Sample>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.samples.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Run {
+    type Item = &'a Sample;
+    type IntoIter = std::slice::Iter<'a, Sample>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.samples.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        sample::{tag_warmup, SampleTag},
+        sdk::Signature,
+    };
+
+    #[test]
+    fn test_run_samples_and_filter() {
+        let mut run = Run::new(Pubkey::new_unique());
+        for index in 0..5 {
+            run.push(Sample::new(
+                index,
+                Signature::default(),
+                index,
+                Some(1_000 + index),
+            ));
+        }
+        tag_warmup(run.samples_mut(), 2);
+
+        assert_eq!(run.samples().count(), 5);
+        let steady_state: Vec<u64> = run
+            .filter(&TagFilter::steady_state())
+            .map(|sample| sample.index)
+            .collect();
+        assert_eq!(steady_state, [2, 3, 4]);
+        assert!(run
+            .into_iter()
+            .take(2)
+            .all(|sample| sample.has_tag(SampleTag::Warmup)));
+    }
+}
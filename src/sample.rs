@@ -0,0 +1,308 @@
+//! Per-transaction measurement records and the tags used to separate noisy
+//! samples from steady-state ones without discarding raw data.
+
+use crate::{metrics::Metric, sdk::Signature, stats};
+use solana_program::{clock::DEFAULT_MS_PER_SLOT, epoch_schedule::EpochSchedule, pubkey::Pubkey};
+use std::{collections::BTreeSet, time::Duration};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum SampleTag {
+    /// Sent while caches and connections were still warming up.
+    Warmup,
+    /// Took more than [`CONGESTED_LAND_SLOTS`] slots to land, as on a
+    /// congested cluster.
+    Congestion,
+    /// Landed in the first [`EPOCH_BOUNDARY_SLOTS`] slots of an epoch.
+    EpochBoundary,
+    /// CU far outside the bulk of the run.
+    Outlier,
+}
+
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Sample {
+    pub index: u64,
+    pub signature: Signature,
+    pub slot: u64,
+    /// Unix timestamp of the block the sample landed in, when the node knew it.
+    pub block_time: Option<i64>,
+    /// CU from the transaction meta, or from the logs when the meta omits it.
+    pub compute_units: Option<u64>,
+    /// CU summed from the runtime's `consumed` log lines, to cross-check
+    /// `compute_units`; `None` when builtins left part of the cost unlogged.
+    pub log_compute_units: Option<u64>,
+    /// CU reported by simulating the transaction right before it was sent.
+    pub simulated_compute_units: Option<u64>,
+    /// Serialized size of the signed transaction in bytes.
+    pub transaction_size: Option<usize>,
+    /// Name and decoded parameters of the bench instructions the transaction
+    /// carried, see [`label_instructions`](crate::instruction::label_instructions).
+    pub instruction: Option<String>,
+    /// CU the documented syscall costs predict for those instructions, see
+    /// [`crate::predict`]; `None` when the model does not price them.
+    pub syscall_compute_units: Option<u64>,
+    /// Values emitted by the run's metric extractors for this transaction.
+    pub metrics: Vec<Metric>,
+    /// Lamport and token balances the transaction changed.
+    pub balance_deltas: Vec<BalanceDelta>,
+    pub timings: PhaseTimings,
+    pub tags: BTreeSet<SampleTag>,
+}
+
+impl Sample {
+    pub fn new(index: u64, signature: Signature, slot: u64, compute_units: Option<u64>) -> Self {
+        Self {
+            index,
+            signature,
+            slot,
+            block_time: None,
+            compute_units,
+            log_compute_units: None,
+            simulated_compute_units: None,
+            transaction_size: None,
+            instruction: None,
+            syscall_compute_units: None,
+            metrics: Vec::new(),
+            balance_deltas: Vec::new(),
+            timings: PhaseTimings::default(),
+            tags: BTreeSet::new(),
+        }
+    }
+
+    /// Executed minus simulated CU, when both were recorded.
+    pub fn cu_discrepancy(&self) -> Option<i64> {
+        Some(self.compute_units? as i64 - self.simulated_compute_units? as i64)
+    }
+
+    pub fn has_tag(&self, tag: SampleTag) -> bool {
+        self.tags.contains(&tag)
+    }
+
+    /// The first value emitted under `name`, if any extractor produced one.
+    pub fn metric(&self, name: &str) -> Option<f64> {
+        self.metrics
+            .iter()
+            .find(|metric| metric.name == name)
+            .map(|metric| metric.value)
+    }
+}
+
+/// One account's balance before and after a transaction: lamports, or token
+/// base units when `mint` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceDelta {
+    /// Position of the account in the transaction's account keys.
+    pub account_index: usize,
+    /// `None` when the transaction could not be decoded to resolve the index.
+    pub account: Option<Pubkey>,
+    pub mint: Option<Pubkey>,
+    pub pre: u64,
+    pub post: u64,
+}
+
+impl BalanceDelta {
+    pub fn change(&self) -> i128 {
+        i128::from(self.post) - i128::from(self.pre)
+    }
+}
+
+/// Wall-clock time a sample spent in each phase, so network delay can be told
+/// apart from runtime cost.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseTimings {
+    pub build: Duration,
+    pub sign: Duration,
+    pub send_rpc: Duration,
+    /// Estimated from the slots elapsed between sending and landing.
+    pub land: Option<Duration>,
+    /// From landing until the harness observed the confirmation.
+    pub confirm_detect: Option<Duration>,
+}
+
+impl PhaseTimings {
+    /// Per-phase mean; optional phases average only the samples that recorded them.
+    pub fn mean<'a>(timings: impl IntoIterator<Item = &'a PhaseTimings>) -> Option<Self> {
+        let timings: Vec<&PhaseTimings> = timings.into_iter().collect();
+        if timings.is_empty() {
+            return None;
+        }
+        let mean = |durations: Vec<Duration>| -> Option<Duration> {
+            let count = u32::try_from(durations.len()).ok().filter(|n| *n > 0)?;
+            Some(durations.iter().sum::<Duration>() / count)
+        };
+
+        Some(Self {
+            build: mean(timings.iter().map(|t| t.build).collect())?,
+            sign: mean(timings.iter().map(|t| t.sign).collect())?,
+            send_rpc: mean(timings.iter().map(|t| t.send_rpc).collect())?,
+            land: mean(timings.iter().filter_map(|t| t.land).collect()),
+            confirm_detect: mean(timings.iter().filter_map(|t| t.confirm_detect).collect()),
+        })
+    }
+}
+
+/// Selects samples by tag. A sample passes when it carries none of the excluded
+/// tags and, if any tags are required, at least one of them.
+#[derive(Debug, Clone, Default)]
+pub struct TagFilter {
+    pub include: BTreeSet<SampleTag>,
+    pub exclude: BTreeSet<SampleTag>,
+}
+
+impl TagFilter {
+    /// Passes every sample.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Drops every tagged sample, leaving steady-state behavior only.
+    pub fn steady_state() -> Self {
+        Self {
+            include: BTreeSet::new(),
+            exclude: [
+                SampleTag::Warmup,
+                SampleTag::Congestion,
+                SampleTag::EpochBoundary,
+                SampleTag::Outlier,
+            ]
+            .into(),
+        }
+    }
+
+    pub fn matches(&self, sample: &Sample) -> bool {
+        let included =
+            self.include.is_empty() || self.include.iter().any(|tag| sample.has_tag(*tag));
+        included && !self.exclude.iter().any(|tag| sample.has_tag(*tag))
+    }
+
+    /// CU of the matching samples that reported it, in sample order.
+    pub fn compute_units<'a>(&self, samples: impl IntoIterator<Item = &'a Sample>) -> Vec<u64> {
+        samples
+            .into_iter()
+            .filter(|sample| self.matches(sample))
+            .filter_map(|sample| sample.compute_units)
+            .collect()
+    }
+}
+
+/// Tags the first `count` samples by index as [`SampleTag::Warmup`].
+pub fn tag_warmup(samples: &mut [Sample], count: u64) {
+    for sample in samples.iter_mut().filter(|sample| sample.index < count) {
+        sample.tags.insert(SampleTag::Warmup);
+    }
+}
+
+/// Slots at the start of an epoch whose samples are tagged
+/// [`SampleTag::EpochBoundary`]: the first blocks of an epoch also carry the
+/// stake and reward work of the new epoch.
+pub const EPOCH_BOUNDARY_SLOTS: u64 = 32;
+
+/// Slots from send to landing beyond which a sample is tagged
+/// [`SampleTag::Congestion`]; an uncongested leader includes a transaction
+/// within a slot or two.
+pub const CONGESTED_LAND_SLOTS: u64 = 8;
+
+/// Tags samples that landed in the first [`EPOCH_BOUNDARY_SLOTS`] slots of an
+/// epoch of `schedule` as [`SampleTag::EpochBoundary`].
+pub fn tag_epoch_boundaries(samples: &mut [Sample], schedule: &EpochSchedule) {
+    for sample in samples.iter_mut() {
+        let (_, slot_index) = schedule.get_epoch_and_slot_index(sample.slot);
+        if slot_index < EPOCH_BOUNDARY_SLOTS {
+            sample.tags.insert(SampleTag::EpochBoundary);
+        }
+    }
+}
+
+/// Tags samples that took more than [`CONGESTED_LAND_SLOTS`] slots to land as
+/// [`SampleTag::Congestion`].
+pub fn tag_congestion(samples: &mut [Sample]) {
+    let threshold = Duration::from_millis(CONGESTED_LAND_SLOTS * DEFAULT_MS_PER_SLOT);
+    for sample in samples.iter_mut() {
+        if sample.timings.land.is_some_and(|land| land > threshold) {
+            sample.tags.insert(SampleTag::Congestion);
+        }
+    }
+}
+
+/// Tags samples whose CU falls outside Tukey's fences (1.5 × IQR) as
+/// [`SampleTag::Outlier`].
+pub fn tag_outliers(samples: &mut [Sample]) {
+    let mut cu: Vec<f64> = samples
+        .iter()
+        .filter_map(|sample| sample.compute_units)
+        .map(|cu| cu as f64)
+        .collect();
+    if cu.len() < 4 {
+        return;
+    }
+    cu.sort_by(f64::total_cmp);
+    let (low, high) = stats::tukey_fences(&cu);
+
+    for sample in samples.iter_mut() {
+        if let Some(cu) = sample.compute_units {
+            if (cu as f64) < low || (cu as f64) > high {
+                sample.tags.insert(SampleTag::Outlier);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mean_phase_timings() {
+        let fast = PhaseTimings {
+            send_rpc: Duration::from_millis(2),
+            land: Some(Duration::from_millis(400)),
+            ..PhaseTimings::default()
+        };
+        let slow = PhaseTimings {
+            send_rpc: Duration::from_millis(4),
+            ..PhaseTimings::default()
+        };
+
+        let mean = PhaseTimings::mean([&fast, &slow]).unwrap();
+        assert_eq!(mean.send_rpc, Duration::from_millis(3));
+        assert_eq!(mean.land, Some(Duration::from_millis(400)));
+        assert_eq!(mean.confirm_detect, None);
+        assert_eq!(PhaseTimings::mean([]), None);
+    }
+
+    #[test]
+    fn test_tag_filters() {
+        let mut samples: Vec<Sample> = (0..10)
+            .map(|i| Sample::new(i, Signature::default(), 100 + i, Some(1_400)))
+            .collect();
+        samples[9].compute_units = Some(9_000);
+        tag_warmup(&mut samples, 2);
+        tag_outliers(&mut samples);
+
+        assert!(samples[0].has_tag(SampleTag::Warmup));
+        assert!(samples[9].has_tag(SampleTag::Outlier));
+        assert_eq!(TagFilter::all().compute_units(&samples).len(), 10);
+        assert_eq!(
+            TagFilter::steady_state().compute_units(&samples),
+            vec![1_400; 7]
+        );
+
+        let schedule = EpochSchedule::without_warmup();
+        let mut boundary = [
+            Sample::new(0, Signature::default(), schedule.slots_per_epoch + 31, None),
+            Sample::new(1, Signature::default(), schedule.slots_per_epoch + 32, None),
+        ];
+        boundary[1].timings.land = Some(Duration::from_millis(9 * DEFAULT_MS_PER_SLOT));
+        tag_epoch_boundaries(&mut boundary, &schedule);
+        tag_congestion(&mut boundary);
+        assert!(boundary[0].tags == [SampleTag::EpochBoundary].into());
+        assert!(boundary[1].tags == [SampleTag::Congestion].into());
+
+        let outliers_only = TagFilter {
+            include: [SampleTag::Outlier].into(),
+            ..TagFilter::default()
+        };
+        assert_eq!(outliers_only.compute_units(&samples), vec![9_000]);
+    }
+}
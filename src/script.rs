@@ -0,0 +1,190 @@
+//! Rhai scripts that customize a run without recompiling. A script may define
+//! any of these functions:
+//!
+//! ```text
+//! // Instruction data for transaction `i`, as an array of bytes or a blob;
+//! // returning () ends the workload
+//! fn payload(i) {
+//!     if i >= 20 { return (); }
+//!     [1, i, 0, 0, 0, 0, 0, 0, 0]
+//! }
+//!
+//! // Log fragment transaction `i` must contain
+//! fn expected_log(i) { `Count: ${i}` }
+//!
+//! // Derived metrics of a landed transaction; `cu` and `fee` are () when the
+//! // node did not report them
+//! fn metrics(cu, fee, logs) { #{ log_lines: logs.len() } }
+//! ```
+//!
+//! The send phase does not wait for results, so `payload` decides when to
+//! stop from the transaction index alone.
+
+use crate::{
+    metrics::{MetricExtractor, MetricSink},
+    tx_view::ConfirmedTxView,
+    workload::{Workload, WorkloadContext, WorkloadTransaction},
+};
+use rhai::{Array, Blob, Dynamic, Engine, Map, Scope, AST};
+use solana_program::instruction::Instruction;
+use std::{error::Error, path::Path, sync::Arc};
+use tracing::warn;
+
+/// A compiled script, cheap to clone into workload factories.
+#[derive(Clone)]
+pub struct Script {
+    engine: Arc<Engine>,
+    ast: Arc<AST>,
+}
+
+impl Script {
+    pub fn compile(source: &str) -> Result<Self, Box<dyn Error>> {
+        let engine = Engine::new();
+        let ast = engine.compile(source)?;
+        Ok(Self {
+            engine: Arc::new(engine),
+            ast: Arc::new(ast),
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Self::compile(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn defines(&self, name: &str) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|function| function.name == name)
+    }
+
+    fn call(&self, name: &str, args: impl rhai::FuncArgs) -> Result<Dynamic, Box<dyn Error>> {
+        Ok(self
+            .engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &self.ast, name, args)?)
+    }
+
+    fn payload(&self, index: u64) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        let value = self.call("payload", (index as i64,))?;
+        if value.is_unit() {
+            return Ok(None);
+        }
+        if value.is_blob() {
+            return Ok(value.try_cast::<Blob>());
+        }
+        let bytes = value
+            .try_cast::<Array>()
+            .ok_or("payload must return an array of bytes, a blob or ()")?;
+        bytes
+            .into_iter()
+            .map(|byte| {
+                byte.as_int()
+                    .ok()
+                    .and_then(|byte| u8::try_from(byte).ok())
+                    .ok_or_else(|| "payload bytes must be integers in 0..=255".into())
+            })
+            .collect::<Result<_, Box<dyn Error>>>()
+            .map(Some)
+    }
+
+    fn expected_log(&self, index: u64) -> Result<Option<String>, Box<dyn Error>> {
+        if !self.defines("expected_log") {
+            return Ok(None);
+        }
+        Ok(Some(
+            self.call("expected_log", (index as i64,))?.to_string(),
+        ))
+    }
+}
+
+/// Sends the benchmark program one instruction per `payload(i)` of the script.
+pub struct ScriptedWorkload {
+    script: Script,
+    next: u64,
+}
+
+impl ScriptedWorkload {
+    pub fn new(script: Script) -> Self {
+        Self { script, next: 0 }
+    }
+}
+
+impl Workload for ScriptedWorkload {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        let index = self.next;
+        self.next += 1;
+
+        let transaction = self.script.payload(index).and_then(|data| {
+            let Some(data) = data else {
+                return Ok(None);
+            };
+            Ok(Some(WorkloadTransaction {
+                instructions: vec![Instruction::new_with_bytes(ctx.program_id, &data, vec![])],
+                expected_log: self.script.expected_log(index)?,
+            }))
+        });
+        transaction.unwrap_or_else(|e| {
+            warn!("Script failed at transaction {}, stopping: {}", index, e);
+            None
+        })
+    }
+}
+
+/// Emits the numeric entries of the map returned by the script's `metrics`.
+impl MetricExtractor for Script {
+    fn extract(&self, tx: &ConfirmedTxView, sink: &mut MetricSink) {
+        let optional = |value: Option<u64>| value.map_or(Dynamic::UNIT, |v| (v as i64).into());
+        let logs: Array = tx.logs().map(|line| line.to_string().into()).collect();
+        let result = self.call(
+            "metrics",
+            (optional(tx.cu_consumed()), optional(tx.fee()), logs),
+        );
+        let metrics = match result.map(|value| value.try_cast::<Map>()) {
+            Ok(Some(metrics)) => metrics,
+            Ok(None) => {
+                warn!("Script metrics must return a map");
+                return;
+            }
+            Err(e) => {
+                warn!("Script metrics failed: {}", e);
+                return;
+            }
+        };
+        for (name, value) in metrics {
+            let value = value
+                .as_float()
+                .ok()
+                .or_else(|| value.as_int().ok().map(|value| value as f64));
+            if let Some(value) = value {
+                sink.emit(name.to_string(), value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_script_payload_and_logs() {
+        let script = Script::compile(
+            r#"
+            fn payload(i) { if i < 2 { [1, i, 0, 0, 0, 0, 0, 0, 0] } else { () } }
+            fn expected_log(i) { `Count: ${i}` }
+            "#,
+        )
+        .unwrap();
+        assert!(script.defines("payload"));
+        assert!(!script.defines("metrics"));
+
+        assert_eq!(
+            script.payload(1).unwrap(),
+            Some(vec![1, 1, 0, 0, 0, 0, 0, 0, 0])
+        );
+        assert_eq!(script.payload(2).unwrap(), None);
+        assert_eq!(script.expected_log(1).unwrap().as_deref(), Some("Count: 1"));
+
+        let invalid = Script::compile("fn payload(i) { [256] }").unwrap();
+        assert!(invalid.payload(0).is_err());
+    }
+}
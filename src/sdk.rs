@@ -0,0 +1,49 @@
+//! Client-side Solana types, sourced either from the monolithic `solana-sdk` /
+//! `solana-client` crates (`legacy-sdk`, the default) or from the split
+//! `solana-*` crates (`split-crates`). Off-chain modules import through here so
+//! consumers can stay in whichever dependency universe they already use.
+
+#[cfg(feature = "legacy-sdk")]
+pub use {
+    solana_client::{
+        client_error::{ClientError, ClientErrorKind},
+        rpc_client::RpcClient,
+        rpc_config::{RpcSimulateTransactionConfig, RpcTransactionConfig},
+        rpc_request::{RpcError, RpcResponseErrorData},
+    },
+    solana_sdk::{
+        account::Account,
+        commitment_config::CommitmentConfig,
+        derivation_path::DerivationPath,
+        signature::{
+            generate_seed_from_seed_phrase_and_passphrase, keypair_from_seed,
+            keypair_from_seed_and_derivation_path, read_keypair, Keypair, Signature, Signer,
+        },
+        transaction::{Transaction, TransactionError, VersionedTransaction},
+    },
+};
+
+#[cfg(all(feature = "split-crates", not(feature = "legacy-sdk")))]
+pub use {
+    solana_account::Account,
+    solana_commitment_config::CommitmentConfig,
+    solana_derivation_path::DerivationPath,
+    solana_keypair::{
+        keypair_from_seed, read_keypair, seed_derivable::keypair_from_seed_and_derivation_path,
+        Keypair,
+    },
+    solana_rpc_client::rpc_client::RpcClient,
+    solana_rpc_client_api::{
+        client_error::{Error as ClientError, ErrorKind as ClientErrorKind},
+        config::{RpcSimulateTransactionConfig, RpcTransactionConfig},
+        request::{RpcError, RpcResponseErrorData},
+    },
+    solana_seed_phrase::generate_seed_from_seed_phrase_and_passphrase,
+    solana_signature::Signature,
+    solana_signer::Signer,
+    solana_transaction::{versioned::VersionedTransaction, Transaction},
+    solana_transaction_error::TransactionError,
+};
+
+#[cfg(not(any(feature = "legacy-sdk", feature = "split-crates")))]
+compile_error!("enable either the `legacy-sdk` or the `split-crates` feature");
@@ -0,0 +1,199 @@
+//! Capacity searches over workload parameters, and the search for the
+//! smallest compute unit limit a transaction needs.
+
+use crate::{
+    accounting::MAX_COMPUTE_UNIT_LIMIT,
+    instruction::{set_compute_unit_limit, sets_compute_unit_limit},
+    sdk::{RpcClient, RpcSimulateTransactionConfig, Transaction},
+};
+use solana_program::{instruction::Instruction, message::Message, pubkey::Pubkey};
+use std::error::Error;
+
+/// The largest parameter value found to stay under a CU limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frontier {
+    pub value: u64,
+    pub compute_units: u64,
+}
+
+/// Binary-searches `[low, high]` for the largest value whose measured CU is at most
+/// `limit`, assuming CU never decreases as the value grows. `measure` returns
+/// `None` when the transaction did not fit at all (e.g. it ran out of compute
+/// budget), which counts as over the limit. Returns `None` if even `low` is over.
+pub fn max_under_limit<E>(
+    low: u64,
+    high: u64,
+    limit: u64,
+    mut measure: impl FnMut(u64) -> Result<Option<u64>, E>,
+) -> Result<Option<Frontier>, E> {
+    let mut fits =
+        |value: u64| -> Result<Option<u64>, E> { Ok(measure(value)?.filter(|&cu| cu <= limit)) };
+
+    let Some(compute_units) = fits(low)? else {
+        return Ok(None);
+    };
+    let mut best = Frontier {
+        value: low,
+        compute_units,
+    };
+
+    let (mut lo, mut hi) = (low, high);
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        match fits(mid)? {
+            Some(compute_units) => {
+                best = Frontier {
+                    value: mid,
+                    compute_units,
+                };
+                lo = mid;
+            }
+            None => hi = mid - 1,
+        }
+    }
+
+    Ok(Some(best))
+}
+
+/// Binary-searches `[low, high]` for the smallest value that `passes`,
+/// assuming every larger value passes too. Returns `None` if even `high`
+/// fails.
+pub fn min_passing<E>(
+    low: u64,
+    high: u64,
+    mut passes: impl FnMut(u64) -> Result<bool, E>,
+) -> Result<Option<u64>, E> {
+    if !passes(high)? {
+        return Ok(None);
+    }
+    let (mut lo, mut hi) = (low, high);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if passes(mid)? {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    Ok(Some(lo))
+}
+
+/// The smallest compute unit limit a transaction succeeds under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitSearch {
+    pub limit: u32,
+    /// CU the transaction consumed under `limit`.
+    pub compute_units: Option<u64>,
+    pub simulations: usize,
+}
+
+/// Binary-searches the `SetComputeUnitLimit` value between 0 and
+/// [`MAX_COMPUTE_UNIT_LIMIT`] for the smallest at which `instructions`
+/// succeed, replacing any limit they already set. Each step is a simulation
+/// without signature checks, so `payer` only has to be an existing account
+/// that can pay the fee. Fails if the instructions fail even at the maximum.
+pub fn minimal_compute_unit_limit(
+    rpc_client: &RpcClient,
+    payer: &Pubkey,
+    instructions: &[Instruction],
+) -> Result<LimitSearch, Box<dyn Error>> {
+    let instructions: Vec<Instruction> = instructions
+        .iter()
+        .filter(|instruction| !sets_compute_unit_limit(instruction))
+        .cloned()
+        .collect();
+    let mut simulations = 0;
+    let mut simulate = |limit: u32| -> Result<_, Box<dyn Error>> {
+        simulations += 1;
+        let mut with_limit = vec![set_compute_unit_limit(limit)];
+        with_limit.extend_from_slice(&instructions);
+        let transaction = Transaction::new_unsigned(Message::new(&with_limit, Some(payer)));
+        let result = rpc_client
+            .simulate_transaction_with_config(
+                &transaction,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    ..RpcSimulateTransactionConfig::default()
+                },
+            )?
+            .value;
+        Ok((result.err, result.units_consumed))
+    };
+
+    if let (Some(err), _) = simulate(MAX_COMPUTE_UNIT_LIMIT)? {
+        return Err(format!(
+            "transaction fails even at the maximum limit of {} CU: {}",
+            MAX_COMPUTE_UNIT_LIMIT, err
+        )
+        .into());
+    }
+    let mut compute_units = None;
+    let limit = min_passing(0, MAX_COMPUTE_UNIT_LIMIT.into(), |limit| {
+        let (err, consumed) = simulate(limit as u32)?;
+        if err.is_none() {
+            compute_units = consumed;
+        }
+        Ok::<_, Box<dyn Error>>(err.is_none())
+    })?
+    .ok_or("transaction stopped succeeding at the maximum limit")?;
+    Ok(LimitSearch {
+        limit: limit as u32,
+        compute_units,
+        simulations,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::convert::Infallible;
+
+    #[test]
+    fn test_max_under_limit() {
+        // 300 CU of fixed overhead plus 7 CU per iteration
+        let cost = |iterations: u64| Ok::<_, Infallible>(Some(300 + 7 * iterations));
+
+        let frontier = max_under_limit(0, 1_000_000, 1_400_000, cost).unwrap();
+        assert_eq!(
+            frontier,
+            Some(Frontier {
+                value: 199_957,
+                compute_units: 1_399_999,
+            })
+        );
+        assert_eq!(max_under_limit(0, 10, 100, cost).unwrap(), None);
+        assert_eq!(
+            max_under_limit(0, 10, 1_000, cost).unwrap().unwrap().value,
+            10
+        );
+
+        let exhausted = |len: u64| Ok::<_, Infallible>((len <= 512).then_some(1_000));
+        assert_eq!(
+            max_under_limit(1, 10_240, 1_400_000, exhausted)
+                .unwrap()
+                .unwrap()
+                .value,
+            512
+        );
+    }
+
+    #[test]
+    fn test_min_passing() {
+        let mut steps = 0;
+        let needs_1234 = |limit: u64| {
+            steps += 1;
+            Ok::<_, Infallible>(limit >= 1_234)
+        };
+        assert_eq!(min_passing(0, 1_400_000, needs_1234).unwrap(), Some(1_234));
+        assert!(steps <= 22);
+        assert_eq!(
+            min_passing(0, 1_000, |limit| Ok::<_, Infallible>(limit >= 1_234)).unwrap(),
+            None
+        );
+        assert_eq!(
+            min_passing(0, 10, |_| Ok::<_, Infallible>(true)).unwrap(),
+            Some(0)
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//! Blocking, thread-pool based transaction sender for embedding the harness in
+//! synchronous tools: plain std threads and channels, no async runtime to manage.
+
+use crate::sdk::{CommitmentConfig, RpcClient, Signature, Transaction};
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+#[derive(Debug)]
+pub struct SendOutcome {
+    pub index: u64,
+    pub result: Result<Signature, String>,
+    pub sent_at: Instant,
+}
+
+/// Sends submitted transactions from a fixed number of worker threads, each with
+/// its own RPC client.
+pub struct ThreadedSender {
+    jobs: Sender<(u64, Transaction)>,
+    outcomes: Receiver<SendOutcome>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl ThreadedSender {
+    pub fn new(rpc_url: &str, commitment: CommitmentConfig, threads: usize) -> Self {
+        let (jobs, job_queue) = mpsc::channel::<(u64, Transaction)>();
+        let (outcome_sender, outcomes) = mpsc::channel();
+        let job_queue = Arc::new(Mutex::new(job_queue));
+
+        let workers = (0..threads.max(1))
+            .map(|_| {
+                let job_queue = Arc::clone(&job_queue);
+                let outcome_sender = outcome_sender.clone();
+                let rpc_client = RpcClient::new_with_commitment(rpc_url.to_string(), commitment);
+                thread::spawn(move || loop {
+                    // Hold the queue lock only while taking a job
+                    let job = job_queue.lock().unwrap().recv();
+                    let Ok((index, transaction)) = job else {
+                        break;
+                    };
+                    let result = rpc_client
+                        .send_transaction(&transaction)
+                        .map_err(|e| e.to_string());
+                    let outcome = SendOutcome {
+                        index,
+                        result,
+                        sent_at: Instant::now(),
+                    };
+                    if outcome_sender.send(outcome).is_err() {
+                        break;
+                    }
+                })
+            })
+            .collect();
+
+        Self {
+            jobs,
+            outcomes,
+            workers,
+        }
+    }
+
+    pub fn submit(&self, index: u64, transaction: Transaction) {
+        // Workers only exit once the job channel closes, so this cannot fail
+        let _ = self.jobs.send((index, transaction));
+    }
+
+    /// Waits for every submitted transaction to be sent and returns the outcomes
+    /// in completion order.
+    pub fn finish(self) -> Vec<SendOutcome> {
+        drop(self.jobs);
+        for worker in self.workers {
+            let _ = worker.join();
+        }
+        self.outcomes.try_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash, instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer,
+    };
+
+    #[test]
+    fn test_every_submission_yields_an_outcome() {
+        // Nothing listens on this port, so every send fails fast
+        let sender = ThreadedSender::new("http://127.0.0.1:1", CommitmentConfig::confirmed(), 2);
+        let payer = Keypair::new();
+        let program_id = Pubkey::new_unique();
+        for index in 0..4u64 {
+            let instruction = Instruction::new_with_bytes(program_id, &index.to_le_bytes(), vec![]);
+            let transaction = Transaction::new_signed_with_payer(
+                &[instruction],
+                Some(&payer.pubkey()),
+                &[&payer],
+                Hash::default(),
+            );
+            sender.submit(index, transaction);
+        }
+
+        let mut outcomes = sender.finish();
+        outcomes.sort_by_key(|outcome| outcome.index);
+        assert_eq!(
+            outcomes
+                .iter()
+                .map(|outcome| outcome.index)
+                .collect::<Vec<_>>(),
+            [0, 1, 2, 3]
+        );
+        assert!(outcomes.iter().all(|outcome| outcome.result.is_err()));
+    }
+}
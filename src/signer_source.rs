@@ -0,0 +1,314 @@
+//! Payer and authority keys given the way the Solana CLI takes them, so runs
+//! can use keys managed with an existing CLI setup.
+//!
+//! | Source | Key |
+//! | --- | --- |
+//! | `PATH`, `file:PATH` | JSON keypair file; a leading `~/` is the home directory |
+//! | `prompt://?key=0/0` | seed phrase read from stdin, derived along `m/44'/501'/0'/0'` |
+//! | `prompt://?full-path=m/44/501/1/2` | seed phrase, derived along the full path given |
+//! | `prompt://` | seed phrase, derived along the default `m/44'/501'` |
+//! | `ASK` | seed phrase, legacy derivation (the first 32 bytes of the seed) |
+//! | `keyring://SERVICE/ACCOUNT` | keypair in the OS credential store; needs the `keyring` feature |
+//! | `usb://ledger?key=0/0` | hardware wallet; recognized, but not supported |
+//!
+//! Seed phrases are followed by an optional passphrase, both read without
+//! echo when stdin is a terminal and as two lines otherwise. They are not
+//! checked against the BIP-39 word list: a mistyped phrase yields a
+//! different, valid key, so check the payer address a run prints.
+
+use crate::{
+    redact::register_secret,
+    sdk::{
+        generate_seed_from_seed_phrase_and_passphrase, keypair_from_seed,
+        keypair_from_seed_and_derivation_path, read_keypair, DerivationPath, Keypair,
+    },
+};
+use std::{
+    error::Error,
+    fs,
+    io::{self, BufRead, IsTerminal, Write},
+    path::PathBuf,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignerSource {
+    File(PathBuf),
+    /// A seed phrase typed in. Without a derivation path, `legacy` picks the
+    /// pre-BIP-44 derivation of `ASK` over the default path of `prompt://`.
+    Prompt {
+        derivation_path: Option<DerivationPath>,
+        legacy: bool,
+    },
+    /// A keypair stored with [`store_in_keyring`](crate::payer::store_in_keyring)
+    /// or `cu-bench keyring` under `service` / `account`.
+    Keyring {
+        service: String,
+        account: String,
+    },
+    /// A hardware wallet, e.g. `ledger`; the harness signs with in-memory
+    /// keypairs only, so loading one fails.
+    Usb {
+        wallet: String,
+        derivation_path: Option<DerivationPath>,
+    },
+}
+
+impl SignerSource {
+    /// Parses `source`; a keypair path is registered with the redactor as
+    /// given, as it may start with `~/`, while sources such as `ASK` are not
+    /// secret and stay readable.
+    pub fn parse(source: &str) -> Result<Self, Box<dyn Error>> {
+        if source == "ASK" {
+            return Ok(Self::Prompt {
+                derivation_path: None,
+                legacy: true,
+            });
+        }
+        if let Some(rest) = source.strip_prefix("prompt:") {
+            return Ok(Self::Prompt {
+                derivation_path: parse_derivation_query(rest)?,
+                legacy: false,
+            });
+        }
+        if let Some(rest) = source.strip_prefix("keyring:") {
+            let rest = rest.strip_prefix("//").unwrap_or(rest);
+            return match rest.split_once('/') {
+                Some((service, account)) if !service.is_empty() && !account.is_empty() => {
+                    Ok(Self::Keyring {
+                        service: service.to_string(),
+                        account: account.to_string(),
+                    })
+                }
+                _ => Err(format!("expected keyring://SERVICE/ACCOUNT, got {:?}", source).into()),
+            };
+        }
+        if let Some(rest) = source.strip_prefix("usb://") {
+            let wallet = rest.split('?').next().unwrap_or_default();
+            return Ok(Self::Usb {
+                wallet: wallet.to_string(),
+                derivation_path: parse_derivation_query(rest)?,
+            });
+        }
+        if let Some(path) = source.strip_prefix("file:") {
+            register_secret(path);
+            return Ok(Self::File(expand_home(path)));
+        }
+        if let Some((scheme, _)) = source.split_once("://") {
+            return Err(format!("unsupported signer scheme {}://", scheme).into());
+        }
+        register_secret(source);
+        Ok(Self::File(expand_home(source)))
+    }
+
+    /// Loads the keypair, reading a seed phrase and passphrase from `input`
+    /// when the source prompts for one. Keypair paths, seed phrases and
+    /// passphrases are registered with the redactor, and errors leave them
+    /// out.
+    pub fn load(&self, input: impl BufRead) -> Result<Keypair, Box<dyn Error>> {
+        let mut lines = input.lines();
+        self.load_with(|prompt| {
+            eprint!("{}", prompt);
+            io::stderr().flush()?;
+            lines.next().transpose()
+        })
+    }
+
+    /// [`SignerSource::load`] with `ask` printing a prompt and reading the
+    /// answer, `None` at the end of input.
+    fn load_with(
+        &self,
+        mut ask: impl FnMut(&str) -> io::Result<Option<String>>,
+    ) -> Result<Keypair, Box<dyn Error>> {
+        match self {
+            Self::File(path) => {
+                register_secret(path.display().to_string());
+                fs::File::open(path)
+                    .map_err(Box::<dyn Error>::from)
+                    .and_then(|mut file| read_keypair(&mut file))
+                    .map_err(|e| format!("cannot read keypair file: {}", e).into())
+            }
+            Self::Prompt {
+                derivation_path,
+                legacy,
+            } => {
+                let phrase = ask("Seed phrase: ")?.ok_or("no seed phrase given")?;
+                register_secret(phrase.trim());
+                let passphrase = ask("Passphrase (empty for none): ")?.unwrap_or_default();
+                register_secret(passphrase.trim_end());
+                keypair_from_seed_phrase(&phrase, &passphrase, derivation_path.clone(), *legacy)
+            }
+            #[cfg(feature = "keyring")]
+            Self::Keyring { service, account } => crate::payer::load_from_keyring(service, account),
+            #[cfg(not(feature = "keyring"))]
+            Self::Keyring { .. } => {
+                Err("keyring:// signers need cu-bench built with the keyring feature".into())
+            }
+            Self::Usb { wallet, .. } => Err(format!(
+                "usb://{} needs a hardware wallet signer, which cu-bench does not support; \
+                 use a keypair file or a seed phrase (prompt://?key=...)",
+                wallet
+            )
+            .into()),
+        }
+    }
+}
+
+/// Loads the keypair of `source`, prompting when it asks for a seed phrase:
+/// without echo on a terminal, as the Solana CLI does, or from piped stdin.
+pub fn read_signer(source: &str) -> Result<Keypair, Box<dyn Error>> {
+    let signer = SignerSource::parse(source)?;
+    if io::stdin().is_terminal() {
+        signer.load_with(|prompt| rpassword::prompt_password(prompt).map(Some))
+    } else {
+        signer.load(io::stdin().lock())
+    }
+}
+
+/// The keypair the Solana CLI derives from a seed phrase: along
+/// `derivation_path`, the default `m/44'/501'` without one, or from the raw
+/// seed when `legacy`.
+pub fn keypair_from_seed_phrase(
+    phrase: &str,
+    passphrase: &str,
+    derivation_path: Option<DerivationPath>,
+    legacy: bool,
+) -> Result<Keypair, Box<dyn Error>> {
+    let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+    if phrase.is_empty() {
+        return Err("empty seed phrase".into());
+    }
+    let seed = generate_seed_from_seed_phrase_and_passphrase(&phrase, passphrase.trim_end());
+    if legacy && derivation_path.is_none() {
+        return keypair_from_seed(&seed[..32]);
+    }
+    keypair_from_seed_and_derivation_path(&seed, derivation_path)
+}
+
+/// The path in a `?key=ACCOUNT/CHANGE` or `?full-path=m/...` query, if any.
+fn parse_derivation_query(uri: &str) -> Result<Option<DerivationPath>, Box<dyn Error>> {
+    let Some((_, query)) = uri.split_once('?') else {
+        return Ok(None);
+    };
+    let mut derivation_path = None;
+    for pair in query.split('&') {
+        derivation_path = match pair.split_once('=') {
+            Some(("key", key)) => Some(DerivationPath::from_key_str(key)?),
+            Some(("full-path", path)) => Some(DerivationPath::from_absolute_path_str(path)?),
+            _ => return Err(format!("unknown signer query {:?}", pair).into()),
+        };
+    }
+    Ok(derivation_path)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{redact::redact, sdk::Signer};
+
+    #[test]
+    fn test_sources_derive_like_the_cli() {
+        assert_eq!(
+            SignerSource::parse("usb://ledger?key=0/1").unwrap(),
+            SignerSource::Usb {
+                wallet: "ledger".to_string(),
+                derivation_path: Some(DerivationPath::new_bip44(Some(0), Some(1))),
+            }
+        );
+        assert_eq!(
+            SignerSource::parse("file:id.json").unwrap(),
+            SignerSource::File(PathBuf::from("id.json"))
+        );
+        assert_eq!(
+            SignerSource::parse("keyring://cu-bench/payer").unwrap(),
+            SignerSource::Keyring {
+                service: "cu-bench".to_string(),
+                account: "payer".to_string(),
+            }
+        );
+        assert!(SignerSource::parse("keyring://cu-bench").is_err());
+        assert!(SignerSource::parse("prompt://?account=0").is_err());
+        assert!(SignerSource::parse("stdin://").is_err());
+        assert!(SignerSource::parse("usb://ledger")
+            .unwrap()
+            .load(io::empty())
+            .is_err());
+        let missing = std::env::temp_dir().join("cu-bench-missing-payer.json");
+        let e = SignerSource::File(missing.clone())
+            .load(io::empty())
+            .unwrap_err()
+            .to_string();
+        assert!(!e.contains("cu-bench-missing-payer"));
+        assert_eq!(redact(&format!("payer {}", missing.display())), "payer ***");
+
+        let phrase = "park remain person kitchen mule spell knee armed position rail grid ankle\n";
+        let load = |source: &str| {
+            SignerSource::parse(source)
+                .unwrap()
+                .load(phrase.as_bytes())
+                .unwrap()
+                .pubkey()
+        };
+        let default_path = load("prompt://");
+        assert_eq!(
+            load("prompt://?key=0/0"),
+            load("prompt:?full-path=m/44/501/0/0")
+        );
+        assert_ne!(load("prompt://?key=0/0"), default_path);
+        assert_ne!(load("prompt://?key=1/0"), load("prompt://?key=0/0"));
+        assert_ne!(load("ASK"), default_path);
+        // The passphrase line is part of the seed
+        assert_ne!(
+            SignerSource::parse("prompt://")
+                .unwrap()
+                .load(format!("{}secret\n", phrase).as_bytes())
+                .unwrap()
+                .pubkey(),
+            default_path
+        );
+    }
+
+    #[test]
+    fn test_seed_phrase_matches_solana_keygen() {
+        // What `solana-keygen pubkey` prints for `prompt://?key=0/0`,
+        // `prompt://` and `ASK` given this phrase and passphrase
+        let input = "park remain person kitchen mule spell knee armed position rail grid ankle\n\
+                     battery staple\n";
+        let load = |source: &str| {
+            SignerSource::parse(source)
+                .unwrap()
+                .load(input.as_bytes())
+                .unwrap()
+                .pubkey()
+                .to_string()
+        };
+        assert_eq!(
+            load("prompt://?key=0/0"),
+            "4sTzogrBKVwDkdbRHi9NK1Hetb3D9bZJZ9Zu5dVaJKJh"
+        );
+        assert_eq!(
+            load("prompt://"),
+            "6UYsryUuhgMmW2kpHJbBQcahNd8cTSowHsvRhTqwDrjM"
+        );
+        assert_eq!(load("ASK"), "GDCnkPqUbExR4tenL3xzqMd7J3VA5T86X4KWaFhNHkLV");
+        assert_eq!(redact("passphrase battery staple"), "passphrase ***");
+    }
+
+    #[test]
+    fn test_prompt_sources_are_not_redacted() {
+        // Both fail, the file once its path is registered
+        let missing = std::env::temp_dir().join("cu-bench-unread-payer.json");
+        assert!(read_signer(&missing.display().to_string()).is_err());
+        assert!(read_signer("usb://ledger?key=0/0").is_err());
+        assert_eq!(
+            redact(&format!("{} or usb://ledger?key=0/0", missing.display())),
+            "*** or usb://ledger?key=0/0"
+        );
+    }
+}
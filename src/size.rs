@@ -0,0 +1,170 @@
+//! On-chain size of deployed programs.
+
+use crate::{error::BenchError, sdk::RpcClient};
+use solana_program::{
+    bpf_loader, bpf_loader_deprecated,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    loader_v4::{self, LoaderV4State},
+    pubkey::Pubkey,
+};
+use std::{fmt, str::FromStr};
+
+/// The header loader-v4 keeps in front of the code in the program account
+/// itself, read field by field as it is a `repr(C)` struct rather than bincode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LoaderV4Header {
+    /// Slot of the last deployment, retraction or initialization.
+    pub slot: u64,
+    pub authority: Pubkey,
+    /// 0 retracted, 1 deployed, 2 finalized (no longer upgradeable).
+    pub status: u64,
+}
+
+impl LoaderV4Header {
+    pub const LEN: usize = LoaderV4State::program_data_offset();
+    pub const FINALIZED: u64 = 2;
+
+    pub fn parse(data: &[u8]) -> Result<Self, BenchError> {
+        let header = data.get(..Self::LEN).ok_or_else(|| {
+            BenchError::DeserializeFailed(
+                "loader-v4 program account is shorter than its header".to_string(),
+            )
+        })?;
+        let (slot, rest) = header.split_at(8);
+        let (authority, status) = rest.split_at(32);
+        Ok(Self {
+            slot: u64::from_le_bytes(slot.try_into().unwrap()),
+            authority: Pubkey::new_from_array(authority.try_into().unwrap()),
+            status: u64::from_le_bytes(status.try_into().unwrap()),
+        })
+    }
+}
+
+/// Size and cost of a deployed program.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ProgramSizeInfo {
+    /// Executable bytes, excluding loader metadata.
+    pub program_data_size: usize,
+    /// Size of the account holding the code: the programdata account for
+    /// upgradeable programs, the program account otherwise.
+    pub total_size: usize,
+    /// Upgradeable programs only.
+    pub programdata_address: Option<Pubkey>,
+    /// Slot of the last deployment, for upgradeable and loader-v4 programs.
+    pub deployment_slot: Option<u64>,
+    /// `None` for immutable programs.
+    pub upgrade_authority: Option<Pubkey>,
+    /// Held by the program account plus its programdata account, if any.
+    pub lamports: u64,
+    /// Rent-exempt minimum for an account of `total_size` bytes.
+    pub rent_exempt_minimum: u64,
+}
+
+impl ProgramSizeInfo {
+    /// Lamports held beyond the rent-exempt minimum of the code account.
+    pub fn excess_lamports(&self) -> u64 {
+        self.lamports.saturating_sub(self.rent_exempt_minimum)
+    }
+}
+
+impl fmt::Display for ProgramSizeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} bytes of program data in a {}-byte account, {} lamports held (rent-exempt minimum {})",
+            self.program_data_size, self.total_size, self.lamports, self.rent_exempt_minimum
+        )
+    }
+}
+
+/// Reads the size of `program_id` and what its accounts cost. Upgradeable
+/// programs keep their code in the programdata account, whether or not they
+/// still have an upgrade authority; loader-v4 programs keep it in the program
+/// account, after a header.
+pub fn get_program_size(
+    rpc_client: &RpcClient,
+    program_id: &str,
+) -> Result<ProgramSizeInfo, BenchError> {
+    let program_pubkey = Pubkey::from_str(program_id)?;
+    let program_account = rpc_client.get_account(&program_pubkey)?;
+    let owner = program_account.owner;
+
+    let mut info = ProgramSizeInfo {
+        total_size: program_account.data.len(),
+        lamports: program_account.lamports,
+        ..ProgramSizeInfo::default()
+    };
+    if owner == bpf_loader::id() || owner == bpf_loader_deprecated::id() {
+        info.program_data_size = program_account.data.len();
+    } else if owner == bpf_loader_upgradeable::id() {
+        let UpgradeableLoaderState::Program {
+            programdata_address,
+        } = bincode::deserialize(&program_account.data)?
+        else {
+            return Err(BenchError::NotAProgram {
+                address: program_pubkey,
+                reason: "not a program account",
+            });
+        };
+        let program_data_account = rpc_client.get_account(&programdata_address)?;
+        let UpgradeableLoaderState::ProgramData {
+            slot,
+            upgrade_authority_address,
+        } = bincode::deserialize(&program_data_account.data)?
+        else {
+            return Err(BenchError::NotAProgram {
+                address: program_pubkey,
+                reason: "invalid program data account",
+            });
+        };
+        let metadata_size = UpgradeableLoaderState::size_of_programdata_metadata();
+        info.program_data_size = program_data_account.data.len() - metadata_size;
+        info.total_size = program_data_account.data.len();
+        info.programdata_address = Some(programdata_address);
+        info.deployment_slot = Some(slot);
+        info.upgrade_authority = upgrade_authority_address;
+        info.lamports += program_data_account.lamports;
+    } else if owner == loader_v4::id() {
+        let header = LoaderV4Header::parse(&program_account.data)?;
+        info.program_data_size = program_account.data.len() - LoaderV4Header::LEN;
+        info.deployment_slot = Some(header.slot);
+        info.upgrade_authority =
+            (header.status != LoaderV4Header::FINALIZED).then_some(header.authority);
+    } else {
+        return Err(BenchError::UnsupportedLoader {
+            program_id: program_pubkey,
+            owner,
+        });
+    }
+    info.rent_exempt_minimum =
+        rpc_client.get_minimum_balance_for_rent_exemption(info.total_size)?;
+
+    Ok(info)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_loader_v4_header() {
+        let authority = Pubkey::new_unique();
+        let mut data = Vec::new();
+        data.extend(42u64.to_le_bytes());
+        data.extend(authority.to_bytes());
+        data.extend(LoaderV4Header::FINALIZED.to_le_bytes());
+        assert_eq!(data.len(), LoaderV4Header::LEN);
+        data.extend([0x7f, b'E', b'L', b'F']);
+
+        assert_eq!(
+            LoaderV4Header::parse(&data).unwrap(),
+            LoaderV4Header {
+                slot: 42,
+                authority,
+                status: LoaderV4Header::FINALIZED,
+            }
+        );
+        assert!(LoaderV4Header::parse(&data[..LoaderV4Header::LEN - 1]).is_err());
+    }
+}
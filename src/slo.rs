@@ -0,0 +1,270 @@
+//! Service level objectives on a run, for teams that budget CU: settings such
+//! as `p99_cu < 5000` or `landing_rate > 99%`, checked against every run and
+//! reported as passed or failed.
+//!
+//! An SLO is `metric comparison threshold`, the comparison one of `<`, `<=`,
+//! `>` and `>=`. CU metrics are `min_cu`, `median_cu`, `mean_cu`, `max_cu` and
+//! `pN_cu` for any percentile `N`, over the steady-state samples;
+//! `landing_rate` is the share of attempted transactions that landed, given
+//! as a fraction or with a `%`.
+
+use crate::{
+    run::Run,
+    sample::TagFilter,
+    stats::{self, CuStats},
+};
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SloMetric {
+    MinCu,
+    MedianCu,
+    MeanCu,
+    MaxCu,
+    /// The given percentile of CU.
+    PercentileCu(f64),
+    LandingRate,
+}
+
+impl SloMetric {
+    /// The metric's value over `run`, `None` without samples to compute it.
+    pub fn observe(&self, run: &Run) -> Option<f64> {
+        let compute_units = TagFilter::steady_state().compute_units(run);
+        let summary = CuStats::summarize(&compute_units);
+        let observed = match self {
+            Self::LandingRate => return run.landing_rate(),
+            _ if summary.count == 0 => return None,
+            Self::MinCu => summary.min as f64,
+            Self::MedianCu => summary.median,
+            Self::MeanCu => summary.mean,
+            Self::MaxCu => summary.max as f64,
+            Self::PercentileCu(pct) => {
+                let mut sorted: Vec<f64> = compute_units.iter().map(|&cu| cu as f64).collect();
+                sorted.sort_by(f64::total_cmp);
+                stats::percentile(&sorted, *pct)
+            }
+        };
+        Some(observed)
+    }
+}
+
+impl FromStr for SloMetric {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let metric = match name {
+            "min_cu" => Self::MinCu,
+            "median_cu" => Self::MedianCu,
+            "mean_cu" => Self::MeanCu,
+            "max_cu" => Self::MaxCu,
+            "landing_rate" => Self::LandingRate,
+            _ => {
+                let pct = name
+                    .strip_prefix('p')
+                    .and_then(|rest| rest.strip_suffix("_cu"))
+                    .and_then(|pct| pct.parse::<f64>().ok())
+                    .filter(|pct| (0.0..=100.0).contains(pct))
+                    .ok_or_else(|| {
+                        format!(
+                            "unknown SLO metric {:?}; expected min_cu, median_cu, mean_cu, \
+                             max_cu, pN_cu or landing_rate",
+                            name
+                        )
+                    })?;
+                Self::PercentileCu(pct)
+            }
+        };
+        Ok(metric)
+    }
+}
+
+impl fmt::Display for SloMetric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MinCu => write!(f, "min_cu"),
+            Self::MedianCu => write!(f, "median_cu"),
+            Self::MeanCu => write!(f, "mean_cu"),
+            Self::MaxCu => write!(f, "max_cu"),
+            Self::PercentileCu(pct) => write!(f, "p{}_cu", pct),
+            Self::LandingRate => write!(f, "landing_rate"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Below,
+    AtMost,
+    Above,
+    AtLeast,
+}
+
+impl Comparison {
+    pub fn holds(&self, observed: f64, threshold: f64) -> bool {
+        match self {
+            Self::Below => observed < threshold,
+            Self::AtMost => observed <= threshold,
+            Self::Above => observed > threshold,
+            Self::AtLeast => observed >= threshold,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            Self::Below => "<",
+            Self::AtMost => "<=",
+            Self::Above => ">",
+            Self::AtLeast => ">=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slo {
+    pub metric: SloMetric,
+    pub comparison: Comparison,
+    /// A fraction for `landing_rate`, CU otherwise.
+    pub threshold: f64,
+}
+
+impl Slo {
+    pub fn evaluate(&self, run: &Run) -> SloOutcome {
+        let observed = self.metric.observe(run);
+        SloOutcome {
+            slo: *self,
+            observed,
+            passed: observed.is_some_and(|value| self.comparison.holds(value, self.threshold)),
+        }
+    }
+}
+
+impl FromStr for Slo {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (index, symbol) = ["<=", ">=", "<", ">"]
+            .iter()
+            .find_map(|symbol| spec.find(symbol).map(|index| (index, *symbol)))
+            .ok_or_else(|| format!("expected metric < threshold, got {:?}", spec))?;
+        let comparison = match symbol {
+            "<" => Comparison::Below,
+            "<=" => Comparison::AtMost,
+            ">" => Comparison::Above,
+            _ => Comparison::AtLeast,
+        };
+        let metric: SloMetric = spec[..index].trim().parse()?;
+        let threshold = spec[index + symbol.len()..].trim();
+        let (number, scale) = match threshold.strip_suffix('%') {
+            Some(number) if metric == SloMetric::LandingRate => (number, 100.0),
+            Some(_) => return Err(format!("only landing_rate takes a %, in {:?}", spec)),
+            None => (threshold, 1.0),
+        };
+        let threshold = number
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| format!("invalid threshold {:?} in {:?}", threshold, spec))?
+            / scale;
+        Ok(Self {
+            metric,
+            comparison,
+            threshold,
+        })
+    }
+}
+
+impl fmt::Display for Slo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.metric {
+            SloMetric::LandingRate => write!(
+                f,
+                "{} {} {}%",
+                self.metric,
+                self.comparison,
+                self.threshold * 100.0
+            ),
+            _ => write!(f, "{} {} {}", self.metric, self.comparison, self.threshold),
+        }
+    }
+}
+
+/// An [`Slo`] checked against a run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SloOutcome {
+    pub slo: Slo,
+    /// `None` when the run had no samples to compute the metric from, which
+    /// fails the SLO.
+    pub observed: Option<f64>,
+    pub passed: bool,
+}
+
+impl fmt::Display for SloOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let verdict = if self.passed { "PASS" } else { "FAIL" };
+        write!(f, "{} {}: ", verdict, self.slo)?;
+        match (self.observed, self.slo.metric) {
+            (None, _) => write!(f, "no samples"),
+            (Some(rate), SloMetric::LandingRate) => write!(f, "{:.2}%", rate * 100.0),
+            (Some(value), _) => write!(f, "{:.0}", value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{sample::Sample, sdk::Signature};
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_parse_and_evaluate() {
+        let slo: Slo = "p99_cu < 5000".parse().unwrap();
+        assert_eq!(slo.metric, SloMetric::PercentileCu(99.0));
+        assert_eq!(slo.comparison, Comparison::Below);
+        assert_eq!(slo.threshold, 5_000.0);
+        let landing: Slo = "landing_rate>=99.5%".parse().unwrap();
+        assert_eq!(landing.comparison, Comparison::AtLeast);
+        assert_eq!(landing.threshold, 0.995);
+        assert_eq!(landing.to_string(), "landing_rate >= 99.5%");
+        assert!("p99_cu < 5%".parse::<Slo>().is_err());
+        assert!("p101_cu < 5000".parse::<Slo>().is_err());
+        assert!("cu = 5000".parse::<Slo>().is_err());
+
+        let mut run = Run::new(Pubkey::new_unique());
+        for index in 0..100 {
+            run.push(Sample::new(
+                index,
+                Signature::default(),
+                1,
+                Some(1_000 + index * 50),
+            ));
+        }
+        run.attempted = 101;
+        let outcome = slo.evaluate(&run);
+        assert!(!outcome.passed);
+        assert_eq!(outcome.to_string(), "FAIL p99_cu < 5000: 5900");
+        assert!(
+            "max_cu <= 5950"
+                .parse::<Slo>()
+                .unwrap()
+                .evaluate(&run)
+                .passed
+        );
+        let outcome = landing.evaluate(&run);
+        assert!(!outcome.passed);
+        assert_eq!(outcome.to_string(), "FAIL landing_rate >= 99.5%: 99.01%");
+        assert!(
+            "landing_rate > 99%"
+                .parse::<Slo>()
+                .unwrap()
+                .evaluate(&run)
+                .passed
+        );
+
+        let empty = slo.evaluate(&Run::new(Pubkey::new_unique()));
+        assert_eq!(empty.observed, None);
+        assert!(!empty.passed);
+    }
+}
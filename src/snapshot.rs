@@ -0,0 +1,116 @@
+//! Fetches accounts from a live cluster so mainnet programs can be profiled
+//! locally against real state. In-process runs load the
+//! [`BenchConfig::snapshot_accounts`](crate::client::BenchConfig::snapshot_accounts)
+//! this way.
+
+use crate::sdk::{Account, RpcClient};
+use solana_program::{bpf_loader_upgradeable, pubkey::Pubkey};
+use std::error::Error;
+
+/// `getMultipleAccounts` accepts at most this many keys per request.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Debug, Clone, Default)]
+pub struct AccountSnapshot {
+    accounts: Vec<(Pubkey, Account)>,
+}
+
+impl AccountSnapshot {
+    /// Fetches `addresses`, adding the programdata account of every upgradeable
+    /// program among them. Fails if any requested account does not exist.
+    pub fn fetch(rpc_client: &RpcClient, addresses: &[Pubkey]) -> Result<Self, Box<dyn Error>> {
+        let mut snapshot = Self::default();
+        snapshot.fetch_batch(rpc_client, addresses)?;
+
+        let programdata: Vec<Pubkey> = snapshot
+            .accounts
+            .iter()
+            .filter(|(_, account)| account.owner == bpf_loader_upgradeable::id())
+            .filter(|(_, account)| account.executable)
+            .map(|(address, _)| programdata_address(address))
+            .filter(|address| !addresses.contains(address))
+            .collect();
+        snapshot.fetch_batch(rpc_client, &programdata)?;
+
+        Ok(snapshot)
+    }
+
+    fn fetch_batch(
+        &mut self,
+        rpc_client: &RpcClient,
+        addresses: &[Pubkey],
+    ) -> Result<(), Box<dyn Error>> {
+        for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            for (address, account) in chunk.iter().zip(rpc_client.get_multiple_accounts(chunk)?) {
+                let account = account.ok_or_else(|| format!("Account {} not found", address))?;
+                self.accounts.push((*address, account));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn accounts(&self) -> &[(Pubkey, Account)] {
+        &self.accounts
+    }
+
+    /// Adds every snapshotted account to a `ProgramTest` environment.
+    #[cfg(feature = "program-test")]
+    pub fn load_into(&self, program_test: &mut solana_program_test::ProgramTest) {
+        for (address, account) in &self.accounts {
+            program_test.add_account(*address, account.clone());
+        }
+    }
+}
+
+fn programdata_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id()).0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_fetch_and_load() {
+        let (address, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let fixture = serde_json::json!({
+            "context": { "slot": 1 },
+            "value": [{
+                "lamports": 1_000_000,
+                "data": ["AQID", "base64"],
+                "owner": owner.to_string(),
+                "executable": false,
+                "rentEpoch": 0,
+                "space": 3,
+            }],
+        });
+        let rpc_client = RpcClient::new_mock_with_mocks(
+            "succeeds",
+            HashMap::from([(RpcRequest::GetMultipleAccounts, fixture)]),
+        );
+        let snapshot = AccountSnapshot::fetch(&rpc_client, &[address]).unwrap();
+        let [(fetched, account)] = snapshot.accounts() else {
+            panic!("expected one account, got {:?}", snapshot.accounts());
+        };
+        assert_eq!(*fetched, address);
+        assert_eq!(account.owner, owner);
+        assert_eq!(account.data, [1, 2, 3]);
+
+        #[cfg(feature = "program-test")]
+        {
+            let mut program_test = solana_program_test::ProgramTest::default();
+            snapshot.load_into(&mut program_test);
+            let loaded = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    let mut context = program_test.start_with_context().await;
+                    context.banks_client.get_account(address).await.unwrap()
+                });
+            assert_eq!(loaded.as_ref(), Some(account));
+        }
+    }
+}
@@ -0,0 +1,383 @@
+//! Statistics over collected CU samples.
+
+use std::fmt;
+
+/// Ordinary least squares fit of `y = intercept + slope * x`.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct LinearFit {
+    pub slope: f64,
+    pub intercept: f64,
+    pub slope_stderr: f64,
+    /// 95% confidence interval for the slope.
+    pub slope_ci: (f64, f64),
+    pub r_squared: f64,
+    pub n: usize,
+}
+
+impl LinearFit {
+    /// Whether the 95% confidence interval for the slope excludes zero.
+    pub fn slope_is_significant(&self) -> bool {
+        self.slope_ci.0 > 0.0 || self.slope_ci.1 < 0.0
+    }
+}
+
+/// Fits `y = intercept + slope * x`. Needs at least three points and some spread in `x`.
+pub fn linear_fit(xs: &[f64], ys: &[f64]) -> Option<LinearFit> {
+    let n = xs.len();
+    if n != ys.len() || n < 3 {
+        return None;
+    }
+
+    let mean_x = xs.iter().sum::<f64>() / n as f64;
+    let mean_y = ys.iter().sum::<f64>() / n as f64;
+    let sxx: f64 = xs.iter().map(|x| (x - mean_x).powi(2)).sum();
+    let sxy: f64 = xs
+        .iter()
+        .zip(ys)
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let syy: f64 = ys.iter().map(|y| (y - mean_y).powi(2)).sum();
+    if sxx == 0.0 {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+    let residual_ss: f64 = xs
+        .iter()
+        .zip(ys)
+        .map(|(x, y)| (y - intercept - slope * x).powi(2))
+        .sum();
+    let df = n - 2;
+    let slope_stderr = (residual_ss / df as f64 / sxx).sqrt();
+    let margin = t_critical_95(df) * slope_stderr;
+    let r_squared = if syy == 0.0 {
+        1.0
+    } else {
+        1.0 - residual_ss / syy
+    };
+
+    Some(LinearFit {
+        slope,
+        intercept,
+        slope_stderr,
+        slope_ci: (slope - margin, slope + margin),
+        r_squared,
+        n,
+    })
+}
+
+/// Fits `y = intercept + slope * ln(x)`; all `x` must be positive.
+pub fn log_fit(xs: &[f64], ys: &[f64]) -> Option<LinearFit> {
+    if xs.iter().any(|&x| x <= 0.0) {
+        return None;
+    }
+    let ln_xs: Vec<f64> = xs.iter().map(|x| x.ln()).collect();
+    linear_fit(&ln_xs, ys)
+}
+
+/// Fits CU against program size, with the slope expressed in CU per KB.
+pub fn cu_per_kb_fit(program_sizes: &[usize], cu: &[u64]) -> Option<LinearFit> {
+    let kb: Vec<f64> = program_sizes
+        .iter()
+        .map(|&size| size as f64 / 1024.0)
+        .collect();
+    let cu: Vec<f64> = cu.iter().map(|&cu| cu as f64).collect();
+    linear_fit(&kb, &cu)
+}
+
+/// Descriptive statistics of a set of CU measurements.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[non_exhaustive]
+pub struct CuStats {
+    pub count: usize,
+    pub min: u64,
+    pub max: u64,
+    pub mean: f64,
+    pub median: f64,
+    pub p95: f64,
+    /// Sample standard deviation.
+    pub stddev: f64,
+    /// Values outside Tukey's fences (1.5 × IQR), as tagged by
+    /// [`crate::sample::tag_outliers`].
+    pub outliers: usize,
+}
+
+impl CuStats {
+    /// Summarizes `cu`; every field is zero when it is empty.
+    pub fn summarize(cu: &[u64]) -> Self {
+        let (Some(&min), Some(&max)) = (cu.iter().min(), cu.iter().max()) else {
+            return Self::default();
+        };
+        let mut sorted: Vec<f64> = cu.iter().map(|&cu| cu as f64).collect();
+        sorted.sort_by(f64::total_cmp);
+        let (low, high) = tukey_fences(&sorted);
+
+        Self {
+            count: cu.len(),
+            min,
+            max,
+            mean: mean(&sorted),
+            median: percentile(&sorted, 50.0),
+            p95: percentile(&sorted, 95.0),
+            stddev: variance(&sorted).sqrt(),
+            outliers: sorted.iter().filter(|&&cu| cu < low || cu > high).count(),
+        }
+    }
+}
+
+impl fmt::Display for CuStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "n={} min {} / median {:.0} / mean {:.1} / p95 {:.0} / max {}, stddev {:.1}, {} outliers",
+            self.count, self.min, self.median, self.mean, self.p95, self.max, self.stddev, self.outliers
+        )
+    }
+}
+
+/// Bootstrap comparison of two runs' CU distributions, expressed as run `b` minus run `a`.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct BootstrapComparison {
+    pub median_a: f64,
+    pub median_b: f64,
+    /// Difference of medians, `b - a`.
+    pub diff: f64,
+    /// 95% percentile bootstrap interval for `diff`.
+    pub ci: (f64, f64),
+    /// Cohen's d using the pooled standard deviation.
+    pub effect_size: f64,
+}
+
+impl BootstrapComparison {
+    pub fn is_significant(&self) -> bool {
+        self.ci.0 > 0.0 || self.ci.1 < 0.0
+    }
+}
+
+impl fmt::Display for BootstrapComparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.is_significant() {
+            return write!(
+                f,
+                "no significant difference ({:+.1} CU, 95% CI [{:.1}, {:.1}])",
+                self.diff, self.ci.0, self.ci.1
+            );
+        }
+        let direction = if self.diff > 0.0 { "higher" } else { "lower" };
+        write!(
+            f,
+            "{:.1} CU {}, 95% CI [{:.1}, {:.1}], d = {:.2}",
+            self.diff.abs(),
+            direction,
+            self.ci.0,
+            self.ci.1,
+            self.effect_size
+        )
+    }
+}
+
+/// Compares the medians of `a` and `b` by resampling each with replacement
+/// `resamples` times. The same `seed` always yields the same interval.
+pub fn bootstrap_compare(
+    a: &[u64],
+    b: &[u64],
+    resamples: usize,
+    seed: u64,
+) -> Option<BootstrapComparison> {
+    if a.is_empty() || b.is_empty() || resamples == 0 {
+        return None;
+    }
+    let a: Vec<f64> = a.iter().map(|&cu| cu as f64).collect();
+    let b: Vec<f64> = b.iter().map(|&cu| cu as f64).collect();
+
+    let mut rng = SplitMix64(seed);
+    let mut scratch = Vec::with_capacity(a.len().max(b.len()));
+    let mut diffs: Vec<f64> = (0..resamples)
+        .map(|_| {
+            let median_a = resampled_median(&a, &mut rng, &mut scratch);
+            let median_b = resampled_median(&b, &mut rng, &mut scratch);
+            median_b - median_a
+        })
+        .collect();
+    diffs.sort_by(f64::total_cmp);
+
+    let median_a = median(&mut a.clone());
+    let median_b = median(&mut b.clone());
+    let diff = median_b - median_a;
+
+    let pooled_sd = ((variance(&a) * (a.len() as f64 - 1.0)
+        + variance(&b) * (b.len() as f64 - 1.0))
+        / (a.len() + b.len()).saturating_sub(2).max(1) as f64)
+        .sqrt();
+    let mean_diff = mean(&b) - mean(&a);
+    let effect_size = if pooled_sd > 0.0 {
+        mean_diff / pooled_sd
+    } else {
+        0.0
+    };
+
+    Some(BootstrapComparison {
+        median_a,
+        median_b,
+        diff,
+        ci: (percentile(&diffs, 2.5), percentile(&diffs, 97.5)),
+        effect_size,
+    })
+}
+
+fn resampled_median(values: &[f64], rng: &mut SplitMix64, scratch: &mut Vec<f64>) -> f64 {
+    scratch.clear();
+    scratch.extend((0..values.len()).map(|_| values[rng.below(values.len())]));
+    median(scratch)
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Sample variance; zero for fewer than two values.
+fn variance(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean = mean(values);
+    values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    percentile(values, 50.0)
+}
+
+/// `(low, high)` Tukey fences at 1.5 × IQR of already sorted values. Fewer than
+/// four values give infinite fences, as quartiles are meaningless there.
+pub(crate) fn tukey_fences(sorted: &[f64]) -> (f64, f64) {
+    if sorted.len() < 4 {
+        return (f64::NEG_INFINITY, f64::INFINITY);
+    }
+    let (q1, q3) = (percentile(sorted, 25.0), percentile(sorted, 75.0));
+    (q1 - 1.5 * (q3 - q1), q3 + 1.5 * (q3 - q1))
+}
+
+/// Linearly interpolated percentile of already sorted, non-empty `values`.
+pub(crate) fn percentile(sorted: &[f64], pct: f64) -> f64 {
+    let rank = pct / 100.0 * (sorted.len() - 1) as f64;
+    let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64)
+}
+
+/// Small deterministic generator so bootstrap results are reproducible from a seed.
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl SplitMix64 {
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Uniform in `[0, 1)`.
+    pub(crate) fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Two-sided 95% critical value of Student's t distribution.
+fn t_critical_95(df: usize) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+    match df {
+        0 => f64::INFINITY,
+        1..=30 => TABLE[df - 1],
+        _ => {
+            // Cornish-Fisher expansion around the normal quantile
+            let z: f64 = 1.959_964;
+            let df = df as f64;
+            z + (z.powi(3) + z) / (4.0 * df)
+                + (5.0 * z.powi(5) + 16.0 * z.powi(3) + 3.0 * z) / (96.0 * df * df)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_linear_fit_recovers_exact_line() {
+        let xs: Vec<f64> = (0..10).map(f64::from).collect();
+        let ys: Vec<f64> = xs.iter().map(|x| 150.0 + 2.5 * x).collect();
+        let fit = linear_fit(&xs, &ys).unwrap();
+
+        assert!((fit.slope - 2.5).abs() < 1e-9);
+        assert!((fit.intercept - 150.0).abs() < 1e-9);
+        assert!((fit.r_squared - 1.0).abs() < 1e-9);
+        assert!(fit.slope_is_significant());
+    }
+
+    #[test]
+    fn test_cu_per_kb_fit_flat_is_not_significant() {
+        let sizes = [18_040, 100_000, 500_000, 1_000_000, 18_040, 100_000];
+        let cu = [1_412, 1_410, 1_411, 1_412, 1_410, 1_411];
+        let fit = cu_per_kb_fit(&sizes, &cu).unwrap();
+
+        assert!(!fit.slope_is_significant());
+        assert!(linear_fit(&[1.0, 1.0, 1.0], &[1.0, 2.0, 3.0]).is_none());
+    }
+
+    #[test]
+    fn test_bootstrap_compare() {
+        let a: Vec<u64> = (0..200).map(|i| 1_400 + i % 7).collect();
+        let shifted: Vec<u64> = a.iter().map(|cu| cu + 25).collect();
+
+        let same = bootstrap_compare(&a, &a, 500, 7).unwrap();
+        assert!(!same.is_significant());
+        assert!(same.to_string().starts_with("no significant difference"));
+
+        let higher = bootstrap_compare(&a, &shifted, 500, 7).unwrap();
+        assert!(higher.is_significant());
+        assert_eq!(higher.diff, 25.0);
+        assert!(higher.to_string().starts_with("25.0 CU higher"));
+        assert_eq!(higher, bootstrap_compare(&a, &shifted, 500, 7).unwrap());
+    }
+
+    #[test]
+    fn test_cu_stats_summary() {
+        let mut cu: Vec<u64> = (0..19).map(|i| 1_400 + i % 3).collect();
+        cu.push(9_000);
+        let stats = CuStats::summarize(&cu);
+
+        assert_eq!(stats.count, 20);
+        assert_eq!((stats.min, stats.max), (1_400, 9_000));
+        assert_eq!(stats.median, 1_401.0);
+        assert!((stats.mean - 1_780.9).abs() < 1e-9);
+        assert!(stats.p95 > 1_402.0);
+        assert!(stats.stddev > 1_000.0);
+        assert_eq!(stats.outliers, 1);
+
+        let single = CuStats::summarize(&[1_234]);
+        assert_eq!(
+            (single.median, single.p95, single.stddev),
+            (1_234.0, 1_234.0, 0.0)
+        );
+        assert_eq!(CuStats::summarize(&[]), CuStats::default());
+    }
+
+    #[test]
+    fn test_t_critical_95() {
+        assert_eq!(t_critical_95(1), 12.706);
+        assert!((t_critical_95(120) - 1.980).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,134 @@
+//! Size distribution of well-known mainnet programs, giving context for where a
+//! program (and this crate's sweep points) fall in the ecosystem.
+
+use crate::{sdk::RpcClient, size::get_program_size};
+use std::fmt;
+
+/// Cluster the survey targets are fetched from unless `SURVEY_RPC_URL` names
+/// another.
+pub const SURVEY_RPC_URL: &str = "https://api.mainnet-beta.solana.com";
+
+/// Default survey targets as `(name, program id)`.
+pub const WELL_KNOWN_PROGRAMS: &[(&str, &str)] = &[
+    ("SPL Token", "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
+    (
+        "SPL Token-2022",
+        "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb",
+    ),
+    (
+        "SPL Associated Token Account",
+        "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL",
+    ),
+    ("SPL Memo", "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"),
+    (
+        "Metaplex Token Metadata",
+        "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s",
+    ),
+    ("Jupiter v6", "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4"),
+    (
+        "Orca Whirlpools",
+        "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc",
+    ),
+    (
+        "Raydium AMM v4",
+        "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8",
+    ),
+    ("Marinade", "MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD"),
+    ("Drift v2", "dRiftyHA39MWEi3m9aunc5MzRF1JYuBsbn6VPcn33UH"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SurveyEntry {
+    pub name: String,
+    pub program_id: String,
+    pub program_size: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SizeSurvey {
+    /// Surveyed programs, smallest first.
+    pub entries: Vec<SurveyEntry>,
+    /// `(name, error)` for programs whose size could not be fetched.
+    pub failures: Vec<(String, String)>,
+}
+
+impl SizeSurvey {
+    /// Fetches the program size of every `(name, program id)` target.
+    pub fn fetch(rpc_client: &RpcClient, targets: &[(&str, &str)]) -> Self {
+        let mut survey = Self::default();
+        for (name, program_id) in targets {
+            match get_program_size(rpc_client, program_id) {
+                Ok(info) => survey.entries.push(SurveyEntry {
+                    name: name.to_string(),
+                    program_id: program_id.to_string(),
+                    program_size: info.program_data_size,
+                }),
+                Err(e) => survey.failures.push((name.to_string(), e.to_string())),
+            }
+        }
+        survey.entries.sort_by_key(|entry| entry.program_size);
+        survey
+    }
+
+    /// Share of surveyed programs no larger than `program_size`, in percent.
+    pub fn percentile_rank(&self, program_size: usize) -> Option<f64> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let at_or_below = self
+            .entries
+            .iter()
+            .filter(|entry| entry.program_size <= program_size)
+            .count();
+        Some(at_or_below as f64 * 100.0 / self.entries.len() as f64)
+    }
+}
+
+impl fmt::Display for SizeSurvey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "{:>10} bytes  {} ({})",
+                entry.program_size, entry.name, entry.program_id
+            )?;
+        }
+        if let (Some(smallest), Some(largest)) = (self.entries.first(), self.entries.last()) {
+            writeln!(
+                f,
+                "{} programs, {} to {} bytes, median {} bytes",
+                self.entries.len(),
+                smallest.program_size,
+                largest.program_size,
+                self.entries[self.entries.len() / 2].program_size
+            )?;
+        }
+        for (name, error) in &self.failures {
+            writeln!(f, "failed: {}: {}", name, error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_percentile_rank() {
+        let mut survey = SizeSurvey::default();
+        assert_eq!(survey.percentile_rank(1), None);
+
+        for (index, program_size) in [50_000, 200_000, 400_000, 1_000_000].iter().enumerate() {
+            survey.entries.push(SurveyEntry {
+                name: format!("program {}", index),
+                program_id: String::new(),
+                program_size: *program_size,
+            });
+        }
+        assert_eq!(survey.percentile_rank(18_040), Some(0.0));
+        assert_eq!(survey.percentile_rank(200_000), Some(50.0));
+        assert_eq!(survey.percentile_rank(2_000_000), Some(100.0));
+    }
+}
@@ -0,0 +1,106 @@
+//! Seeded pseudo-random data, so hashing and validation workloads run on
+//! realistic non-zero bytes while staying reproducible. The same seed always
+//! yields the same bytes and accounts.
+
+use crate::{sdk::Account, stats::SplitMix64};
+use solana_program::{pubkey::Pubkey, rent::Rent};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyntheticData {
+    seed: u64,
+    entropy_bits: u8,
+}
+
+impl SyntheticData {
+    /// Bytes with `entropy_bits` bits of entropy each, from 0 (all zero) to 8
+    /// (uniformly random); larger values are treated as 8.
+    pub fn new(seed: u64, entropy_bits: u8) -> Self {
+        Self {
+            seed,
+            entropy_bits: entropy_bits.min(8),
+        }
+    }
+
+    /// `len` bytes of stream `stream`; streams of one seed are independent.
+    pub fn bytes(&self, stream: u64, len: usize) -> Vec<u8> {
+        let mask = (0xffu16 >> (8 - self.entropy_bits)) as u8;
+        let mut rng = SplitMix64(self.seed ^ stream.wrapping_mul(0x2545_f491_4f6c_dd1d));
+        let mut bytes = Vec::with_capacity(len + 8);
+        while bytes.len() < len {
+            bytes.extend(rng.next_u64().to_le_bytes().map(|byte| byte & mask));
+        }
+        bytes.truncate(len);
+        bytes
+    }
+
+    /// `count` rent-exempt accounts owned by `owner`, each holding `len` bytes
+    /// of its own stream at an address derived from the seed.
+    pub fn accounts(&self, count: u64, len: usize, owner: &Pubkey) -> Vec<(Pubkey, Account)> {
+        let lamports = Rent::default().minimum_balance(len);
+        (0..count)
+            .map(|index| {
+                let mut address = [0; 32];
+                address
+                    .copy_from_slice(&SyntheticData::new(self.seed, 8).bytes(u64::MAX - index, 32));
+                let account = Account {
+                    lamports,
+                    data: self.bytes(index, len),
+                    owner: *owner,
+                    executable: false,
+                    rent_epoch: 0,
+                };
+                (Pubkey::new_from_array(address), account)
+            })
+            .collect()
+    }
+
+    /// Adds [`SyntheticData::accounts`] to a `ProgramTest` environment and
+    /// returns their addresses.
+    #[cfg(feature = "program-test")]
+    pub fn load_into(
+        &self,
+        program_test: &mut solana_program_test::ProgramTest,
+        count: u64,
+        len: usize,
+        owner: &Pubkey,
+    ) -> Vec<Pubkey> {
+        self.accounts(count, len, owner)
+            .into_iter()
+            .map(|(address, account)| {
+                program_test.add_account(address, account);
+                address
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_seeded_entropy() {
+        let data = SyntheticData::new(42, 8);
+        assert_eq!(data.bytes(0, 100), data.bytes(0, 100));
+        assert_ne!(data.bytes(0, 100), data.bytes(1, 100));
+        assert_ne!(data.bytes(0, 100), SyntheticData::new(43, 8).bytes(0, 100));
+        assert_eq!(data.bytes(0, 13).len(), 13);
+
+        assert!(SyntheticData::new(42, 0)
+            .bytes(0, 64)
+            .iter()
+            .all(|b| *b == 0));
+        let nibbles: BTreeSet<u8> = SyntheticData::new(42, 4)
+            .bytes(0, 4_096)
+            .into_iter()
+            .collect();
+        assert_eq!(nibbles, (0..16).collect());
+
+        let owner = Pubkey::new_unique();
+        let accounts = data.accounts(3, 10, &owner);
+        assert_eq!(accounts, data.accounts(3, 10, &owner));
+        assert_eq!(accounts[2].1.data, data.bytes(2, 10));
+        assert_ne!(accounts[0].0, accounts[1].0);
+    }
+}
@@ -0,0 +1,56 @@
+//! Tracing setup with an optional OTLP/HTTP exporter, so the harness's run,
+//! phase and sample spans can be inspected in Jaeger or Tempo.
+
+use crate::redact::RedactingWriter;
+use opentelemetry::{trace::TracerProvider as _, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{trace::TracerProvider, Resource};
+use std::{error::Error, io};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+/// Flushes exported spans when dropped; keep it alive for the whole run.
+#[derive(Debug)]
+pub struct TelemetryGuard {
+    provider: Option<TracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            let _ = provider.shutdown();
+        }
+    }
+}
+
+/// Installs a formatting subscriber, logging to stderr through the
+/// redactor, and, when `otlp_endpoint` is set (e.g.
+/// `http://localhost:4318/v1/traces`), also exports every span there.
+pub fn init_tracing(otlp_endpoint: Option<&str>) -> Result<TelemetryGuard, Box<dyn Error>> {
+    let Some(endpoint) = otlp_endpoint else {
+        tracing_subscriber::fmt()
+            .with_writer(redacted_stderr)
+            .try_init()?;
+        return Ok(TelemetryGuard { provider: None });
+    };
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()?;
+    let provider = TracerProvider::builder()
+        .with_simple_exporter(exporter)
+        .with_resource(Resource::new([KeyValue::new("service.name", "cu-bench")]))
+        .build();
+
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_writer(redacted_stderr))
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("cu-bench")))
+        .try_init()?;
+    Ok(TelemetryGuard {
+        provider: Some(provider),
+    })
+}
+
+fn redacted_stderr() -> RedactingWriter<io::Stderr> {
+    RedactingWriter::new(io::stderr())
+}
@@ -0,0 +1,62 @@
+//! Helpers for inspecting confirmed transaction metadata in tests, so callers don't
+//! have to unpack `OptionSerializer` fields by hand, and for draining workloads
+//! without a validator.
+
+use crate::{
+    compat,
+    sdk::{Keypair, RpcClient},
+    workload::WorkloadContext,
+};
+use solana_program::pubkey::Pubkey;
+use solana_transaction_status::UiTransactionStatusMeta;
+
+/// Returns the compute units consumed by the transaction, if the node reported them.
+pub fn extract_cu(meta: &UiTransactionStatusMeta) -> Option<u64> {
+    compat::compute_units_consumed(meta)
+}
+
+/// Returns the program log lines, or an empty slice if the node omitted them.
+pub fn extract_logs(meta: &UiTransactionStatusMeta) -> &[String] {
+    compat::log_messages(meta)
+}
+
+/// Panics unless one of the transaction's log lines contains `needle`.
+#[track_caller]
+pub fn assert_log_contains(meta: &UiTransactionStatusMeta, needle: &str) {
+    let logs = extract_logs(meta);
+    assert!(
+        logs.iter().any(|line| line.contains(needle)),
+        "no log line contains {:?}, logs: {:#?}",
+        needle,
+        logs
+    );
+}
+
+/// Owns what a [`WorkloadContext`] borrows: a client for a local validator
+/// that workloads building transactions offline never call, a fresh payer and
+/// a fresh program ID.
+pub struct OfflineContext {
+    rpc_client: RpcClient,
+    payer: Keypair,
+    program_id: Pubkey,
+}
+
+impl Default for OfflineContext {
+    fn default() -> Self {
+        Self {
+            rpc_client: RpcClient::new("http://127.0.0.1:8899".to_string()),
+            payer: Keypair::new(),
+            program_id: Pubkey::new_unique(),
+        }
+    }
+}
+
+impl OfflineContext {
+    pub fn ctx(&self) -> WorkloadContext<'_> {
+        WorkloadContext {
+            rpc_client: &self.rpc_client,
+            payer: &self.payer,
+            program_id: self.program_id,
+        }
+    }
+}
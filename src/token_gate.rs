@@ -0,0 +1,199 @@
+//! Token gating: with the `token-gate` feature the program accepts
+//! [`BenchInstruction::TokenGated`] only from a holder of at least
+//! `min_amount` of an SPL token, read straight from the token account data as
+//! gated programs do, so the suite prices that access check. Builds without
+//! the feature reject the instruction.
+//!
+//! [`BenchInstruction::TokenGated`] takes the accounts
+//! `[holder (signer), holder's token account]` and names the mint in its
+//! data, as the program keeps no configuration; a gate reading its mint from
+//! state pays about the same. A holder short of tokens fails with
+//! [`GATE_DENIED`]. The SPL Token instructions the harness needs to set up a
+//! mint and a funded token account are encoded by hand, like
+//! [`crate::guard`]'s `CreateAccount`.
+
+use crate::{guard::create_account, instruction::BenchInstruction};
+#[cfg(feature = "token-gate")]
+use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+    rent::Rent,
+};
+
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const MINT_LEN: usize = 82;
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+/// Custom program error returned when the holder does not hold enough of the
+/// mint.
+pub const GATE_DENIED: u32 = 3;
+
+/// Offset of the `AccountState` byte in a token account; 1 is initialized,
+/// 2 frozen.
+const STATE_OFFSET: usize = 108;
+const INITIALIZED: u8 = 1;
+
+/// Checks `data`, owned by `owner`, is an unfrozen token account of `mint`
+/// held by `holder` with at least `min_amount` tokens. Returns the amount.
+pub fn check_holding(
+    owner: &Pubkey,
+    data: &[u8],
+    mint: &Pubkey,
+    holder: &Pubkey,
+    min_amount: u64,
+) -> Result<u64, ProgramError> {
+    if *owner != TOKEN_PROGRAM_ID {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if data.len() != TOKEN_ACCOUNT_LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let amount = u64::from_le_bytes(data[64..72].try_into().unwrap());
+    if data[..32] != mint.to_bytes()
+        || data[32..64] != holder.to_bytes()
+        || data[STATE_OFFSET] != INITIALIZED
+        || amount < min_amount
+    {
+        return Err(ProgramError::Custom(GATE_DENIED));
+    }
+    Ok(amount)
+}
+
+#[cfg(feature = "token-gate")]
+pub fn process_token_gated(
+    accounts: &[AccountInfo],
+    mint: &Pubkey,
+    min_amount: u64,
+) -> Result<u64, ProgramError> {
+    let account_info_iter = &mut accounts.iter();
+    let holder = next_account_info(account_info_iter)?;
+    let token_account = next_account_info(account_info_iter)?;
+
+    if !holder.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let data = token_account.try_borrow_data()?;
+    check_holding(token_account.owner, &data, mint, holder.key, min_amount)
+}
+
+/// Builds the gated instruction for `holder`, who holds `token_account`.
+pub fn token_gated(
+    program_id: &Pubkey,
+    holder: &Pubkey,
+    token_account: &Pubkey,
+    mint: &Pubkey,
+    min_amount: u64,
+) -> Instruction {
+    BenchInstruction::TokenGated {
+        mint: *mint,
+        min_amount,
+    }
+    .to_instruction(
+        *program_id,
+        vec![
+            AccountMeta::new_readonly(*holder, true),
+            AccountMeta::new_readonly(*token_account, false),
+        ],
+    )
+}
+
+/// Creates `mint` with `payer` as its authority and no decimals, and
+/// `token_account` holding `amount` of it for `payer`. Both new accounts sign,
+/// next to the payer.
+pub fn create_gate_accounts(
+    payer: &Pubkey,
+    mint: &Pubkey,
+    token_account: &Pubkey,
+    amount: u64,
+) -> Vec<Instruction> {
+    let rent = Rent::default();
+    // InitializeMint2: decimals, mint authority, no freeze authority
+    let mut initialize_mint = vec![20, 0];
+    initialize_mint.extend_from_slice(payer.as_ref());
+    initialize_mint.push(0);
+    // InitializeAccount3: owner
+    let mut initialize_account = vec![18];
+    initialize_account.extend_from_slice(payer.as_ref());
+    // MintTo: amount
+    let mut mint_to = vec![7];
+    mint_to.extend_from_slice(&amount.to_le_bytes());
+    vec![
+        create_account(
+            payer,
+            mint,
+            rent.minimum_balance(MINT_LEN),
+            MINT_LEN as u64,
+            &TOKEN_PROGRAM_ID,
+        ),
+        Instruction::new_with_bytes(
+            TOKEN_PROGRAM_ID,
+            &initialize_mint,
+            vec![AccountMeta::new(*mint, false)],
+        ),
+        create_account(
+            payer,
+            token_account,
+            rent.minimum_balance(TOKEN_ACCOUNT_LEN),
+            TOKEN_ACCOUNT_LEN as u64,
+            &TOKEN_PROGRAM_ID,
+        ),
+        Instruction::new_with_bytes(
+            TOKEN_PROGRAM_ID,
+            &initialize_account,
+            vec![
+                AccountMeta::new(*token_account, false),
+                AccountMeta::new_readonly(*mint, false),
+            ],
+        ),
+        Instruction::new_with_bytes(
+            TOKEN_PROGRAM_ID,
+            &mint_to,
+            vec![
+                AccountMeta::new(*mint, false),
+                AccountMeta::new(*token_account, false),
+                AccountMeta::new_readonly(*payer, true),
+            ],
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_holding() {
+        let (mint, holder) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![0; TOKEN_ACCOUNT_LEN];
+        data[..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(holder.as_ref());
+        data[64..72].copy_from_slice(&5u64.to_le_bytes());
+        data[STATE_OFFSET] = INITIALIZED;
+
+        let check = |data: &[u8], min_amount| {
+            check_holding(&TOKEN_PROGRAM_ID, data, &mint, &holder, min_amount)
+        };
+        assert_eq!(check(&data, 5), Ok(5));
+        assert_eq!(check(&data, 6), Err(ProgramError::Custom(GATE_DENIED)));
+        assert_eq!(
+            check(&data[..MINT_LEN], 1),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            check_holding(&Pubkey::new_unique(), &data, &mint, &holder, 1),
+            Err(ProgramError::IllegalOwner)
+        );
+        assert_eq!(
+            check_holding(&TOKEN_PROGRAM_ID, &data, &mint, &mint, 1),
+            Err(ProgramError::Custom(GATE_DENIED))
+        );
+        data[STATE_OFFSET] = 2;
+        assert_eq!(check(&data, 1), Err(ProgramError::Custom(GATE_DENIED)));
+
+        let setup = create_gate_accounts(&holder, &mint, &Pubkey::new_unique(), 5);
+        assert_eq!(setup.len(), 5);
+        assert_eq!(setup[4].data, [7, 5, 0, 0, 0, 0, 0, 0, 0]);
+    }
+}
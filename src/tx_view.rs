@@ -0,0 +1,225 @@
+//! Plain accessors over a confirmed transaction fetched through RPC, hiding the
+//! `OptionSerializer` wrapping of the status meta.
+
+use crate::{attribution::LogAttribution, compat, sample::BalanceDelta, sdk::TransactionError};
+use solana_program::pubkey::Pubkey;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiTransactionStatusMeta,
+};
+
+#[derive(Debug)]
+pub struct ConfirmedTxView {
+    inner: EncodedConfirmedTransactionWithStatusMeta,
+}
+
+impl ConfirmedTxView {
+    pub fn new(inner: EncodedConfirmedTransactionWithStatusMeta) -> Self {
+        Self { inner }
+    }
+
+    pub fn slot(&self) -> u64 {
+        self.inner.slot
+    }
+
+    pub fn block_time(&self) -> Option<i64> {
+        self.inner.block_time
+    }
+
+    /// The raw status meta, absent when the node returned the transaction without it.
+    pub fn meta(&self) -> Option<&UiTransactionStatusMeta> {
+        self.inner.transaction.meta.as_ref()
+    }
+
+    /// CU from the status meta, falling back to [`Self::log_cu_consumed`] for
+    /// older RPC versions whose meta omits it.
+    pub fn cu_consumed(&self) -> Option<u64> {
+        self.meta_cu_consumed().or_else(|| self.log_cu_consumed())
+    }
+
+    /// CU as reported in the status meta.
+    pub fn meta_cu_consumed(&self) -> Option<u64> {
+        compat::compute_units_consumed(self.meta()?)
+    }
+
+    /// CU summed from the runtime's `consumed X of Y compute units` lines of the
+    /// top-level instructions. `None` when any top-level invocation, such as a
+    /// builtin, logged no such line, as its cost is then unknown.
+    pub fn log_cu_consumed(&self) -> Option<u64> {
+        LogAttribution::parse(self.logs()).metered_total()
+    }
+
+    pub fn fee(&self) -> Option<u64> {
+        self.meta().map(|meta| meta.fee)
+    }
+
+    /// Program log lines; empty when the meta or its logs are missing.
+    pub fn logs(&self) -> impl Iterator<Item = &str> {
+        self.meta()
+            .map(compat::log_messages)
+            .unwrap_or_default()
+            .iter()
+            .map(String::as_str)
+    }
+
+    /// Number of inner instructions under each of `instruction_count`
+    /// top-level instructions, or `None` when the meta does not record them.
+    pub fn inner_instruction_counts(&self, instruction_count: usize) -> Option<Vec<usize>> {
+        let mut counts = vec![0; instruction_count];
+        for inner in compat::inner_instructions(self.meta()?)? {
+            *counts.get_mut(usize::from(inner.index))? = inner.instructions.len();
+        }
+        Some(counts)
+    }
+
+    pub fn err(&self) -> Option<TransactionError> {
+        compat::transaction_error(self.meta()?)
+    }
+
+    /// `(pre, post)` lamport balances per account key, in message order.
+    pub fn pre_post_balances(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        let (pre, post) = match self.meta() {
+            Some(meta) => (meta.pre_balances.as_slice(), meta.post_balances.as_slice()),
+            None => (&[][..], &[][..]),
+        };
+        pre.iter().copied().zip(post.iter().copied())
+    }
+
+    /// Account keys in the order the meta reports balances: the message's own
+    /// keys, then writable and readonly addresses loaded from lookup tables.
+    /// `None` when the transaction cannot be decoded.
+    pub fn account_keys(&self) -> Option<Vec<Pubkey>> {
+        let transaction = self.inner.transaction.transaction.decode()?;
+        let mut keys = transaction.message.static_account_keys().to_vec();
+        if let Some(loaded) = self.meta().and_then(compat::loaded_addresses) {
+            for address in loaded.writable.iter().chain(&loaded.readonly) {
+                keys.push(address.parse().ok()?);
+            }
+        }
+        Some(keys)
+    }
+
+    /// Lamport and token balances that differ before and after the
+    /// transaction. A token account missing on one side held nothing there.
+    pub fn balance_deltas(&self) -> Vec<BalanceDelta> {
+        let keys = self.account_keys();
+        let key = |index: usize| keys.as_ref().and_then(|keys| keys.get(index).copied());
+        let mut deltas: Vec<BalanceDelta> = self
+            .pre_post_balances()
+            .enumerate()
+            .filter(|(_, (pre, post))| pre != post)
+            .map(|(index, (pre, post))| BalanceDelta {
+                account_index: index,
+                account: key(index),
+                mint: None,
+                pre,
+                post,
+            })
+            .collect();
+
+        let Some(meta) = self.meta() else {
+            return deltas;
+        };
+        let (pre_tokens, post_tokens) = compat::token_balances(meta);
+        let mut tokens: Vec<BalanceDelta> = Vec::new();
+        for (balances, is_post) in [(pre_tokens, false), (post_tokens, true)] {
+            for balance in balances {
+                let (Ok(mint), Ok(amount)) = (
+                    balance.mint.parse::<Pubkey>(),
+                    balance.ui_token_amount.amount.parse::<u64>(),
+                ) else {
+                    continue;
+                };
+                let index = usize::from(balance.account_index);
+                let delta = match tokens
+                    .iter_mut()
+                    .find(|delta| delta.account_index == index && delta.mint == Some(mint))
+                {
+                    Some(delta) => delta,
+                    None => {
+                        tokens.push(BalanceDelta {
+                            account_index: index,
+                            account: key(index),
+                            mint: Some(mint),
+                            pre: 0,
+                            post: 0,
+                        });
+                        tokens.last_mut().unwrap()
+                    }
+                };
+                if is_post {
+                    delta.post = amount;
+                } else {
+                    delta.pre = amount;
+                }
+            }
+        }
+        deltas.extend(tokens.into_iter().filter(|delta| delta.pre != delta.post));
+        deltas
+    }
+
+    pub fn into_inner(self) -> EncodedConfirmedTransactionWithStatusMeta {
+        self.inner
+    }
+}
+
+impl From<EncodedConfirmedTransactionWithStatusMeta> for ConfirmedTxView {
+    fn from(inner: EncodedConfirmedTransactionWithStatusMeta) -> Self {
+        Self::new(inner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::RunFixture;
+    use solana_transaction_status::option_serializer::OptionSerializer;
+
+    #[test]
+    fn test_cu_falls_back_to_logs() {
+        let fixture = RunFixture {
+            base_compute_units: 1_500,
+            jitter: 0,
+            outlier_rate: 0.0,
+            failure_rate: 0.0,
+            ..RunFixture::default()
+        };
+        let tx = fixture.transactions().remove(0);
+        assert_eq!(tx.meta_cu_consumed(), Some(1_500));
+        assert_eq!(tx.log_cu_consumed(), Some(1_500));
+
+        let mut inner = tx.into_inner();
+        let meta = inner.transaction.meta.as_mut().unwrap();
+        meta.compute_units_consumed = OptionSerializer::Skip;
+        let tx = ConfirmedTxView::new(inner);
+        assert_eq!(tx.meta_cu_consumed(), None);
+        assert_eq!(tx.cu_consumed(), Some(1_500));
+
+        let mut inner = tx.into_inner();
+        let meta = inner.transaction.meta.as_mut().unwrap();
+        meta.log_messages = OptionSerializer::None;
+        assert_eq!(ConfirmedTxView::new(inner).cu_consumed(), None);
+    }
+
+    #[test]
+    fn test_lamport_deltas() {
+        let fixture = RunFixture {
+            fee: 5_000,
+            failure_rate: 0.0,
+            ..RunFixture::default()
+        };
+        let tx = fixture.transactions().remove(0);
+        // The fixture's transaction has no encoded message to resolve keys from
+        assert_eq!(tx.account_keys(), None);
+        assert_eq!(
+            tx.balance_deltas(),
+            [BalanceDelta {
+                account_index: 0,
+                account: None,
+                mint: None,
+                pre: 1_000_000_000,
+                post: 999_995_000,
+            }]
+        );
+        assert_eq!(tx.balance_deltas()[0].change(), -5_000);
+    }
+}
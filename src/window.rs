@@ -0,0 +1,89 @@
+//! Timing sends against slot boundaries so a measured batch lands in as few
+//! blocks as possible, keeping cross-block variance out of CU comparisons.
+
+use crate::sdk::{CommitmentConfig, RpcClient};
+use std::{
+    error::Error,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Groups sends into batches of `batch_size`, each started right after a new
+/// slot begins so the whole batch fits in that slot's window.
+#[derive(Debug, Clone)]
+pub struct SlotWindow {
+    batch_size: usize,
+    sent_in_window: usize,
+    slot: Option<u64>,
+}
+
+impl SlotWindow {
+    pub fn new(batch_size: usize) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+            sent_in_window: 0,
+            slot: None,
+        }
+    }
+
+    /// Call before every send. Blocks until the next slot boundary when the
+    /// current window is full, and returns the slot the send is aimed at.
+    pub fn wait_turn(&mut self, rpc_client: &RpcClient) -> Result<u64, Box<dyn Error>> {
+        if self.advance() {
+            self.slot = Some(wait_for_next_slot(rpc_client, Duration::from_secs(5))?);
+        }
+        Ok(self.slot.unwrap_or_default())
+    }
+
+    /// Counts one send; true when it has to open a new window first.
+    fn advance(&mut self) -> bool {
+        let new_window = self.slot.is_none() || self.sent_in_window >= self.batch_size;
+        if new_window {
+            self.sent_in_window = 0;
+        }
+        self.sent_in_window += 1;
+        new_window
+    }
+}
+
+/// Polls the processed slot until it moves past the current one.
+pub fn wait_for_next_slot(
+    rpc_client: &RpcClient,
+    timeout: Duration,
+) -> Result<u64, Box<dyn Error>> {
+    let processed = CommitmentConfig::processed();
+    let start_slot = rpc_client.get_slot_with_commitment(processed)?;
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        let slot = rpc_client.get_slot_with_commitment(processed)?;
+        if slot > start_slot {
+            return Ok(slot);
+        }
+        sleep(POLL_INTERVAL);
+    }
+    Err(format!(
+        "slot did not advance past {} within {:?}",
+        start_slot, timeout
+    )
+    .into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_windows_open_every_batch_size_sends() {
+        let mut window = SlotWindow::new(3);
+        let opened: Vec<bool> = (0..7)
+            .map(|_| {
+                let new_window = window.advance();
+                window.slot = Some(1);
+                new_window
+            })
+            .collect();
+        assert_eq!(opened, [true, false, false, true, false, false, true]);
+    }
+}
@@ -0,0 +1,898 @@
+//! Workloads decide which transactions a run sends. Third-party crates implement
+//! [`Workload`] and register a factory under a name, and the harness drives the
+//! setup, send and teardown phases.
+
+use crate::{
+    burn_compute,
+    cpi::{cpi_chain, MAX_CPI_DEPTH},
+    curve::{curve, CurveOp},
+    guard::{guarded_increment, GUARD_CAPACITY},
+    instruction::{set_compute_unit_limit, AllocPattern, BenchInstruction, MemOp},
+    math::{math, MathOp},
+    pda::{derive_pda, verify_pda_pair, PdaMode, MAX_PDA_SEEDS},
+    sdk::{Keypair, RpcClient, Signer, Transaction},
+    synthetic::SyntheticData,
+    token_gate::{create_gate_accounts, token_gated},
+};
+use solana_program::{
+    hash,
+    instruction::{AccountMeta, Instruction},
+    keccak,
+    pubkey::Pubkey,
+};
+use std::{collections::BTreeMap, error::Error};
+use tracing::warn;
+
+pub struct WorkloadContext<'a> {
+    pub rpc_client: &'a RpcClient,
+    pub payer: &'a Keypair,
+    pub program_id: Pubkey,
+}
+
+/// A workload between setup and teardown. Dropping it tears the workload down,
+/// so a run failing partway still cleans up; [`ActiveWorkload::teardown`]
+/// does so explicitly and returns the error a drop can only log.
+pub struct ActiveWorkload<'a> {
+    workload: Box<dyn Workload>,
+    ctx: &'a WorkloadContext<'a>,
+    torn_down: bool,
+}
+
+impl<'a> ActiveWorkload<'a> {
+    /// Sets `workload` up, tearing it down again if setup fails.
+    pub fn setup(
+        workload: Box<dyn Workload>,
+        ctx: &'a WorkloadContext<'a>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut active = Self {
+            workload,
+            ctx,
+            torn_down: false,
+        };
+        active.workload.setup(ctx)?;
+        Ok(active)
+    }
+
+    pub fn next_transaction(&mut self) -> Option<WorkloadTransaction> {
+        self.workload.next_transaction(self.ctx)
+    }
+
+    pub fn teardown(mut self) -> Result<(), Box<dyn Error>> {
+        self.torn_down = true;
+        self.workload.teardown(self.ctx)
+    }
+}
+
+impl Drop for ActiveWorkload<'_> {
+    fn drop(&mut self) {
+        if !self.torn_down {
+            if let Err(e) = self.workload.teardown(self.ctx) {
+                warn!("Workload teardown failed: {}", e);
+            }
+        }
+    }
+}
+
+/// Instructions for one transaction, signed and sent by the harness with the payer.
+pub struct WorkloadTransaction {
+    pub instructions: Vec<Instruction>,
+    /// Log fragment the landed transaction must contain, if the workload checks one.
+    pub expected_log: Option<String>,
+}
+
+pub trait Workload {
+    fn setup(&mut self, _ctx: &WorkloadContext) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// Returns the next transaction to send, or `None` once the workload is done.
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction>;
+
+    fn teardown(&mut self, _ctx: &WorkloadContext) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// Sends `count` [`BenchInstruction::LogCounter`] transactions with an
+/// increasing counter.
+pub struct CounterWorkload {
+    next: u64,
+    count: u64,
+}
+
+impl CounterWorkload {
+    pub fn new(count: u64) -> Self {
+        Self { next: 0, count }
+    }
+}
+
+impl Workload for CounterWorkload {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        if self.next >= self.count {
+            return None;
+        }
+        let counter = self.next;
+        self.next += 1;
+
+        Some(WorkloadTransaction {
+            instructions: vec![
+                BenchInstruction::LogCounter { counter }.to_instruction(ctx.program_id, vec![])
+            ],
+            expected_log: Some(format!("Count: {}", counter)),
+        })
+    }
+}
+
+/// Like [`CounterWorkload`], but every increment goes through the on-chain
+/// replay guard so a nonce can only land once.
+pub struct GuardedCounterWorkload {
+    next: u64,
+    count: u64,
+}
+
+impl GuardedCounterWorkload {
+    pub fn new(count: u64) -> Self {
+        Self { next: 0, count }
+    }
+}
+
+impl Workload for GuardedCounterWorkload {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        if self.next >= self.count.min(GUARD_CAPACITY) {
+            return None;
+        }
+        let nonce = self.next;
+        self.next += 1;
+
+        Some(WorkloadTransaction {
+            instructions: vec![guarded_increment(
+                &ctx.program_id,
+                &ctx.payer.pubkey(),
+                nonce,
+            )],
+            expected_log: Some(format!("Count: {}", nonce)),
+        })
+    }
+}
+
+/// Calls the benchmark program and every program in `co_programs` within each
+/// transaction, so per-program CU from the logs can be checked against the
+/// transaction total. All programs must be deployments of this crate.
+pub struct MultiProgramWorkload {
+    co_programs: Vec<Pubkey>,
+    next: u64,
+    count: u64,
+}
+
+impl MultiProgramWorkload {
+    pub fn new(co_programs: Vec<Pubkey>, count: u64) -> Self {
+        Self {
+            co_programs,
+            next: 0,
+            count,
+        }
+    }
+}
+
+impl Workload for MultiProgramWorkload {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        if self.next >= self.count {
+            return None;
+        }
+        let counter = self.next;
+        self.next += 1;
+
+        let instruction = BenchInstruction::LogCounter { counter };
+        Some(WorkloadTransaction {
+            instructions: std::iter::once(&ctx.program_id)
+                .chain(&self.co_programs)
+                .map(|program_id| instruction.to_instruction(*program_id, vec![]))
+                .collect(),
+            expected_log: Some(format!("Count: {}", counter)),
+        })
+    }
+}
+
+/// Sends one [`BenchInstruction::BurnCompute`] per iteration count, under the
+/// maximum compute unit limit, to sweep CU across the per-transaction range.
+pub struct BurnComputeWorkload {
+    iterations: Vec<u32>,
+    next: usize,
+}
+
+impl BurnComputeWorkload {
+    pub fn new(iterations: Vec<u32>) -> Self {
+        Self {
+            iterations,
+            next: 0,
+        }
+    }
+
+    /// Powers of two from 1 to 2^17 iterations.
+    pub fn sweep() -> Self {
+        Self::new((0..18).map(|exponent| 1 << exponent).collect())
+    }
+}
+
+impl Workload for BurnComputeWorkload {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        let iterations = *self.iterations.get(self.next)?;
+        self.next += 1;
+
+        Some(WorkloadTransaction {
+            instructions: vec![
+                set_compute_unit_limit(1_400_000),
+                BenchInstruction::BurnCompute { iterations }.to_instruction(ctx.program_id, vec![]),
+            ],
+            expected_log: Some(format!(
+                "Iterations: {} Checksum: {}",
+                iterations,
+                burn_compute(iterations)
+            )),
+        })
+    }
+}
+
+/// Sends one [`BenchInstruction::Math`] per iteration count, under the
+/// maximum compute unit limit, to price one arithmetic kernel per step. The
+/// 256-bit kernels fail on programs built without the `bigint` feature, and
+/// their checksum is only checked when the harness has it too.
+pub struct MathWorkload {
+    op: MathOp,
+    iterations: Vec<u32>,
+    next: usize,
+}
+
+impl MathWorkload {
+    pub fn new(op: MathOp, iterations: Vec<u32>) -> Self {
+        Self {
+            op,
+            iterations,
+            next: 0,
+        }
+    }
+
+    /// Powers of two from 1 to 256 steps.
+    pub fn sweep(op: MathOp) -> Self {
+        Self::new(op, (0..=8).map(|exponent| 1 << exponent).collect())
+    }
+}
+
+impl Workload for MathWorkload {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        let iterations = *self.iterations.get(self.next)?;
+        self.next += 1;
+
+        let op = self.op;
+        let expected_log = match math(op, iterations) {
+            Ok(checksum) => format!("Math: {} x{} checksum {}", op, iterations, checksum),
+            Err(_) => format!("Math: {} x{} checksum", op, iterations),
+        };
+        Some(WorkloadTransaction {
+            instructions: vec![
+                set_compute_unit_limit(1_400_000),
+                BenchInstruction::Math { op, iterations }.to_instruction(ctx.program_id, vec![]),
+            ],
+            expected_log: Some(expected_log),
+        })
+    }
+}
+
+/// Sends one [`BenchInstruction::Curve`] per operation count, under the
+/// maximum compute unit limit, to price a curve25519 syscall by the number of
+/// additions or multiplication points. Programs built without the
+/// `curve25519` feature reject the instruction, and the checksum is only
+/// checked when the harness has the feature too.
+pub struct CurveWorkload {
+    op: CurveOp,
+    counts: Vec<u16>,
+    next: usize,
+}
+
+impl CurveWorkload {
+    pub fn new(op: CurveOp, counts: Vec<u16>) -> Self {
+        Self {
+            op,
+            counts,
+            next: 0,
+        }
+    }
+
+    /// Powers of two from 1 to 256 operations or points.
+    pub fn sweep(op: CurveOp) -> Self {
+        Self::new(op, (0..=8).map(|exponent| 1 << exponent).collect())
+    }
+}
+
+impl Workload for CurveWorkload {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        let count = *self.counts.get(self.next)?;
+        self.next += 1;
+
+        let op = self.op;
+        let expected_log = match curve(op, count) {
+            Ok(checksum) => format!("Curve: {} x{} checksum {}", op, count, checksum),
+            Err(_) => format!("Curve: {} x{} checksum", op, count),
+        };
+        Some(WorkloadTransaction {
+            instructions: vec![
+                set_compute_unit_limit(1_400_000),
+                BenchInstruction::Curve { op, count }.to_instruction(ctx.program_id, vec![]),
+            ],
+            expected_log: Some(expected_log),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashSyscall {
+    Sha256,
+    Keccak256,
+}
+
+/// Sends one hash instruction per data length, to price a hashing syscall by
+/// input size. Inputs are random bytes from seed 0 unless set otherwise.
+pub struct HashWorkload {
+    syscall: HashSyscall,
+    lengths: Vec<usize>,
+    data: SyntheticData,
+    next: usize,
+}
+
+impl HashWorkload {
+    pub fn new(syscall: HashSyscall, lengths: Vec<usize>) -> Self {
+        Self {
+            syscall,
+            lengths,
+            data: SyntheticData::new(0, 8),
+            next: 0,
+        }
+    }
+
+    /// Hashes input from `data` instead.
+    pub fn with_data(mut self, data: SyntheticData) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Empty input, then powers of two up to 1 KiB, which still fits in a
+    /// transaction.
+    pub fn sweep(syscall: HashSyscall) -> Self {
+        let lengths = (0..=10).map(|exponent| 1 << exponent);
+        Self::new(syscall, std::iter::once(0).chain(lengths).collect())
+    }
+}
+
+impl Workload for HashWorkload {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        let length = *self.lengths.get(self.next)?;
+        let data = self.data.bytes(self.next as u64, length);
+        self.next += 1;
+
+        let (expected_log, instruction) = match self.syscall {
+            HashSyscall::Sha256 => (
+                format!("Sha256: {}", hash::hash(&data)),
+                BenchInstruction::Sha256 { data },
+            ),
+            HashSyscall::Keccak256 => (
+                format!("Keccak256: {}", keccak::hash(&data)),
+                BenchInstruction::Keccak256 { data },
+            ),
+        };
+        Some(WorkloadTransaction {
+            instructions: vec![instruction.to_instruction(ctx.program_id, vec![])],
+            expected_log: Some(expected_log),
+        })
+    }
+}
+
+/// Sends one [`BenchInstruction::MemOps`] per buffer length, with a fixed
+/// repetition count, to chart CU against bytes moved for one memory syscall.
+pub struct MemOpsWorkload {
+    op: MemOp,
+    lengths: Vec<u32>,
+    reps: u16,
+    next: usize,
+}
+
+impl MemOpsWorkload {
+    pub fn new(op: MemOp, lengths: Vec<u32>, reps: u16) -> Self {
+        Self {
+            op,
+            lengths,
+            reps,
+            next: 0,
+        }
+    }
+
+    /// Powers of two from 1 byte to 8 KiB, 16 repetitions each.
+    pub fn sweep(op: MemOp) -> Self {
+        Self::new(op, (0..=13).map(|exponent| 1 << exponent).collect(), 16)
+    }
+}
+
+impl Workload for MemOpsWorkload {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        let len = *self.lengths.get(self.next)?;
+        self.next += 1;
+
+        let (op, reps) = (self.op, self.reps);
+        Some(WorkloadTransaction {
+            instructions: vec![
+                BenchInstruction::MemOps { op, len, reps }.to_instruction(ctx.program_id, vec![])
+            ],
+            expected_log: Some(format!("MemOps: {} {} bytes x{}", op, len, reps)),
+        })
+    }
+}
+
+/// Sends one [`BenchInstruction::HeapAlloc`] per allocation size, with a
+/// fixed allocation count.
+pub struct HeapAllocWorkload {
+    pattern: AllocPattern,
+    count: u16,
+    sizes: Vec<u32>,
+    next: usize,
+}
+
+impl HeapAllocWorkload {
+    pub fn new(pattern: AllocPattern, count: u16, sizes: Vec<u32>) -> Self {
+        Self {
+            pattern,
+            count,
+            sizes,
+            next: 0,
+        }
+    }
+
+    /// Eight allocations of 8 bytes to 1 KiB, which fits the default 32 KiB
+    /// heap even when the default allocator leaks every `Vec` regrowth.
+    pub fn sweep(pattern: AllocPattern) -> Self {
+        Self::new(pattern, 8, (3..=10).map(|exponent| 1 << exponent).collect())
+    }
+}
+
+impl Workload for HeapAllocWorkload {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        let size = *self.sizes.get(self.next)?;
+        self.next += 1;
+
+        let (pattern, count) = (self.pattern, self.count);
+        let instruction = BenchInstruction::HeapAlloc {
+            pattern,
+            count,
+            size,
+        };
+        Some(WorkloadTransaction {
+            instructions: vec![instruction.to_instruction(ctx.program_id, vec![])],
+            expected_log: Some(format!(
+                "Allocated: {} x {} bytes ({})",
+                count, size, pattern
+            )),
+        })
+    }
+}
+
+/// Sends [`BenchInstruction::Cpi`] chains of every depth up to the limit,
+/// `rounds` times over, into `callee` or the benchmark program itself.
+pub struct CpiWorkload {
+    callee: Option<Pubkey>,
+    depths: Vec<u8>,
+    next: usize,
+}
+
+impl CpiWorkload {
+    pub fn new(callee: Option<Pubkey>, rounds: usize) -> Self {
+        Self {
+            callee,
+            depths: (0..rounds).flat_map(|_| 0..=MAX_CPI_DEPTH).collect(),
+            next: 0,
+        }
+    }
+}
+
+impl Workload for CpiWorkload {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        let depth = *self.depths.get(self.next)?;
+        self.next += 1;
+
+        let callee = self.callee.unwrap_or(ctx.program_id);
+        Some(WorkloadTransaction {
+            instructions: vec![cpi_chain(&ctx.program_id, &callee, depth)],
+            // The innermost level logs depth 0 whichever program it runs in
+            expected_log: Some("Cpi depth: 0".to_string()),
+        })
+    }
+}
+
+/// Sends one [`BenchInstruction::DerivePda`] per `(seeds, seed_len)` point,
+/// each deriving `reps` times under the maximum compute unit limit, to chart
+/// PDA derivation cost against seed count and length.
+pub struct PdaWorkload {
+    mode: PdaMode,
+    points: Vec<(u8, u8)>,
+    reps: u16,
+    next: usize,
+}
+
+impl PdaWorkload {
+    pub fn new(mode: PdaMode, points: Vec<(u8, u8)>, reps: u16) -> Self {
+        Self {
+            mode,
+            points,
+            reps,
+            next: 0,
+        }
+    }
+
+    /// [`pda_sweep_points`], derived 8 times.
+    pub fn sweep(mode: PdaMode) -> Self {
+        Self::new(mode, pda_sweep_points(), 8)
+    }
+}
+
+impl Workload for PdaWorkload {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        let (seeds, seed_len) = *self.points.get(self.next)?;
+        self.next += 1;
+
+        let (instruction, expected_log) =
+            derive_pda(&ctx.program_id, self.mode, seeds, seed_len, self.reps).ok()?;
+        Some(WorkloadTransaction {
+            instructions: vec![set_compute_unit_limit(1_400_000), instruction],
+            expected_log: Some(expected_log),
+        })
+    }
+}
+
+/// 1, 2, 4, 8 and the maximum number of seeds, each 1, 8 and 32 bytes long.
+pub fn pda_sweep_points() -> Vec<(u8, u8)> {
+    [1, 2, 4, 8, MAX_PDA_SEEDS]
+        .into_iter()
+        .flat_map(|seeds| [1, 8, 32].map(|seed_len| (seeds, seed_len)))
+        .collect()
+}
+
+/// Verifies a PDA by re-deriving it and with a cached bump in each
+/// transaction, see [`verify_pda_pair`], for every [`pda_sweep_points`]
+/// configuration, `rounds` times over. [`crate::pda::BumpCacheSavings`]
+/// turns the pairs into CU saved by caching the bump.
+pub struct BumpCacheWorkload {
+    points: Vec<(u8, u8)>,
+    next: usize,
+}
+
+impl BumpCacheWorkload {
+    pub fn new(rounds: usize) -> Self {
+        Self {
+            points: (0..rounds).flat_map(|_| pda_sweep_points()).collect(),
+            next: 0,
+        }
+    }
+}
+
+impl Workload for BumpCacheWorkload {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        let (seeds, seed_len) = *self.points.get(self.next)?;
+        self.next += 1;
+
+        let mut instructions = vec![set_compute_unit_limit(1_400_000)];
+        instructions.extend(verify_pda_pair(&ctx.program_id, seeds, seed_len).ok()?);
+        Some(WorkloadTransaction {
+            instructions,
+            expected_log: Some(format!("Pda verified: cached {}x{} bytes", seeds, seed_len)),
+        })
+    }
+}
+
+/// Most accounts a [`BenchInstruction::TouchAccounts`] transaction can carry
+/// within the 1232-byte packet limit, next to the payer and the program.
+pub const MAX_TOUCHED_ACCOUNTS: usize = 32;
+
+/// Sends [`BenchInstruction::TouchAccounts`] with every account count from 0
+/// to [`MAX_TOUCHED_ACCOUNTS`], `rounds` times over, to chart entrypoint
+/// deserialization cost against account count. The accounts are distinct,
+/// read-only and empty; no compute budget instruction is added, as it would
+/// not fit.
+pub struct TouchAccountsWorkload {
+    accounts: Vec<Pubkey>,
+    counts: Vec<usize>,
+    next: usize,
+}
+
+impl TouchAccountsWorkload {
+    pub fn new(rounds: usize) -> Self {
+        Self {
+            accounts: (0..MAX_TOUCHED_ACCOUNTS)
+                .map(|_| Pubkey::new_unique())
+                .collect(),
+            counts: (0..rounds).flat_map(|_| 0..=MAX_TOUCHED_ACCOUNTS).collect(),
+            next: 0,
+        }
+    }
+}
+
+impl Workload for TouchAccountsWorkload {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        let count = *self.counts.get(self.next)?;
+        self.next += 1;
+
+        let accounts = self.accounts[..count]
+            .iter()
+            .map(|address| AccountMeta::new_readonly(*address, false))
+            .collect();
+        Some(WorkloadTransaction {
+            instructions: vec![
+                BenchInstruction::TouchAccounts.to_instruction(ctx.program_id, accounts)
+            ],
+            expected_log: Some(format!("Touched: {} accounts", count)),
+        })
+    }
+}
+
+/// Message signed by [`RECOVER_SIGNATURE`], hashed with keccak as Ethereum does.
+const RECOVER_MESSAGE: &[u8] = b"increase-cu-program-size";
+/// Signature of [`RECOVER_MESSAGE`] by the secret key `[7; 32]`.
+const RECOVER_SIGNATURE: [u8; 64] = [
+    183, 232, 210, 128, 56, 187, 192, 243, 161, 241, 201, 197, 80, 213, 32, 243, 48, 197, 62, 205,
+    245, 109, 173, 232, 201, 212, 208, 103, 176, 135, 33, 115, 28, 13, 34, 226, 237, 5, 247, 135,
+    247, 71, 59, 7, 212, 210, 49, 162, 91, 108, 214, 164, 57, 157, 84, 171, 125, 138, 2, 219, 71,
+    121, 92, 219,
+];
+const RECOVER_ID: u8 = 1;
+
+/// Sends `count` [`BenchInstruction::Secp256k1Recover`] transactions over the
+/// same valid signature; the syscall has a fixed price.
+pub struct Secp256k1RecoverWorkload {
+    next: u64,
+    count: u64,
+}
+
+impl Secp256k1RecoverWorkload {
+    pub fn new(count: u64) -> Self {
+        Self { next: 0, count }
+    }
+}
+
+impl Workload for Secp256k1RecoverWorkload {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        if self.next >= self.count {
+            return None;
+        }
+        self.next += 1;
+
+        let instruction = BenchInstruction::Secp256k1Recover {
+            hash: keccak::hash(RECOVER_MESSAGE).to_bytes(),
+            recovery_id: RECOVER_ID,
+            signature: RECOVER_SIGNATURE,
+        };
+        Some(WorkloadTransaction {
+            instructions: vec![instruction.to_instruction(ctx.program_id, vec![])],
+            expected_log: Some("Recovered: true".to_string()),
+        })
+    }
+}
+
+/// Sends `count` [`BenchInstruction::TokenGated`] transactions from the payer,
+/// which setup makes the holder of one token of a fresh mint. The program
+/// must be built with the `token-gate` feature. The mint and token account
+/// are left behind with their rent.
+pub struct TokenGateWorkload {
+    mint: Keypair,
+    token_account: Keypair,
+    next: u64,
+    count: u64,
+}
+
+impl TokenGateWorkload {
+    pub fn new(count: u64) -> Self {
+        Self {
+            mint: Keypair::new(),
+            token_account: Keypair::new(),
+            next: 0,
+            count,
+        }
+    }
+}
+
+impl Workload for TokenGateWorkload {
+    fn setup(&mut self, ctx: &WorkloadContext) -> Result<(), Box<dyn Error>> {
+        let instructions = create_gate_accounts(
+            &ctx.payer.pubkey(),
+            &self.mint.pubkey(),
+            &self.token_account.pubkey(),
+            1,
+        );
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&ctx.payer.pubkey()),
+            &[ctx.payer, &self.mint, &self.token_account],
+            ctx.rpc_client.get_latest_blockhash()?,
+        );
+        ctx.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(())
+    }
+
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        if self.next >= self.count {
+            return None;
+        }
+        self.next += 1;
+
+        Some(WorkloadTransaction {
+            instructions: vec![token_gated(
+                &ctx.program_id,
+                &ctx.payer.pubkey(),
+                &self.token_account.pubkey(),
+                &self.mint.pubkey(),
+                1,
+            )],
+            expected_log: Some("Gate passed: 1 tokens".to_string()),
+        })
+    }
+}
+
+type WorkloadFactory = Box<dyn Fn() -> Box<dyn Workload> + Send + Sync>;
+
+/// Named workload factories available to the harness.
+#[derive(Default)]
+pub struct WorkloadRegistry {
+    factories: BTreeMap<String, WorkloadFactory>,
+}
+
+impl WorkloadRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry preloaded with the workloads shipped in this crate.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register("burn-compute", BurnComputeWorkload::sweep);
+        registry.register("counter", || CounterWorkload::new(100));
+        registry.register("cpi", || CpiWorkload::new(None, 10));
+        for op in CurveOp::ALL {
+            registry.register(format!("curve-{}", op), move || CurveWorkload::sweep(op));
+        }
+        registry.register("guarded-counter", || GuardedCounterWorkload::new(100));
+        registry.register("keccak256", || HashWorkload::sweep(HashSyscall::Keccak256));
+        for pattern in AllocPattern::ALL {
+            registry.register(format!("heap-{}", pattern), move || {
+                HeapAllocWorkload::sweep(pattern)
+            });
+        }
+        for op in MathOp::ALL {
+            registry.register(format!("math-{}", op), move || MathWorkload::sweep(op));
+        }
+        for op in MemOp::ALL {
+            registry.register(op.to_string(), move || MemOpsWorkload::sweep(op));
+        }
+        registry.register("pda-bump-cache", || BumpCacheWorkload::new(3));
+        for mode in PdaMode::ALL {
+            registry.register(format!("pda-{}", mode), move || PdaWorkload::sweep(mode));
+        }
+        registry.register("secp256k1-recover", || Secp256k1RecoverWorkload::new(10));
+        registry.register("sha256", || HashWorkload::sweep(HashSyscall::Sha256));
+        registry.register("token-gate", || TokenGateWorkload::new(10));
+        registry.register("touch-accounts", || TouchAccountsWorkload::new(3));
+        registry
+    }
+
+    pub fn register<W, F>(&mut self, name: impl Into<String>, factory: F) -> &mut Self
+    where
+        W: Workload + 'static,
+        F: Fn() -> W + Send + Sync + 'static,
+    {
+        self.factories
+            .insert(name.into(), Box::new(move || Box::new(factory())));
+        self
+    }
+
+    pub fn create(&self, name: &str) -> Option<Box<dyn Workload>> {
+        self.factories.get(name).map(|factory| factory())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{sdk::Transaction, test_utils::OfflineContext};
+    use std::{cell::Cell, rc::Rc};
+
+    #[test]
+    fn test_counter_workload() {
+        let context = OfflineContext::default();
+        let ctx = context.ctx();
+
+        let mut workload = WorkloadRegistry::with_builtins().create("counter").unwrap();
+        let transactions: Vec<_> = std::iter::from_fn(|| workload.next_transaction(&ctx)).collect();
+
+        assert_eq!(transactions.len(), 100);
+        assert_eq!(
+            BenchInstruction::unpack(&transactions[7].instructions[0].data),
+            Ok(BenchInstruction::LogCounter { counter: 7 })
+        );
+        assert_eq!(transactions[7].expected_log.as_deref(), Some("Count: 7"));
+    }
+
+    #[test]
+    fn test_active_workload_tears_down_on_every_path() {
+        struct Teardowns {
+            fail_setup: bool,
+            count: Rc<Cell<u32>>,
+        }
+        impl Workload for Teardowns {
+            fn setup(&mut self, _ctx: &WorkloadContext) -> Result<(), Box<dyn Error>> {
+                if self.fail_setup {
+                    return Err("setup failed".into());
+                }
+                Ok(())
+            }
+            fn next_transaction(&mut self, _ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+                None
+            }
+            fn teardown(&mut self, _ctx: &WorkloadContext) -> Result<(), Box<dyn Error>> {
+                self.count.set(self.count.get() + 1);
+                Ok(())
+            }
+        }
+
+        let context = OfflineContext::default();
+        let ctx = context.ctx();
+        let count = Rc::new(Cell::new(0));
+        let workload = |fail_setup| {
+            Box::new(Teardowns {
+                fail_setup,
+                count: count.clone(),
+            })
+        };
+        ActiveWorkload::setup(workload(false), &ctx)
+            .unwrap()
+            .teardown()
+            .unwrap();
+        assert_eq!(count.get(), 1);
+        drop(ActiveWorkload::setup(workload(false), &ctx).unwrap());
+        assert_eq!(count.get(), 2);
+        assert!(ActiveWorkload::setup(workload(true), &ctx).is_err());
+        assert_eq!(count.get(), 3);
+    }
+
+    #[test]
+    fn test_syscall_workloads() {
+        let context = OfflineContext::default();
+        let ctx = context.ctx();
+
+        let mut sha256 = HashWorkload::sweep(HashSyscall::Sha256);
+        let transactions: Vec<_> = std::iter::from_fn(|| sha256.next_transaction(&ctx)).collect();
+        assert_eq!(transactions.len(), 12);
+        assert_eq!(transactions[11].instructions[0].data.len(), 1 + 1024);
+
+        // The signature must be valid, or the workload measures the error path
+        let recovered = solana_program::secp256k1_recover::secp256k1_recover(
+            &keccak::hash(RECOVER_MESSAGE).to_bytes(),
+            RECOVER_ID,
+            &RECOVER_SIGNATURE,
+        );
+        assert!(recovered.is_ok());
+    }
+
+    #[test]
+    fn test_touch_accounts_fit_in_a_packet() {
+        let context = OfflineContext::default();
+        let ctx = context.ctx();
+
+        let mut workload = TouchAccountsWorkload::new(1);
+        let transactions: Vec<_> = std::iter::from_fn(|| workload.next_transaction(&ctx)).collect();
+        assert_eq!(transactions.len(), MAX_TOUCHED_ACCOUNTS + 1);
+
+        let largest = Transaction::new_with_payer(
+            &transactions[MAX_TOUCHED_ACCOUNTS].instructions,
+            Some(&ctx.payer.pubkey()),
+        );
+        assert!(bincode::serialized_size(&largest).unwrap() <= 1232);
+    }
+}
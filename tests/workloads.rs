@@ -0,0 +1,85 @@
+use increase_cu_program_size::{
+    cpi::MAX_CPI_DEPTH,
+    instruction::BenchInstruction,
+    test_utils::OfflineContext,
+    workload::{Workload, WorkloadContext, WorkloadRegistry, WorkloadTransaction},
+};
+use solana_sdk::hash::hash;
+
+/// A workload defined outside the crate, as a downstream user would write one.
+struct RepeatNoop {
+    remaining: usize,
+}
+
+impl Workload for RepeatNoop {
+    fn next_transaction(&mut self, ctx: &WorkloadContext) -> Option<WorkloadTransaction> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        Some(WorkloadTransaction {
+            instructions: vec![BenchInstruction::Noop.to_instruction(ctx.program_id, vec![])],
+            expected_log: None,
+        })
+    }
+}
+
+fn drain(workload: &mut dyn Workload, ctx: &WorkloadContext) -> Vec<WorkloadTransaction> {
+    workload.setup(ctx).unwrap();
+    let transactions = std::iter::from_fn(|| workload.next_transaction(ctx)).collect();
+    workload.teardown(ctx).unwrap();
+    transactions
+}
+
+#[test]
+fn test_builtin_and_external_workloads() {
+    let context = OfflineContext::default();
+    let ctx = context.ctx();
+
+    let mut registry = WorkloadRegistry::with_builtins();
+    registry.register("repeat-noop", || RepeatNoop { remaining: 3 });
+    let names: Vec<&str> = registry.names().collect();
+    for name in ["burn-compute", "counter", "guarded-counter", "repeat-noop"] {
+        assert!(names.contains(&name), "{} is not registered", name);
+    }
+
+    let counter = drain(&mut *registry.create("counter").unwrap(), &ctx);
+    assert_eq!(counter.len(), 100);
+    assert!(counter
+        .iter()
+        .all(|tx| tx.instructions[0].program_id == ctx.program_id));
+
+    let noop = drain(&mut *registry.create("repeat-noop").unwrap(), &ctx);
+    assert_eq!(noop.len(), 3);
+    assert!(noop
+        .iter()
+        .all(|tx| tx.instructions[0].data == [BenchInstruction::NOOP]));
+}
+
+/// The workloads the examples send, drained without a validator.
+#[test]
+fn test_example_workloads() {
+    let context = OfflineContext::default();
+    let ctx = context.ctx();
+    let registry = WorkloadRegistry::with_builtins();
+    let expected_logs = |transactions: &[WorkloadTransaction]| -> Vec<String> {
+        transactions
+            .iter()
+            .map(|tx| tx.expected_log.clone().unwrap())
+            .collect()
+    };
+
+    let pda = drain(&mut *registry.create("pda-find").unwrap(), &ctx);
+    assert_eq!(pda.len(), 15);
+    assert!(expected_logs(&pda)
+        .iter()
+        .all(|log| log.starts_with("Pda: find ")));
+
+    let cpi = drain(&mut *registry.create("cpi").unwrap(), &ctx);
+    assert_eq!(cpi.len(), 10 * (usize::from(MAX_CPI_DEPTH) + 1));
+    assert!(cpi
+        .iter()
+        .all(|tx| tx.instructions[0].program_id == ctx.program_id));
+    assert!(expected_logs(&cpi).iter().all(|log| log == "Cpi depth: 0"));
+
+    let sha256 = drain(&mut *registry.create("sha256").unwrap(), &ctx);
+    assert_eq!(sha256.len(), 12);
+    assert_eq!(expected_logs(&sha256)[0], format!("Sha256: {}", hash(&[])));
+}